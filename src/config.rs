@@ -0,0 +1,351 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// User settings read from `$XDG_CONFIG_HOME/gitu/config.toml`
+/// (or `~/.config/gitu/config.toml`), optionally overridden per repository
+/// by a `.gitu.toml` in the repo root or in `.git/`.
+///
+/// Only a small subset of TOML is understood: `# comments`, `[section]`
+/// headers and `key = value` lines whose value is a quoted string, a bare
+/// word, a number or a boolean. Keys inside a section are stored as
+/// `section.key`.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    /// Reads the user config file. A missing file is not an error.
+    pub fn load() -> Result<Config> {
+        let Some(path) = config_path() else {
+            return Ok(Config::default());
+        };
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Config::parse(&text).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    /// Reads the repository's `.gitu.toml` files, the one in the repo root
+    /// first and then the one in `.git/`, which wins for keys set in both.
+    /// Returns None when neither exists.
+    pub fn load_repo() -> Result<Option<Config>> {
+        let mut paths = Vec::new();
+        if let Ok(root) = crate::git::get_repo_root() {
+            paths.push(PathBuf::from(root).join(REPO_CONFIG_FILE));
+        }
+        if let Ok(git_dir) = crate::git::get_git_dir() {
+            paths.push(PathBuf::from(git_dir).join(REPO_CONFIG_FILE));
+        }
+
+        let mut repo_config: Option<Config> = None;
+        for path in paths.iter().filter(|path| path.exists()) {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let config = Config::parse(&text).with_context(|| format!("Invalid config {}", path.display()))?;
+            match repo_config {
+                Some(ref mut base) => base.merge(config),
+                None => repo_config = Some(config),
+            }
+        }
+        Ok(repo_config)
+    }
+
+    /// Overrides this config with every key set in `other`
+    pub fn merge(&mut self, other: Config) {
+        self.values.extend(other.values);
+    }
+
+    /// Overrides this user config with a repository's, except for personal
+    /// choices gitu saves for the user (the theme `T` picks), which a
+    /// shared repo config only sets when the user hasn't
+    pub fn merge_repo(&mut self, mut repo: Config) {
+        for key in USER_SAVED_KEYS {
+            if self.values.contains_key(*key) {
+                repo.values.remove(*key);
+            }
+        }
+        self.merge(repo);
+    }
+
+    pub fn parse(text: &str) -> Result<Config> {
+        let mut values = HashMap::new();
+        let mut section = String::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                anyhow::bail!("line {}: expected `key = value`", number + 1);
+            };
+            let key = key.trim();
+            if key.is_empty() {
+                anyhow::bail!("line {}: missing key", number + 1);
+            }
+
+            let value = parse_value(value.trim())
+                .ok_or_else(|| anyhow::anyhow!("line {}: unterminated string", number + 1))?;
+            let key = if section.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", section, key)
+            };
+            values.insert(key, value);
+        }
+
+        Ok(Config { values })
+    }
+
+    /// Raw value of a key (`section.key` for keys inside a section)
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|v| v.as_str())
+    }
+
+    /// Keys and values inside `[name]`, with the section prefix removed,
+    /// sorted by key
+    pub fn section(&self, name: &str) -> Vec<(&str, &str)> {
+        let prefix = format!("{}.", name);
+        let mut entries: Vec<(&str, &str)> = self
+            .values
+            .iter()
+            .filter_map(|(key, value)| Some((key.strip_prefix(&prefix)?, value.as_str())))
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    /// Boolean value of a key; anything but `true`/`false` counts as unset
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key)? {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Draw in the terminal's alternate screen (the default). When false the
+    /// UI stays in the main buffer and its last frame remains in scrollback.
+    pub fn alternate_screen(&self) -> bool {
+        self.get_bool("alternate_screen").unwrap_or(true)
+    }
+
+    /// Panel to show on startup: status, log, stash, branches or tags
+    pub fn default_panel(&self) -> Option<&str> {
+        self.get("default_panel")
+    }
+
+    /// Syntax highlighting theme for diffs
+    pub fn theme(&self) -> Option<&str> {
+        self.get("theme")
+    }
+
+    /// Sets a top-level key in memory and in the user config file, keeping
+    /// the rest of the file (comments, sections) as it is
+    pub fn save_user_value(&mut self, key: &str, value: &str) -> Result<()> {
+        self.values.insert(key.to_string(), value.to_string());
+
+        let path = config_path().ok_or_else(|| anyhow::anyhow!("No config directory (HOME is not set)"))?;
+        let text = if path.exists() {
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?
+        } else {
+            String::new()
+        };
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        std::fs::write(&path, set_top_level_value(&text, key, value))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Keep the "@@" header and the +/- markers when copying a diff hunk;
+    /// when false the hunk is copied as the code reads after the change
+    pub fn copy_hunk_markers(&self) -> bool {
+        self.get_bool("copy_hunk_markers").unwrap_or(true)
+    }
+
+    /// Diff lines longer than this many characters are cut short until
+    /// expanded; 0 never cuts them
+    pub fn max_diff_line_length(&self) -> usize {
+        self.get("max_diff_line_length")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MAX_DIFF_LINE_LENGTH)
+    }
+
+    /// Columns a tab expands to in diffs and viewed files; None defers to
+    /// git's `core.tabwidth`
+    pub fn tab_width(&self) -> Option<usize> {
+        self.get("tab_width").and_then(|value| value.parse().ok())
+    }
+
+    /// Write commit and amend messages in the external editor (`$EDITOR`,
+    /// as git picks it) instead of the built-in input
+    pub fn commit_in_editor(&self) -> bool {
+        self.get_bool("commit_in_editor").unwrap_or(false)
+    }
+
+    /// Template used when copying a commit summary from the Log panel
+    pub fn commit_copy_format(&self) -> &str {
+        self.get("commit_copy_format").unwrap_or("{hash} {subject}")
+    }
+}
+
+const DEFAULT_MAX_DIFF_LINE_LENGTH: usize = 500;
+
+/// Keys gitu writes to the user config, where the user's value beats the
+/// repository's
+const USER_SAVED_KEYS: &[&str] = &["theme"];
+
+/// Name of the per-repository config file
+const REPO_CONFIG_FILE: &str = ".gitu.toml";
+
+fn config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("gitu").join("config.toml"))
+}
+
+/// Replaces `key = ...` before the first section, or adds it there
+fn set_top_level_value(text: &str, key: &str, value: &str) -> String {
+    let entry = format!("{} = \"{}\"", key, value.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+
+    let first_section = lines
+        .iter()
+        .position(|line| strip_comment(line).trim().starts_with('['))
+        .unwrap_or(lines.len());
+    let existing = lines[..first_section].iter().position(|line| {
+        strip_comment(line)
+            .split_once('=')
+            .is_some_and(|(k, _)| k.trim() == key)
+    });
+
+    match existing {
+        Some(i) => lines[i] = entry,
+        None => {
+            // Keep a blank line between the new key and a following section
+            let at = lines[..first_section]
+                .iter()
+                .rposition(|line| !line.trim().is_empty())
+                .map_or(0, |i| i + 1);
+            lines.insert(at, entry);
+        }
+    }
+
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+/// Drops a trailing `# comment`, ignoring `#` inside quoted strings
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, ch) in line.char_indices() {
+        match ch {
+            '\\' if in_string && !escaped => {
+                escaped = true;
+                continue;
+            }
+            '"' if !escaped => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+/// Unquotes a string value; other values are kept as written.
+/// Returns None for an unterminated string.
+fn parse_value(value: &str) -> Option<String> {
+    match value.strip_prefix('"') {
+        Some(rest) => rest
+            .strip_suffix('"')
+            .map(|inner| inner.replace("\\\"", "\"").replace("\\\\", "\\")),
+        None => Some(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(
+            "# gitu settings\ndefault_panel = \"log\"  # start in history\n\n[diff]\ncontext = 5\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.default_panel(), Some("log"));
+        assert_eq!(config.get("diff.context"), Some("5"));
+        assert_eq!(config.get("context"), None);
+        assert!(config.alternate_screen());
+        assert_eq!(config.max_diff_line_length(), DEFAULT_MAX_DIFF_LINE_LENGTH);
+        assert_eq!(Config::parse("max_diff_line_length = 0").unwrap().max_diff_line_length(), 0);
+        assert_eq!(config.tab_width(), None);
+        assert_eq!(Config::parse("tab_width = 8").unwrap().tab_width(), Some(8));
+    }
+
+    #[test]
+    fn test_parse_config_bool() {
+        let config = Config::parse("alternate_screen = false\nother = maybe\n").unwrap();
+        assert!(!config.alternate_screen());
+        assert_eq!(config.get_bool("other"), None);
+    }
+
+    #[test]
+    fn test_merge_overrides_keys() {
+        let mut config = Config::parse("default_panel = \"log\"\n[keys]\nstash.apply = \"A\"\n").unwrap();
+        config.merge(Config::parse("default_panel = \"branches\"\n").unwrap());
+
+        assert_eq!(config.default_panel(), Some("branches"));
+        assert_eq!(config.get("keys.stash.apply"), Some("A"));
+    }
+
+    #[test]
+    fn test_merge_repo_keeps_saved_theme() {
+        let repo = "theme = \"Solarized (dark)\"\ndefault_panel = \"log\"\n";
+        let mut config = Config::parse("theme = \"base16-eighties.dark\"\n").unwrap();
+        config.merge_repo(Config::parse(repo).unwrap());
+        assert_eq!(config.theme(), Some("base16-eighties.dark"));
+        assert_eq!(config.default_panel(), Some("log"));
+
+        let mut config = Config::default();
+        config.merge_repo(Config::parse(repo).unwrap());
+        assert_eq!(config.theme(), Some("Solarized (dark)"));
+    }
+
+    #[test]
+    fn test_set_top_level_value() {
+        let text = "# mine\ntheme = \"old\"\n\n[keys]\ntheme = \"x\"\n";
+        let updated = set_top_level_value(text, "theme", "Solarized (dark)");
+        assert_eq!(updated, "# mine\ntheme = \"Solarized (dark)\"\n\n[keys]\ntheme = \"x\"\n");
+
+        let added = set_top_level_value("default_panel = \"log\"\n\n[keys]\n", "theme", "t");
+        assert_eq!(added, "default_panel = \"log\"\ntheme = \"t\"\n\n[keys]\n");
+        assert_eq!(Config::parse(&added).unwrap().theme(), Some("t"));
+        assert_eq!(set_top_level_value("", "theme", "t"), "theme = \"t\"\n");
+    }
+
+    #[test]
+    fn test_parse_config_errors() {
+        assert!(Config::parse("default_panel").is_err());
+        assert!(Config::parse("default_panel = \"log").is_err());
+    }
+}
@@ -1,5 +1,8 @@
 use anyhow::{Context, Result};
-use std::process::Command;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum FileStatus {
@@ -8,6 +11,9 @@ pub enum FileStatus {
     Deleted,
     Renamed,
     Untracked,
+    /// Unmerged after a merge, rebase, cherry-pick or revert stopped on
+    /// conflicts; listed once, as unstaged
+    Conflicted,
 }
 
 #[derive(Debug, Clone)]
@@ -15,6 +21,7 @@ pub struct StatusFile {
     pub path: String,
     pub status: FileStatus,
     pub staged: bool,
+    pub is_submodule: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -31,6 +38,40 @@ pub struct Branch {
     pub is_remote: bool,
     pub commit_hash: String,
     pub commit_message: String,
+    /// Remote branch a local branch tracks, e.g. "origin/main"
+    pub upstream: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tag {
+    pub name: String,
+    /// Abbreviated hash of the tagged commit
+    pub target: String,
+    /// Annotation subject, or the commit subject for lightweight tags
+    pub subject: String,
+    pub signature: TagSignature,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagSignature {
+    Unsigned,
+    /// Carries a signature that hasn't been checked yet (checking runs gpg)
+    Unchecked,
+    /// Verified by `git verify-tag`; holds the signer
+    Good(String),
+    /// gpg found the signature doesn't match the tag (BADSIG)
+    Bad,
+    /// The signature couldn't be checked, e.g. the public key is missing
+    /// or gpg isn't installed; holds the reason
+    Unverifiable(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Worktree {
+    pub path: String,
+    pub head: String,
+    pub branch: Option<String>,
+    pub is_bare: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -45,14 +86,28 @@ pub enum Decoration {
 pub struct Commit {
     pub graph: String,
     pub hash: String,
+    /// Abbreviated parent hashes; more than one means a merge commit
+    pub parents: Vec<String>,
     pub message: String,
     pub decorations: Vec<Decoration>,
+    /// Committer date as YYYY-MM-DD; empty if the log output had none
+    pub date: String,
+}
+
+impl Commit {
+    pub fn is_merge(&self) -> bool {
+        self.parents.len() > 1
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct FileDiff {
     pub filename: String,
     pub diff_content: String,
+    /// Why there are no hunks to show (mode change, binary file, pure rename)
+    pub note: Option<String>,
+    /// (old, new) file mode when the commit changed it, e.g. the executable bit
+    pub mode_change: Option<(String, String)>,
 }
 
 #[derive(Debug, Clone)]
@@ -60,16 +115,103 @@ pub struct CommitDiff {
     pub files: Vec<FileDiff>,
 }
 
+/// Name of the placeholder file listed when a diff has no changes
+const NO_CHANGES: &str = "(no changes)";
+
+impl CommitDiff {
+    /// Whether the diff changes nothing (only the placeholder is listed)
+    pub fn is_empty(&self) -> bool {
+        self.files.iter().all(|file| file.filename == NO_CHANGES)
+    }
+}
+
+/// Verdict for a commit during `git bisect`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BisectMark {
+    Good,
+    Bad,
+    Skip,
+}
+
 /// Search filter type for git log
 #[derive(Debug, Clone, PartialEq)]
 pub enum SearchFilter {
     Message(String),
     Author(String),
+    /// Commits whose diff adds or removes `pattern` (`-S`), or with
+    /// `regex` set, whose diff has a line matching it (`-G`)
+    Pickaxe { pattern: String, regex: bool },
+}
+
+impl SearchFilter {
+    /// Reads a search query: `@name` searches authors, `S:text` and
+    /// `G:regex` search diffs, anything else commit messages.
+    /// An empty query is no filter; an empty pickaxe pattern is an error.
+    pub fn parse(query: &str) -> Result<Option<SearchFilter>> {
+        if query.is_empty() {
+            return Ok(None);
+        }
+        if let Some(author) = query.strip_prefix('@') {
+            return Ok(Some(SearchFilter::Author(author.to_string())));
+        }
+        for (prefix, regex) in [("S:", false), ("G:", true)] {
+            if let Some(pattern) = query.strip_prefix(prefix) {
+                if pattern.is_empty() {
+                    anyhow::bail!("pickaxe pattern is empty");
+                }
+                return Ok(Some(SearchFilter::Pickaxe {
+                    pattern: pattern.to_string(),
+                    regex,
+                }));
+            }
+        }
+        Ok(Some(SearchFilter::Message(query.to_string())))
+    }
+}
+
+/// Which commits the log lists by their number of parents
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MergeFilter {
+    #[default]
+    All,
+    /// Only merge commits (`--merges`)
+    MergesOnly,
+    /// Everything but merge commits (`--no-merges`)
+    NoMerges,
+}
+
+impl MergeFilter {
+    /// All -> merges only -> no merges -> all
+    pub fn next(self) -> MergeFilter {
+        match self {
+            MergeFilter::All => MergeFilter::MergesOnly,
+            MergeFilter::MergesOnly => MergeFilter::NoMerges,
+            MergeFilter::NoMerges => MergeFilter::All,
+        }
+    }
+
+    fn arg(self) -> Option<&'static str> {
+        match self {
+            MergeFilter::All => None,
+            MergeFilter::MergesOnly => Some("--merges"),
+            MergeFilter::NoMerges => Some("--no-merges"),
+        }
+    }
 }
 
-/// Parses git log output and returns a vector of commits
-pub fn get_commits(filter: Option<&SearchFilter>) -> Result<Vec<Commit>> {
-    let mut args = vec!["log", "--graph", "--oneline", "--all", "--decorate"];
+/// Parses git log output and returns a vector of commits.
+/// With `first_parent` only HEAD's mainline is listed (`--first-parent`),
+/// hiding the side commits of merged branches.
+pub fn get_commits(filter: Option<&SearchFilter>, first_parent: bool, merges: MergeFilter) -> Result<Vec<Commit>> {
+    // Like --oneline, plus the parent hashes and the commit day; tabs also
+    // set the decorations apart from a subject that starts with "("
+    let mut args = vec!["log", "--graph", "--decorate", "--format=%h %p%x09%cs%x09%d%x09%s"];
+    if first_parent {
+        args.push("--first-parent");
+    } else {
+        args.push("--all");
+    }
+    args.extend(merges.arg());
 
     // Add search filter arguments
     let filter_arg;
@@ -82,6 +224,10 @@ pub fn get_commits(filter: Option<&SearchFilter>) -> Result<Vec<Commit>> {
             filter_arg = format!("--author={}", query);
             args.push(&filter_arg);
         }
+        Some(SearchFilter::Pickaxe { pattern, regex }) => {
+            filter_arg = format!("{}{}", if *regex { "-G" } else { "-S" }, pattern);
+            args.push(&filter_arg);
+        }
         None => {}
     }
 
@@ -133,35 +279,78 @@ fn parse_log_output(output: &str) -> Vec<Commit> {
 
         let rest = &line[hash_start..];
 
-        // Parse hash and message
-        // Format is: hash message
-        let parts: Vec<&str> = rest.splitn(2, ' ').collect();
+        // "hash parent... <TAB> (decorations) message" carries the parents;
+        // plain --oneline output is just "hash (decorations) message"
+        let (hash, parents, rest_after_hash) = match rest.split_once('\t') {
+            Some((ids, text)) => {
+                let mut ids = ids.split_whitespace();
+                let Some(hash) = ids.next() else {
+                    continue;
+                };
+                (hash.to_string(), ids.map(|id| id.to_string()).collect(), text)
+            }
+            None => {
+                // Parse hash and message
+                // Format is: hash message
+                let parts: Vec<&str> = rest.splitn(2, ' ').collect();
 
-        if parts.is_empty() {
-            continue;
-        }
+                if parts.is_empty() {
+                    continue;
+                }
+
+                let rest_after_hash = if parts.len() > 1 { parts[1] } else { "" };
+                (parts[0].to_string(), Vec::new(), rest_after_hash)
+            }
+        };
 
-        let hash = parts[0].to_string();
+        // An optional "YYYY-MM-DD<TAB>" comes before the decorations
+        let (date, rest_after_hash) = match rest_after_hash.split_once('\t') {
+            Some((date, text)) if is_iso_date(date) => (date.to_string(), text),
+            _ => (String::new(), rest_after_hash),
+        };
 
         // Parse decorations and message
         // Format can be: "hash (decorations) message" or "hash message"
-        let rest_after_hash = if parts.len() > 1 { parts[1] } else { "" };
         let (decorations, message) = parse_decorations_and_message(rest_after_hash);
 
         commits.push(Commit {
             graph,
             hash,
+            parents,
             message,
             decorations,
+            date,
         });
     }
 
     commits
 }
 
+/// Whether text is a YYYY-MM-DD date
+fn is_iso_date(text: &str) -> bool {
+    text.len() == 10
+        && text
+            .char_indices()
+            .all(|(i, c)| if i == 4 || i == 7 { c == '-' } else { c.is_ascii_digit() })
+}
+
 /// Parses decorations and message from the text after the hash
-/// Input format: "(HEAD -> main, tag: v1.0) Commit message" or just "Commit message"
+/// Input format: " (HEAD -> main, tag: v1.0)<TAB>Commit message" or
+/// "<TAB>Commit message" as our log format prints them, or the --oneline
+/// "(HEAD -> main, tag: v1.0) Commit message" or just "Commit message"
 fn parse_decorations_and_message(text: &str) -> (Vec<Decoration>, String) {
+    // The tab leaves no doubt where the decorations end, even when the
+    // subject itself starts with '('
+    if let Some((decoration_str, message)) = text.split_once('\t') {
+        let decoration_str = decoration_str.trim();
+        if decoration_str.is_empty() {
+            return (Vec::new(), message.trim().to_string());
+        }
+        if let Some(inner) = decoration_str.strip_prefix('(').and_then(|d| d.strip_suffix(')')) {
+            return (parse_decoration_string(inner), message.trim().to_string());
+        }
+    }
+
     let text = text.trim();
 
     // Check if there are decorations (starts with '(')
@@ -169,19 +358,37 @@ fn parse_decorations_and_message(text: &str) -> (Vec<Decoration>, String) {
         return (Vec::new(), text.to_string());
     }
 
-    // Find the closing parenthesis
-    if let Some(close_paren) = text.find(')') {
-        let decoration_str = &text[1..close_paren]; // Skip opening '('
-        let message = text[close_paren + 1..].trim().to_string();
-
-        let decorations = parse_decoration_string(decoration_str);
-        (decorations, message)
-    } else {
-        // Malformed, treat as message
-        (Vec::new(), text.to_string())
+    // Find the closing parenthesis; a subject such as "(bugfix, part 2) ..."
+    // is only taken for decorations when every entry could be a ref
+    match text.find(')') {
+        Some(close_paren) if is_decoration_string(&text[1..close_paren]) => {
+            let decorations = parse_decoration_string(&text[1..close_paren]);
+            let message = text[close_paren + 1..].trim().to_string();
+            (decorations, message)
+        }
+        // Malformed or not decorations, treat as message
+        _ => (Vec::new(), text.to_string()),
     }
 }
 
+/// Whether a parenthesized list reads like git's decorations: "HEAD",
+/// "HEAD -> branch", "tag: name" or ref names, which never contain spaces
+fn is_decoration_string(decoration_str: &str) -> bool {
+    let is_ref_name = |name: &str| {
+        !name.is_empty()
+            && !name.contains("..")
+            && !name.ends_with('.')
+            && !name.chars().any(|c| c.is_whitespace() || c.is_control() || "~^:?*[\\".contains(c))
+    };
+    decoration_str.split(", ").all(|part| {
+        let name = part
+            .strip_prefix("HEAD -> ")
+            .or_else(|| part.strip_prefix("tag: "))
+            .unwrap_or(part);
+        is_ref_name(name)
+    })
+}
+
 /// Parses a decoration string like "HEAD -> main, origin/main, tag: v1.0"
 fn parse_decoration_string(decoration_str: &str) -> Vec<Decoration> {
     let mut decorations = Vec::new();
@@ -229,10 +436,57 @@ fn parse_decoration_string(decoration_str: &str) -> Vec<Decoration> {
     decorations
 }
 
+/// Kind of a conflict marker line left in a working-tree file by a merge
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictMarker {
+    /// `<<<<<<<`, where our side starts
+    Ours,
+    /// `|||||||`, the merge base (diff3 conflict style)
+    Base,
+    /// `=======`, where their side starts
+    Separator,
+    /// `>>>>>>>`, the end of the conflict
+    Theirs,
+}
+
+impl ConflictMarker {
+    pub fn parse(line: &str) -> Option<ConflictMarker> {
+        let marker = match line.get(..7)? {
+            "<<<<<<<" => ConflictMarker::Ours,
+            "|||||||" => ConflictMarker::Base,
+            "=======" => ConflictMarker::Separator,
+            ">>>>>>>" => ConflictMarker::Theirs,
+            _ => return None,
+        };
+        // Exactly seven characters, then the end of the line or a label
+        match line[7..].chars().next() {
+            None | Some(' ') => Some(marker),
+            _ => None,
+        }
+    }
+}
+
+/// Line numbers (0-based) where each conflict of a working-tree file
+/// starts: a `<<<<<<<` later closed by `>>>>>>>`
+pub fn find_conflicts(content: &str) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut open = None;
+    for (i, line) in content.lines().enumerate() {
+        match ConflictMarker::parse(line) {
+            Some(ConflictMarker::Ours) => open = Some(i),
+            Some(ConflictMarker::Theirs) => starts.extend(open.take()),
+            _ => {}
+        }
+    }
+    starts
+}
+
 /// Gets the full diff for a specific commit, split by files
-pub fn get_commit_diff(hash: &str) -> Result<CommitDiff> {
+pub fn get_commit_diff(hash: &str, ignore_space: bool) -> Result<CommitDiff> {
     let output = Command::new("git")
-        .args(["show", "--color=never", hash])
+        .args(["show", "--color=never"])
+        .args(ignore_space.then_some("--ignore-all-space"))
+        .arg(hash)
         .output()
         .context("Failed to execute git show command")?;
 
@@ -245,50 +499,138 @@ pub fn get_commit_diff(hash: &str) -> Result<CommitDiff> {
     Ok(parse_commit_diff(&full_output))
 }
 
+/// Notes attached to a commit (`git notes`), or None when it has none
+pub fn get_commit_notes(hash: &str) -> Result<Option<String>> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%N", hash])
+        .output()
+        .context("Failed to execute git log")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to read notes: {}", error);
+    }
+
+    let notes = String::from_utf8_lossy(&output.stdout).trim_end().to_string();
+    Ok((!notes.is_empty()).then_some(notes))
+}
+
+/// Get the changes of a commit relative to one of its parents. Used for merge
+/// commits, where plain `git show` prints a combined diff.
+pub fn get_commit_diff_against(hash: &str, parent: &str, ignore_space: bool) -> Result<CommitDiff> {
+    let output = Command::new("git")
+        .args(["diff", "--color=never", "--no-ext-diff"])
+        .args(ignore_space.then_some("--ignore-all-space"))
+        .args([parent, hash])
+        .output()
+        .context("Failed to execute git diff command")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Git diff failed: {}", error);
+    }
+
+    let full_output = String::from_utf8_lossy(&output.stdout).to_string();
+    Ok(parse_commit_diff(&full_output))
+}
+
+/// Changes in the working tree (staged or not) since a commit, i.e. `git diff <hash>`
+pub fn get_worktree_diff(hash: &str, ignore_space: bool) -> Result<CommitDiff> {
+    let output = Command::new("git")
+        .args(["diff", "--color=never", "--no-ext-diff"])
+        .args(ignore_space.then_some("--ignore-all-space"))
+        .arg(hash)
+        .output()
+        .context("Failed to execute git diff command")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Git diff failed: {}", error);
+    }
+
+    let full_output = String::from_utf8_lossy(&output.stdout).to_string();
+    Ok(parse_commit_diff(&full_output))
+}
+
 /// Parses the git show output into structured file diffs
 fn parse_commit_diff(output: &str) -> CommitDiff {
     let lines: Vec<&str> = output.lines().collect();
     let mut files = Vec::new();
     let mut current_file: Option<FileDiff> = None;
+    let mut has_prefix = true;
+    let mut in_header = false;
     let mut found_first_diff = false;
 
     for line in lines {
+        // Merges shown as a combined diff start each file with "diff --cc <file>"
+        let combined = line
+            .strip_prefix("diff --cc ")
+            .or_else(|| line.strip_prefix("diff --combined "));
+
         // Skip everything before the first "diff --git" line
-        if !found_first_diff && !line.starts_with("diff --git") {
+        if !found_first_diff && !line.starts_with("diff --git") && combined.is_none() {
             continue;
         }
 
         // Detect start of a new file diff
-        if line.starts_with("diff --git") {
+        if let Some(header) = line.strip_prefix("diff --git ").or(combined) {
             found_first_diff = true;
 
             // Save the previous file diff if exists
             if let Some(file_diff) = current_file.take() {
-                files.push(file_diff);
+                files.push(with_note(file_diff));
             }
 
-            // Extract filename from "diff --git a/file b/file"
-            let filename = line
-                .split_whitespace()
-                .nth(2)
-                .unwrap_or("unknown")
-                .trim_start_matches("a/")
-                .to_string();
+            // Best-effort filename from "diff --git a/file b/file"; refined
+            // below by the more reliable "---"/"+++" lines when present
+            let (filename, prefixed) = match combined {
+                Some(name) => (name.to_string(), true),
+                None => parse_diff_git_header(header),
+            };
+            has_prefix = prefixed;
+            in_header = true;
 
             current_file = Some(FileDiff {
                 filename,
                 diff_content: String::new(),
+                note: None,
+                mode_change: None,
             });
+            continue;
         }
 
-        // Add line to current file (skip the "diff --git" line itself and metadata)
         if let Some(ref mut file_diff) = current_file {
+            if line.starts_with("@@") {
+                in_header = false;
+            }
+
+            if in_header {
+                // Mode changes are shown above the diff instead of as raw lines
+                if let Some(mode) = line.strip_prefix("old mode ") {
+                    let new = file_diff.mode_change.take().map(|(_, new)| new).unwrap_or_default();
+                    file_diff.mode_change = Some((mode.trim().to_string(), new));
+                    continue;
+                }
+                if let Some(mode) = line.strip_prefix("new mode ") {
+                    let old = file_diff.mode_change.take().map(|(old, _)| old).unwrap_or_default();
+                    file_diff.mode_change = Some((old, mode.trim().to_string()));
+                    continue;
+                }
+
+                // Take the filename from the "+++" line (or "---" for deletions)
+                if let Some(path) = line
+                    .strip_prefix("+++ ")
+                    .or_else(|| line.strip_prefix("--- "))
+                {
+                    if let Some(name) = parse_diff_path(path, has_prefix) {
+                        file_diff.filename = name;
+                    }
+                    continue;
+                }
+            }
+
             // Skip diff metadata lines, only keep the actual diff content
-            if !line.starts_with("diff --git")
-                && !line.starts_with("index ")
-                && !line.starts_with("--- ")
-                && !line.starts_with("+++ ")
-            {
+            if !(in_header && line.starts_with("index ")) {
                 file_diff.diff_content.push_str(line);
                 file_diff.diff_content.push('\n');
             }
@@ -297,27 +639,136 @@ fn parse_commit_diff(output: &str) -> CommitDiff {
 
     // Don't forget the last file
     if let Some(file_diff) = current_file {
-        files.push(file_diff);
+        files.push(with_note(file_diff));
     }
 
     // If no files were found, show a message
     if files.is_empty() {
         files.push(FileDiff {
-            filename: "(no changes)".to_string(),
+            filename: NO_CHANGES.to_string(),
             diff_content: "No file changes in this commit.\n".to_string(),
+            note: None,
+            mode_change: None,
         });
     }
 
     CommitDiff { files }
 }
 
+/// Explains a file diff that has no hunks, using the mode change and the
+/// extended header lines (`Binary files ... differ`, `rename from`, ...)
+fn with_note(mut file_diff: FileDiff) -> FileDiff {
+    let lines: Vec<&str> = file_diff.diff_content.lines().collect();
+    if lines.iter().any(|line| line.starts_with("@@")) {
+        return file_diff;
+    }
+
+    let header = |prefix: &str| {
+        lines
+            .iter()
+            .find_map(|line| line.strip_prefix(prefix))
+            .map(|value| value.trim().to_string())
+    };
+
+    let note = if lines.iter().any(|line| line.starts_with("Binary files ")) {
+        "(binary file — no textual diff)".to_string()
+    } else if let Some((ref old, ref new)) = file_diff.mode_change {
+        format!("(no textual changes — mode change {} → {})", old, new)
+    } else if let (Some(from), Some(_)) = (header("rename from "), header("rename to ")) {
+        format!("(no textual changes — renamed from {})", from)
+    } else if let Some(mode) = header("new file mode ") {
+        format!("(empty file added, mode {})", mode)
+    } else if let Some(mode) = header("deleted file mode ") {
+        format!("(empty file deleted, mode {})", mode)
+    } else {
+        "(no textual changes)".to_string()
+    };
+    file_diff.note = Some(note);
+    file_diff
+}
+
+/// Parses the paths after "diff --git " and returns the filename along with
+/// whether the paths carry a one-letter prefix ("a/" and "b/", or mnemonic
+/// pairs like "c/" and "w/"). With `diff.noprefix` both sides are bare.
+fn parse_diff_git_header(header: &str) -> (String, bool) {
+    // Unless the file was renamed both sides name the same path, so the
+    // header splits at its middle space however many spaces the path has
+    let mid = header.len() / 2;
+    if header.len() % 2 == 1 && header.as_bytes()[mid] == b' ' {
+        let (old, new) = (&header[..mid], &header[mid + 1..]);
+        match (strip_diff_prefix(old), strip_diff_prefix(new)) {
+            (Some(old_path), Some(new_path)) if old_path == new_path && old.as_bytes()[0] != new.as_bytes()[0] => {
+                return (new_path.to_string(), true);
+            }
+            _ if old == new => return (new.to_string(), false),
+            _ => {}
+        }
+    }
+
+    // Renamed: the new side starts at the other one-letter prefix
+    if let Some(old_letter) = strip_diff_prefix(header).map(|_| header.as_bytes()[0]) {
+        let new_start = header.as_bytes().windows(3).enumerate().skip(2).find_map(|(i, window)| {
+            (window[0] == b' ' && window[1].is_ascii_lowercase() && window[1] != old_letter && window[2] == b'/')
+                .then_some(i + 1)
+        });
+        if let Some(new_path) = new_start.and_then(|start| strip_diff_prefix(&header[start..])) {
+            return (new_path.to_string(), true);
+        }
+    }
+
+    let parts: Vec<&str> = header.split_whitespace().collect();
+    if parts.len() < 2 {
+        return (header.trim().to_string(), false);
+    }
+
+    let old = parts[0];
+    let new = parts[parts.len() - 1];
+
+    // Source and destination prefixes always differ, so identical leading
+    // letters mean the path itself starts with a one-letter directory
+    match (strip_diff_prefix(old), strip_diff_prefix(new)) {
+        (Some(_), Some(new_path)) if old.as_bytes()[0] != new.as_bytes()[0] => {
+            (new_path.to_string(), true)
+        }
+        _ => (new.to_string(), false),
+    }
+}
+
+/// Strips a single-letter diff prefix such as "a/" or "w/"
+fn strip_diff_prefix(path: &str) -> Option<&str> {
+    let mut chars = path.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), Some('/')) if c.is_ascii_lowercase() && path.len() > 2 => Some(&path[2..]),
+        _ => None,
+    }
+}
+
+/// Parses the path from a "---"/"+++" line, ignoring /dev/null
+fn parse_diff_path(path: &str, has_prefix: bool) -> Option<String> {
+    // Git appends a tab after paths containing spaces
+    let path = path.trim_end_matches('\t');
+
+    if path == "/dev/null" {
+        return None;
+    }
+
+    if has_prefix {
+        strip_diff_prefix(path).map(|p| p.to_string())
+    } else {
+        Some(path.to_string())
+    }
+}
+
 /// Get diff for a specific file
-pub fn get_file_diff(path: &str, staged: bool) -> Result<String> {
+pub fn get_file_diff(path: &str, staged: bool, ignore_space: bool) -> Result<String> {
     let mut args = vec!["diff"];
 
     if staged {
         args.push("--cached");
     }
+    if ignore_space {
+        args.push("--ignore-all-space");
+    }
 
     args.push("--");
     args.push(path);
@@ -341,6 +792,74 @@ pub fn get_file_diff(path: &str, staged: bool) -> Result<String> {
     }
 }
 
+/// Get a file's diff in a form suitable for building patches for `git apply`
+/// (fixed a/ b/ prefixes, no color, no external diff driver)
+pub fn get_file_patch(path: &str, staged: bool) -> Result<String> {
+    let mut args = vec!["diff", "--no-color", "--no-ext-diff", "--src-prefix=a/", "--dst-prefix=b/"];
+
+    if staged {
+        args.push("--cached");
+    }
+
+    args.push("--");
+    args.push(path);
+
+    let output = Command::new("git")
+        .args(&args)
+        .output()
+        .context("Failed to execute git diff")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Diff failed: {}", error);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Apply a patch to the index (`git apply --cached`), optionally in reverse
+pub fn apply_patch_to_index(patch: &str, reverse: bool) -> Result<()> {
+    let mut args = vec!["apply", "--cached"];
+
+    if reverse {
+        args.push("-R");
+    }
+
+    apply_patch(&args, patch, None)
+}
+
+/// Runs `git <args> -` with the patch on stdin, against `index_file`
+/// instead of the repository's index when given
+fn apply_patch(args: &[&str], patch: &str, index_file: Option<&Path>) -> Result<()> {
+    let mut command = Command::new("git");
+    command.args(args).arg("-");
+    if let Some(index_file) = index_file {
+        command.env("GIT_INDEX_FILE", index_file);
+    }
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute git apply")?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(patch.as_bytes())
+            .context("Failed to write patch to git apply")?;
+    }
+
+    let output = child.wait_with_output().context("Failed to execute git apply")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Applying patch failed: {}", error);
+    }
+
+    Ok(())
+}
+
 /// Checkout a specific commit (detached HEAD state)
 pub fn checkout_commit(hash: &str) -> Result<String> {
     let output = Command::new("git")
@@ -416,7 +935,7 @@ pub fn revert_commit(hash: &str) -> Result<String> {
 /// Get git status (staged and unstaged files)
 pub fn get_status() -> Result<Vec<StatusFile>> {
     let output = Command::new("git")
-        .args(["status", "--porcelain"])
+        .args(["status", "--porcelain", "--untracked-files=normal"])
         .output()
         .context("Failed to execute git status")?;
 
@@ -426,7 +945,68 @@ pub fn get_status() -> Result<Vec<StatusFile>> {
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(parse_status_output(&stdout))
+    let mut files = parse_status_output(&stdout);
+
+    // Porcelain v1 reports submodules like plain files, so mark them
+    let submodules = get_submodule_paths().unwrap_or_default();
+    for file in &mut files {
+        file.is_submodule = submodules.iter().any(|path| path == &file.path);
+    }
+
+    Ok(files)
+}
+
+/// Get the paths of all registered submodules
+pub fn get_submodule_paths() -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .args(["submodule", "status"])
+        .output()
+        .context("Failed to execute git submodule status")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Submodule status failed: {}", error);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(parse_submodule_status(&stdout))
+}
+
+/// Parse git submodule status output
+/// Format: "<flag><hash> <path> (<describe>)" where flag is ' ', '-', '+' or 'U'
+fn parse_submodule_status(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter(|line| line.len() > 1)
+        .filter_map(|line| line[1..].split_whitespace().nth(1))
+        .map(|path| path.to_string())
+        .collect()
+}
+
+/// Update a submodule to the commit recorded in the superproject
+/// (all submodules when no path is given)
+pub fn update_submodule(path: Option<&str>) -> Result<String> {
+    let mut args = vec!["submodule", "update", "--init", "--recursive"];
+
+    if let Some(path) = path {
+        args.push("--");
+        args.push(path);
+    }
+
+    let output = Command::new("git")
+        .args(&args)
+        .output()
+        .context("Failed to execute git submodule update")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Submodule update failed: {}", error);
+    }
+
+    Ok(match path {
+        Some(path) => format!("Updated submodule {}", path),
+        None => "Updated all submodules".to_string(),
+    })
 }
 
 /// Parse git status --porcelain output
@@ -442,6 +1022,20 @@ fn parse_status_output(output: &str) -> Vec<StatusFile> {
         let unstaged_char = line.chars().nth(1).unwrap();
         let path = line[3..].to_string();
 
+        // Unmerged pairs: DD AU UD UA DU AA UU
+        if matches!(
+            (staged_char, unstaged_char),
+            ('D', 'D') | ('A', 'A') | ('U', _) | (_, 'U')
+        ) {
+            files.push(StatusFile {
+                path,
+                status: FileStatus::Conflicted,
+                staged: false,
+                is_submodule: false,
+            });
+            continue;
+        }
+
         // Handle staged files
         if staged_char != ' ' && staged_char != '?' {
             let status = match staged_char {
@@ -456,11 +1050,12 @@ fn parse_status_output(output: &str) -> Vec<StatusFile> {
                 path: path.clone(),
                 status,
                 staged: true,
+                is_submodule: false,
             });
         }
 
-        // Handle unstaged files
-        if unstaged_char != ' ' {
+        // Handle unstaged files (untracked ones are added below)
+        if unstaged_char != ' ' && unstaged_char != '?' {
             let status = match unstaged_char {
                 'M' => FileStatus::Modified,
                 'D' => FileStatus::Deleted,
@@ -471,6 +1066,7 @@ fn parse_status_output(output: &str) -> Vec<StatusFile> {
                 path: path.clone(),
                 status,
                 staged: false,
+                is_submodule: false,
             });
         }
 
@@ -480,6 +1076,7 @@ fn parse_status_output(output: &str) -> Vec<StatusFile> {
                 path,
                 status: FileStatus::Untracked,
                 staged: false,
+                is_submodule: false,
             });
         }
     }
@@ -487,6 +1084,28 @@ fn parse_status_output(output: &str) -> Vec<StatusFile> {
     files
 }
 
+/// List the untracked files inside the given directories in one call,
+/// honoring .gitignore. Paths are relative to the repository root, like
+/// `git status --porcelain`
+pub fn get_untracked_files(dirs: &[&str]) -> Result<Vec<String>> {
+    if dirs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let output = Command::new("git")
+        .args(["ls-files", "--others", "--exclude-standard", "--full-name", "--"])
+        .args(dirs.iter().map(|dir| format!(":(top){}", dir)))
+        .output()
+        .context("Failed to execute git ls-files")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Listing untracked files failed: {}", error);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().map(|line| line.to_string()).collect())
+}
+
 /// Get list of stashes
 pub fn get_stashes() -> Result<Vec<StashEntry>> {
     let output = Command::new("git")
@@ -545,6 +1164,26 @@ fn parse_stash_output(output: &str) -> Vec<StashEntry> {
     stashes
 }
 
+/// Rename or move a tracked file with `git mv`, which stages the rename
+pub fn move_file(from: &str, to: &str) -> Result<String> {
+    let dest = Path::new(to);
+    if dest.exists() && !dest.is_dir() {
+        anyhow::bail!("{} already exists", to);
+    }
+
+    let output = Command::new("git")
+        .args(["mv", "--", from, to])
+        .output()
+        .context("Failed to execute git mv")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Rename failed: {}", error);
+    }
+
+    Ok(format!("Renamed {} to {}", from, to))
+}
+
 /// Stage a file
 pub fn stage_file(path: &str) -> Result<String> {
     let output = Command::new("git")
@@ -605,12 +1244,31 @@ pub fn unstage_all() -> Result<String> {
     Ok("Unstaged all files".to_string())
 }
 
-/// Commit with a message
-pub fn commit(message: &str) -> Result<String> {
+/// Whether the index differs from HEAD (`git diff --cached --quiet` exits 1)
+pub fn has_staged_changes() -> Result<bool> {
     let output = Command::new("git")
-        .args(["commit", "-m", message])
+        .args(["diff", "--cached", "--quiet"])
         .output()
-        .context("Failed to execute git commit")?;
+        .context("Failed to execute git diff --cached")?;
+
+    match output.status.code() {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        _ => {
+            let error = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("Failed to check staged changes: {}", error);
+        }
+    }
+}
+
+/// Commit with a message
+pub fn commit(message: &str, allow_empty: bool) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.args(["commit", "-m", message]);
+    if allow_empty {
+        cmd.arg("--allow-empty");
+    }
+    let output = cmd.output().context("Failed to execute git commit")?;
 
     if !output.status.success() {
         let error = String::from_utf8_lossy(&output.stderr);
@@ -620,11 +1278,43 @@ pub fn commit(message: &str) -> Result<String> {
     Ok("Committed successfully".to_string())
 }
 
-/// Create a stash
-pub fn create_stash(message: Option<&str>, include_untracked: bool) -> Result<String> {
-    let mut args = vec!["stash", "push"];
+/// Lines added/removed per staged file, keyed by path. Binary files map to None.
+pub fn get_staged_numstat() -> Result<HashMap<String, Option<(usize, usize)>>> {
+    let output = Command::new("git")
+        .args(["diff", "--cached", "--numstat", "--no-renames"])
+        .output()
+        .context("Failed to execute git diff --numstat")?;
 
-    if include_untracked {
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to get staged stats: {}", error);
+    }
+
+    Ok(parse_numstat(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_numstat(output: &str) -> HashMap<String, Option<(usize, usize)>> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let added = parts.next()?;
+            let removed = parts.next()?;
+            let path = parts.next()?;
+            let counts = match (added.parse(), removed.parse()) {
+                (Ok(added), Ok(removed)) => Some((added, removed)),
+                _ => None,
+            };
+            Some((path.to_string(), counts))
+        })
+        .collect()
+}
+
+/// Create a stash
+pub fn create_stash(message: Option<&str>, include_untracked: bool) -> Result<String> {
+    let mut args = vec!["stash", "push"];
+
+    if include_untracked {
         args.push("-u");
     }
 
@@ -652,36 +1342,173 @@ pub fn create_stash(message: Option<&str>, include_untracked: bool) -> Result<St
     Ok(msg)
 }
 
-/// Apply a stash
-pub fn apply_stash(index: usize) -> Result<String> {
+/// Stashes only the changes in `patch` (a diff of the working tree against
+/// the index), like `git stash push -p`: the stash records the index plus
+/// the patch, the patch is taken out of the working tree and the index is
+/// left alone.
+pub fn stash_patch(patch: &str, message: &str) -> Result<String> {
+    let head = rev_parse("HEAD")?;
+    let branch = Command::new("git")
+        .args(["symbolic-ref", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "(no branch)".to_string());
+    let (short_hash, subject) = get_commit_summary("HEAD")?;
+
+    let index_tree = plumbing(&["write-tree"], None)?;
+    let index_message = format!("index on {}: {} {}", branch, short_hash, subject);
+    let index_commit = plumbing(&["commit-tree", &index_tree, "-p", &head, "-m", &index_message], None)?;
+
+    // Build the stashed tree in a scratch index so the real one stays put
+    let scratch_index = PathBuf::from(get_git_dir()?).join("gitu-stash-index");
+    let work_tree = plumbing(&["read-tree", &index_tree], Some(&scratch_index))
+        .and_then(|_| apply_patch(&["apply", "--cached"], patch, Some(&scratch_index)))
+        .and_then(|_| plumbing(&["write-tree"], Some(&scratch_index)));
+    let _ = std::fs::remove_file(&scratch_index);
+    let work_tree = work_tree?;
+
+    let stash_message = format!("On {}: {}", branch, message);
+    let stash_commit = plumbing(
+        &["commit-tree", &work_tree, "-p", &head, "-p", &index_commit, "-m", &stash_message],
+        None,
+    )?;
+    plumbing(&["stash", "store", "-m", &stash_message, &stash_commit], None)?;
+
+    // The stash is safe; now drop the stashed changes from the working tree
+    apply_patch(&["apply", "-R"], patch, None)
+        .context("Stashed, but removing the changes from the working tree failed")?;
+
+    Ok(format!("Stashed selected changes: {}", message))
+}
+
+/// Runs a git plumbing command and returns its trimmed output
+fn plumbing(args: &[&str], index_file: Option<&Path>) -> Result<String> {
+    let mut command = Command::new("git");
+    command.args(args);
+    if let Some(index_file) = index_file {
+        command.env("GIT_INDEX_FILE", index_file);
+    }
+
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to execute git {}", args[0]))?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git {} failed: {}", args[0], error);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Summary line of `git stash show --stat`, e.g. "2 files changed, 3 insertions(+)"
+pub fn get_stash_stat(index: usize) -> Result<String> {
+    let stash_ref = format!("stash@{{{}}}", index);
+    let output = Command::new("git")
+        .args(["stash", "show", "--stat", "--no-color", &stash_ref])
+        .output()
+        .context("Failed to execute git stash show")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Stash show failed: {}", error);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().last().unwrap_or("").trim().to_string())
+}
+
+/// Changes in a stash split per file (`git stash show -p`)
+pub fn get_stash_diff(index: usize, ignore_space: bool) -> Result<CommitDiff> {
+    let stash_ref = format!("stash@{{{}}}", index);
+    let output = Command::new("git")
+        .args(["stash", "show", "-p", "--color=never", "--no-ext-diff"])
+        .args(ignore_space.then_some("--ignore-all-space"))
+        .arg(&stash_ref)
+        .output()
+        .context("Failed to execute git stash show")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Stash show failed: {}", error);
+    }
+
+    Ok(parse_commit_diff(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Apply a stash; with `restore_index` what was staged is staged again
+/// (`--index`). Returns the message and whether the stash left conflicts.
+pub fn apply_stash(index: usize, restore_index: bool) -> Result<(String, bool)> {
     let stash_ref = format!("stash@{{{}}}", index);
     let output = Command::new("git")
-        .args(["stash", "apply", &stash_ref])
+        .args(["stash", "apply"])
+        .args(restore_index.then_some("--index"))
+        .arg(&stash_ref)
         .output()
         .context("Failed to execute git stash apply")?;
 
     if !output.status.success() {
+        check_index_conflict(&output, index)?;
+        if has_conflict_output(&output) {
+            return Ok((
+                format!("Applied stash@{{{}}} with conflicts. Resolve them before continuing", index),
+                true,
+            ));
+        }
+
         let error = String::from_utf8_lossy(&output.stderr);
         anyhow::bail!("Stash apply failed: {}", error);
     }
 
-    Ok(format!("Applied stash@{{{}}}", index))
+    Ok((format!("Applied stash@{{{}}}", index), false))
+}
+
+/// Whether a failed command reported merge conflicts
+fn has_conflict_output(output: &std::process::Output) -> bool {
+    String::from_utf8_lossy(&output.stdout).contains("CONFLICT")
+        || String::from_utf8_lossy(&output.stderr).contains("CONFLICT")
+}
+
+/// `git stash --index` refuses (and changes nothing) when the stash's
+/// staged changes don't apply to the current index
+fn check_index_conflict(output: &std::process::Output, index: usize) -> Result<()> {
+    if String::from_utf8_lossy(&output.stderr).contains("conflicts in index") {
+        anyhow::bail!(
+            "Staged changes in stash@{{{}}} conflict with the index; nothing was applied. Apply without restoring the index instead",
+            index
+        );
+    }
+    Ok(())
 }
 
-/// Pop a stash (apply and remove)
-pub fn pop_stash(index: usize) -> Result<String> {
+/// Pop a stash (apply and remove); with `restore_index` what was staged is
+/// staged again (`--index`). Returns the message and whether the stash left
+/// conflicts, in which case it is kept.
+pub fn pop_stash(index: usize, restore_index: bool) -> Result<(String, bool)> {
     let stash_ref = format!("stash@{{{}}}", index);
     let output = Command::new("git")
-        .args(["stash", "pop", &stash_ref])
+        .args(["stash", "pop"])
+        .args(restore_index.then_some("--index"))
+        .arg(&stash_ref)
         .output()
         .context("Failed to execute git stash pop")?;
 
     if !output.status.success() {
+        check_index_conflict(&output, index)?;
+        if has_conflict_output(&output) {
+            return Ok((
+                format!("Applied stash@{{{}}} with conflicts; the stash was kept. Resolve them, then drop it", index),
+                true,
+            ));
+        }
+
         let error = String::from_utf8_lossy(&output.stderr);
         anyhow::bail!("Stash pop failed: {}", error);
     }
 
-    Ok(format!("Popped stash@{{{}}}", index))
+    Ok((format!("Popped stash@{{{}}}", index), false))
 }
 
 /// Drop a stash
@@ -700,6 +1527,33 @@ pub fn drop_stash(index: usize) -> Result<String> {
     Ok(format!("Dropped stash@{{{}}}", index))
 }
 
+/// Give a stash a new message. Git can't rename stashes, so the stash
+/// commit is dropped and stored again under the new message, which moves
+/// it to stash@{0}. The working tree is not touched.
+pub fn restash(index: usize, branch: &str, message: &str) -> Result<String> {
+    let stash_ref = format!("stash@{{{}}}", index);
+    let hash = rev_parse(&stash_ref)?;
+
+    drop_stash(index)?;
+
+    let output = Command::new("git")
+        .args(["stash", "store", "-m", &format!("On {}: {}", branch, message), &hash])
+        .output()
+        .context("Failed to execute git stash store")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(
+            "Stash store failed: {}. The stash is still reachable as {} (git stash store {})",
+            error.trim(),
+            hash,
+            hash
+        );
+    }
+
+    Ok(format!("Re-stashed {} as stash@{{0}}: {}", stash_ref, message))
+}
+
 /// Get list of all branches (local and remote)
 pub fn get_branches() -> Result<Vec<Branch>> {
     // Get local branches with -vv for detailed info
@@ -716,6 +1570,23 @@ pub fn get_branches() -> Result<Vec<Branch>> {
     let local_output = String::from_utf8_lossy(&output.stdout);
     let mut branches = parse_branch_output(&local_output, false);
 
+    // The upstreams come from for-each-ref: -vv shows them in brackets that
+    // can't be told apart from a subject starting with '['
+    let output = Command::new("git")
+        .args([
+            "for-each-ref",
+            "--format=%(refname:short)%00%(upstream:short)%00%(upstream:track)",
+            "refs/heads",
+        ])
+        .output()
+        .context("Failed to execute git for-each-ref")?;
+    if output.status.success() {
+        let upstreams = parse_upstreams(&String::from_utf8_lossy(&output.stdout));
+        for branch in &mut branches {
+            branch.upstream = upstreams.get(&branch.name).cloned();
+        }
+    }
+
     // Get remote branches
     let output = Command::new("git")
         .args(["branch", "-r", "-v", "--no-color"])
@@ -743,30 +1614,49 @@ fn parse_branch_output(output: &str, is_remote: bool) -> Vec<Branch> {
         let is_current = line.starts_with('*');
         let line_content = &line[2..];
 
-        // Parse format: "branch_name hash commit message"
-        let parts: Vec<&str> = line_content.trim().splitn(3, ' ').collect();
-        if parts.len() >= 2 {
-            let name = parts[0].to_string();
-            let commit_hash = parts[1].to_string();
-            let commit_message = if parts.len() >= 3 {
-                parts[2].to_string()
-            } else {
-                String::new()
-            };
-
-            branches.push(Branch {
-                name,
-                is_current,
-                is_remote,
-                commit_hash,
-                commit_message,
-            });
-        }
+        // Parse format: "branch_name hash commit message", where names and
+        // hashes are padded to line up in columns
+        let Some((name, rest)) = line_content.trim().split_once(' ') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        let (commit_hash, commit_message) = rest.split_once(' ').unwrap_or((rest, ""));
+
+        branches.push(Branch {
+            name: name.to_string(),
+            is_current,
+            is_remote,
+            commit_hash: commit_hash.to_string(),
+            commit_message: commit_message.trim_start().to_string(),
+            upstream: None,
+        });
     }
 
     branches
 }
 
+/// Upstream of each local branch from `for-each-ref` lines of
+/// "name NUL upstream NUL track"; an upstream that is "[gone]" (deleted on
+/// the remote) counts as none
+fn parse_upstreams(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\0');
+            let (name, upstream, track) = (fields.next()?, fields.next()?, fields.next().unwrap_or(""));
+            (!upstream.is_empty() && track != "[gone]").then(|| (name.to_string(), upstream.to_string()))
+        })
+        .collect()
+}
+
+/// Short name of a branch without its remote, e.g. "main" for "origin/main"
+pub fn branch_short_name(branch: &Branch) -> &str {
+    match branch.is_remote {
+        true => branch.name.split_once('/').map_or(&branch.name, |(_, name)| name),
+        false => &branch.name,
+    }
+}
+
 /// Switch to a branch
 pub fn switch_branch(name: &str) -> Result<String> {
     // Remove "origin/" prefix if switching to remote branch
@@ -785,6 +1675,28 @@ pub fn switch_branch(name: &str) -> Result<String> {
     Ok(format!("Switched to branch '{}'", branch_name))
 }
 
+/// Switch back to the previous checkout (`git checkout -`): a branch, or a
+/// commit when HEAD was detached there. `git switch -` refuses the latter.
+pub fn switch_previous_branch() -> Result<String> {
+    let output = Command::new("git")
+        .args(["checkout", "-"])
+        .output()
+        .context("Failed to execute git checkout")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        if error.contains("pathspec '-' did not match") {
+            anyhow::bail!("No previous branch to switch to");
+        }
+        anyhow::bail!("Branch switch failed: {}", error);
+    }
+
+    Ok(match get_head_branch() {
+        Some(branch) => format!("Switched back to branch '{}'", branch),
+        None => "Switched back to the previous checkout (detached HEAD)".to_string(),
+    })
+}
+
 /// Delete a branch
 pub fn delete_branch(name: &str, force: bool) -> Result<String> {
     let flag = if force { "-D" } else { "-d" };
@@ -802,175 +1714,1982 @@ pub fn delete_branch(name: &str, force: bool) -> Result<String> {
     Ok(format!("Deleted branch '{}'", name))
 }
 
-/// Create a new branch (but don't switch to it)
-pub fn create_new_branch(name: &str) -> Result<String> {
+/// List tags, newest first
+pub fn get_tags() -> Result<Vec<Tag>> {
+    // Annotated tags point at a tag object; `*objectname` peels it to the commit
     let output = Command::new("git")
-        .args(["branch", name])
+        .args([
+            "tag",
+            "--list",
+            "--sort=-creatordate",
+            "--format=%(refname:short)%09%(if)%(*objectname)%(then)%(*objectname:short)%(else)%(objectname:short)%(end)%09%(if)%(contents:signature)%(then)signed%(end)%09%(contents:subject)",
+        ])
         .output()
-        .context("Failed to execute git branch")?;
+        .context("Failed to execute git tag")?;
 
     if !output.status.success() {
         let error = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Branch creation failed: {}", error);
+        anyhow::bail!("Tag listing failed: {}", error);
     }
 
-    Ok(format!("Created branch '{}'", name))
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(parse_tag_output(&stdout))
 }
 
-/// Fetch from remote
-pub fn fetch() -> Result<String> {
+/// Parse `name<TAB>target<TAB>signed<TAB>subject` lines from `get_tags`
+fn parse_tag_output(output: &str) -> Vec<Tag> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, '\t');
+            let name = parts.next()?.trim();
+            if name.is_empty() {
+                return None;
+            }
+            let target = parts.next().unwrap_or("").trim().to_string();
+            let signature = if parts.next().unwrap_or("").trim() == "signed" {
+                TagSignature::Unchecked
+            } else {
+                TagSignature::Unsigned
+            };
+            Some(Tag {
+                name: name.to_string(),
+                target,
+                subject: parts.next().unwrap_or("").trim().to_string(),
+                signature,
+            })
+        })
+        .collect()
+}
+
+/// Check a tag's signature with `git verify-tag --raw`, which passes on
+/// gpg's status lines so a bad signature can be told from one that can't
+/// be checked at all
+pub fn verify_tag(name: &str) -> Result<TagSignature> {
     let output = Command::new("git")
-        .args(["fetch"])
+        .args(["verify-tag", "--raw", name])
         .output()
-        .context("Failed to execute git fetch")?;
-
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Fetch failed: {}", error);
-    }
+        .context("Failed to execute git verify-tag")?;
 
-    Ok("Fetched from remote".to_string())
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Ok(parse_verification(output.status.success(), &stderr))
 }
 
-/// Push to remote
-pub fn push(force: bool) -> Result<String> {
-    let mut args = vec!["push"];
+fn parse_verification(success: bool, stderr: &str) -> TagSignature {
+    if success {
+        return TagSignature::Good(parse_signer(stderr).unwrap_or_default());
+    }
+    if stderr.contains("[GNUPG:] BADSIG") {
+        return TagSignature::Bad;
+    }
+    let reason = if stderr.contains("[GNUPG:] NO_PUBKEY") {
+        "public key missing".to_string()
+    } else if stderr.contains("no signature found") {
+        "no signature".to_string()
+    } else if stderr.contains("non-tag object") {
+        "lightweight tag".to_string()
+    } else {
+        stderr
+            .lines()
+            .find(|line| !line.starts_with("[GNUPG:]") && !line.trim().is_empty())
+            .map(|line| line.trim().trim_start_matches("error: ").to_string())
+            .unwrap_or_else(|| "can't be checked".to_string())
+    };
+    TagSignature::Unverifiable(reason)
+}
 
-    if force {
-        args.push("--force-with-lease");
+/// Signer from gpg's `[GNUPG:] GOODSIG <keyid> Name <email>` status line,
+/// or its `Good signature from "Name <email>" [trust]` message
+fn parse_signer(gpg_output: &str) -> Option<String> {
+    if let Some(line) = gpg_output.lines().find_map(|line| line.strip_prefix("[GNUPG:] GOODSIG ")) {
+        return line.split_once(' ').map(|(_, signer)| signer.to_string());
     }
+    let line = gpg_output.lines().find(|line| line.contains("Good signature from"))?;
+    let start = line.find('"')? + 1;
+    let end = line.rfind('"')?;
+    (end > start).then(|| line[start..end].to_string())
+}
 
+/// Check out a tag (detached HEAD)
+pub fn checkout_tag(name: &str) -> Result<String> {
     let output = Command::new("git")
-        .args(&args)
+        .args(["checkout", &format!("refs/tags/{}", name)])
         .output()
-        .context("Failed to execute git push")?;
+        .context("Failed to execute git checkout")?;
 
     if !output.status.success() {
         let error = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Push failed: {}", error);
+        anyhow::bail!("Checkout failed: {}", error);
     }
 
-    let msg = if force {
-        "Force pushed to remote"
-    } else {
-        "Pushed to remote"
-    };
-
-    Ok(msg.to_string())
+    Ok(format!("Checked out tag '{}' (detached HEAD)", name))
 }
 
-/// Get the last commit message (for amend)
-pub fn get_last_commit_message() -> Result<String> {
+pub fn delete_tag(name: &str) -> Result<String> {
     let output = Command::new("git")
-        .args(["log", "-1", "--format=%s"])
+        .args(["tag", "-d", name])
         .output()
-        .context("Failed to execute git log")?;
+        .context("Failed to execute git tag -d")?;
 
     if !output.status.success() {
         let error = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Failed to get last commit message: {}", error);
+        anyhow::bail!("Tag deletion failed: {}", error);
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    Ok(format!("Deleted tag '{}'", name))
+}
+
+/// Tag HEAD. A message makes an annotated tag, otherwise it is lightweight.
+/// Signed tags are always annotated and fall back to the name as message.
+pub fn create_tag(name: &str, message: Option<&str>, sign: bool) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.arg("tag");
+    if sign {
+        cmd.args(["-s", "-m", message.unwrap_or(name)]);
+    } else if let Some(message) = message {
+        cmd.args(["-a", "-m", message]);
+    }
+    let output = cmd.arg(name).output().context("Failed to execute git tag")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        if sign && error.contains("failed to sign") {
+            anyhow::bail!(
+                "Signing failed; check user.signingkey and that gpg can sign: {}",
+                error.trim()
+            );
+        }
+        anyhow::bail!("Tag creation failed: {}", error);
+    }
+
+    if sign {
+        Ok(format!("Created signed tag '{}'", name))
+    } else {
+        Ok(format!("Created tag '{}'", name))
+    }
 }
 
-/// Commit with amend
-pub fn commit_amend(message: &str) -> Result<String> {
+/// Push a single tag to the default remote
+pub fn push_tag(name: &str) -> Result<String> {
+    let remote = default_remote()?;
+
     let output = Command::new("git")
-        .args(["commit", "--amend", "-m", message])
+        .args(["push", &remote, &format!("refs/tags/{}", name)])
         .output()
-        .context("Failed to execute git commit --amend")?;
+        .context("Failed to execute git push")?;
 
     if !output.status.success() {
         let error = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Amend failed: {}", error);
+        if !parse_rejected_tags(&error).is_empty() {
+            anyhow::bail!("Tag '{}' already exists on {} with a different target", name, remote);
+        }
+        anyhow::bail!("Push failed: {}", error);
     }
 
-    Ok("Amended commit successfully".to_string())
+    Ok(format!("Pushed tag '{}' to {}", name, remote))
 }
 
-/// Discard changes in a file (git checkout -- <path>)
-pub fn discard_file(path: &str) -> Result<String> {
+/// Push every local tag to the default remote
+pub fn push_tags() -> Result<String> {
+    let remote = default_remote()?;
+
     let output = Command::new("git")
-        .args(["checkout", "--", path])
+        .args(["push", &remote, "--tags"])
         .output()
-        .context("Failed to execute git checkout")?;
+        .context("Failed to execute git push --tags")?;
 
     if !output.status.success() {
         let error = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Discard failed: {}", error);
+        let rejected = parse_rejected_tags(&error);
+        if !rejected.is_empty() {
+            // Only mention the rest when git reports pushing some
+            let pushed = error.lines().any(|line| line.contains("[new tag]"));
+            anyhow::bail!(
+                "Already on {} with a different target: {}{}",
+                remote,
+                rejected.join(", "),
+                if pushed { " (other tags were pushed)" } else { "" }
+            );
+        }
+        anyhow::bail!("Push failed: {}", error);
     }
 
-    Ok(format!("Discarded changes in {}", path))
+    Ok(format!("Pushed all tags to {}", remote))
 }
 
-/// Merge a branch into the current branch
-pub fn merge_branch(name: &str) -> Result<String> {
+/// Tags git refused to overwrite, from lines like
+/// ` ! [rejected]        v1 -> v1 (already exists)`
+fn parse_rejected_tags(stderr: &str) -> Vec<String> {
+    stderr
+        .lines()
+        .filter(|line| line.contains("[rejected]") && line.ends_with("(already exists)"))
+        .filter_map(|line| line.split("[rejected]").nth(1)?.split_whitespace().next())
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Create a new branch (but don't switch to it)
+pub fn create_new_branch(name: &str) -> Result<String> {
     let output = Command::new("git")
-        .args(["merge", name])
+        .args(["branch", name])
         .output()
-        .context("Failed to execute git merge")?;
+        .context("Failed to execute git branch")?;
 
     if !output.status.success() {
         let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Branch creation failed: {}", error);
+    }
 
-        if error.contains("conflict") || error.contains("CONFLICT") {
-            return Ok("Merge has conflicts. Resolve them and run 'git merge --continue'".to_string());
+    Ok(format!("Created branch '{}'", name))
+}
+
+/// Whether a file or directory exists in the git dir (`git rev-parse --git-path`)
+fn git_path_exists(name: &str) -> bool {
+    Command::new("git")
+        .args(["rev-parse", "--git-path", name])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .is_some_and(|path| std::path::Path::new(&path).exists())
+}
+
+/// Check whether a bisect session is in progress
+pub fn is_bisecting() -> bool {
+    git_path_exists("BISECT_LOG")
+}
+
+/// An operation that stopped part way, usually on conflicts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InProgress {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+}
+
+impl InProgress {
+    /// Label for the banner, e.g. "MERGING"
+    pub fn label(self) -> &'static str {
+        match self {
+            InProgress::Merge => "MERGING",
+            InProgress::Rebase => "REBASING",
+            InProgress::CherryPick => "CHERRY-PICKING",
+            InProgress::Revert => "REVERTING",
         }
+    }
 
-        anyhow::bail!("Merge failed: {}", error);
+    /// The git command that finishes (`--continue`) or abandons (`--abort`) it
+    pub fn command(self) -> &'static str {
+        match self {
+            InProgress::Merge => "git merge",
+            InProgress::Rebase => "git rebase",
+            InProgress::CherryPick => "git cherry-pick",
+            InProgress::Revert => "git revert",
+        }
     }
+}
 
-    Ok(format!("Merged branch '{}' into current branch", name))
+/// The merge, rebase, cherry-pick or revert waiting to be finished, found
+/// through the files git leaves in its directory (MERGE_HEAD, rebase-merge/ ...)
+pub fn get_in_progress() -> Option<InProgress> {
+    if is_rebasing() {
+        Some(InProgress::Rebase)
+    } else if git_path_exists("MERGE_HEAD") {
+        Some(InProgress::Merge)
+    } else if git_path_exists("CHERRY_PICK_HEAD") {
+        Some(InProgress::CherryPick)
+    } else if git_path_exists("REVERT_HEAD") {
+        Some(InProgress::Revert)
+    } else {
+        None
+    }
 }
 
-/// Pull from remote
-pub fn pull(rebase: bool) -> Result<String> {
-    let mut args = vec!["pull"];
+/// Start a bisect session
+pub fn bisect_start() -> Result<String> {
+    run_bisect(&["bisect", "start"])
+}
 
-    if rebase {
-        args.push("--rebase");
-    }
+/// Mark a commit as good, bad, or skipped and return git's progress report
+pub fn bisect_mark(mark: BisectMark, hash: &str) -> Result<String> {
+    let verb = match mark {
+        BisectMark::Good => "good",
+        BisectMark::Bad => "bad",
+        BisectMark::Skip => "skip",
+    };
+    run_bisect(&["bisect", verb, hash])
+}
+
+/// End the bisect session and return to the original HEAD
+pub fn bisect_reset() -> Result<String> {
+    run_bisect(&["bisect", "reset"])?;
+    Ok("Bisect finished, returned to original HEAD".to_string())
+}
 
+fn run_bisect(args: &[&str]) -> Result<String> {
     let output = Command::new("git")
-        .args(&args)
+        .args(args)
         .output()
-        .context("Failed to execute git pull")?;
+        .context("Failed to execute git bisect")?;
 
     if !output.status.success() {
         let error = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Pull failed: {}", error);
+        anyhow::bail!("Bisect failed: {}", error);
     }
 
-    let msg = if rebase {
-        "Pulled with rebase from remote"
-    } else {
-        "Pulled from remote"
-    };
-
-    Ok(msg.to_string())
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(parse_bisect_output(&stdout))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_parse_simple_log() {
-        let input = "* abc1234 Initial commit\n* def5678 Second commit";
-        let commits = parse_log_output(input);
+/// Condenses git bisect output into a one-line progress message
+fn parse_bisect_output(output: &str) -> String {
+    if let Some(line) = output.lines().find(|l| l.ends_with("is the first bad commit")) {
+        let hash = line.split_whitespace().next().unwrap_or("");
+        let subject = output
+            .lines()
+            .find(|l| l.starts_with("    "))
+            .map(|l| l.trim())
+            .unwrap_or("");
+        return format!("First bad commit: {} {}", &hash[..7.min(hash.len())], subject);
+    }
 
-        assert_eq!(commits.len(), 2);
-        assert_eq!(commits[0].hash, "abc1234");
-        assert_eq!(commits[0].message, "Initial commit");
+    if let Some(line) = output.lines().find(|l| l.starts_with("Bisecting: ")) {
+        return format!("Bisect: {}", &line["Bisecting: ".len()..]);
     }
 
-    #[test]
-    fn test_parse_with_graph() {
-        let input = "* | abc1234 Merge commit\n|\\ \n| * def5678 Feature branch";
-        let commits = parse_log_output(input);
+    let first = output.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+    format!("Bisect: {}", first.strip_prefix("status: ").unwrap_or(first))
+}
 
-        assert!(commits.len() >= 2);
-        assert_eq!(commits[0].hash, "abc1234");
+/// Read a git config value. Returns None when the key is unset.
+pub fn get_config(key: &str) -> Result<Option<String>> {
+    let output = Command::new("git")
+        .args(["config", "--get", key])
+        .output()
+        .context("Failed to execute git config")?;
+
+    // Exit code 1 means the key is not set
+    if output.status.code() == Some(1) {
+        return Ok(None);
+    }
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to read {}: {}", key, error);
+    }
+
+    Ok(Some(String::from_utf8_lossy(&output.stdout).trim().to_string()))
+}
+
+/// Read a git config value as a boolean by git's own rules (`yes`, `off`,
+/// `0`, ... via `--type=bool`). Fails when the value is not a boolean.
+pub fn get_config_bool(key: &str) -> Result<Option<bool>> {
+    let output = Command::new("git")
+        .args(["config", "--type=bool", "--get", key])
+        .output()
+        .context("Failed to execute git config")?;
+
+    // Exit code 1 means the key is not set
+    if output.status.code() == Some(1) {
+        return Ok(None);
+    }
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to read {}: {}", key, error);
+    }
+
+    Ok(Some(String::from_utf8_lossy(&output.stdout).trim() == "true"))
+}
+
+/// Get the git directory of the current repository (`.git`, or the
+/// worktree's own directory under it)
+pub fn get_git_dir() -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--absolute-git-dir"])
+        .output()
+        .context("Failed to execute git rev-parse")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Not a git repository: {}", error);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Get the top-level directory of the current repository (or worktree)
+pub fn get_repo_root() -> Result<String> {
+    find_repo_root(Path::new("."))
+}
+
+/// Top-level directory of the repository (or worktree) containing `dir`
+fn find_repo_root(dir: &Path) -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(dir)
+        .output()
+        .context("Failed to execute git rev-parse")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Not a git repository: {}", error);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Makes the repository root the working directory, so every git command
+/// (and every path gitu builds from git's root-relative output) behaves the
+/// same wherever gitu was launched. Returns the root.
+pub fn enter_repo_root() -> Result<String> {
+    let root = find_repo_root(Path::new("."))?;
+    std::env::set_current_dir(&root).with_context(|| format!("Failed to enter {}", root))?;
+    Ok(root)
+}
+
+/// Get list of worktrees
+pub fn get_worktrees() -> Result<Vec<Worktree>> {
+    let output = Command::new("git")
+        .args(["worktree", "list", "--porcelain"])
+        .output()
+        .context("Failed to execute git worktree list")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Worktree listing failed: {}", error);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(parse_worktree_output(&stdout))
+}
+
+/// An entry of a branch's reflog: where the branch pointed and why it moved
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflogEntry {
+    pub hash: String,
+    /// Reflog selector, e.g. "main@{2}"
+    pub selector: String,
+    /// What moved the ref, e.g. "commit: Fix typo" or "reset: moving to HEAD~1"
+    pub subject: String,
+}
+
+/// Get the reflog of a local branch, newest first
+pub fn get_branch_reflog(branch: &str) -> Result<Vec<ReflogEntry>> {
+    let output = Command::new("git")
+        .args(["reflog", "show", "--format=%h%x09%gd%x09%gs", branch, "--"])
+        .output()
+        .context("Failed to execute git reflog")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to read reflog of {}: {}", branch, error);
+    }
+
+    Ok(parse_reflog_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse `git reflog --format=%h%x09%gd%x09%gs` output
+fn parse_reflog_output(output: &str) -> Vec<ReflogEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            Some(ReflogEntry {
+                hash: fields.next()?.to_string(),
+                selector: fields.next()?.to_string(),
+                subject: fields.next().unwrap_or("").to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Point a local branch at another commit. The checked-out branch is moved
+/// with `reset --keep`, which refuses to throw away local changes.
+pub fn reset_branch(branch: &str, target: &str, is_current: bool) -> Result<String> {
+    let output = if is_current {
+        Command::new("git")
+            .args(["reset", "--keep", target])
+            .output()
+            .context("Failed to execute git reset")?
+    } else {
+        Command::new("git")
+            .args(["branch", "--force", branch, target])
+            .output()
+            .context("Failed to execute git branch")?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Reset of {} failed: {}", branch, error);
+    }
+
+    Ok(format!("Reset {} to {}", branch, target))
+}
+
+/// Parse git worktree list --porcelain output (blank-line separated records)
+fn parse_worktree_output(output: &str) -> Vec<Worktree> {
+    let mut worktrees = Vec::new();
+    let mut current: Option<Worktree> = None;
+
+    for line in output.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            if let Some(worktree) = current.take() {
+                worktrees.push(worktree);
+            }
+            current = Some(Worktree {
+                path: path.to_string(),
+                head: String::new(),
+                branch: None,
+                is_bare: false,
+            });
+        } else if let Some(ref mut worktree) = current {
+            if let Some(head) = line.strip_prefix("HEAD ") {
+                worktree.head = head.to_string();
+            } else if let Some(branch) = line.strip_prefix("branch ") {
+                worktree.branch = Some(branch.trim_start_matches("refs/heads/").to_string());
+            } else if line == "bare" {
+                worktree.is_bare = true;
+            }
+        }
+    }
+
+    if let Some(worktree) = current {
+        worktrees.push(worktree);
+    }
+
+    worktrees
+}
+
+/// Add a new worktree, optionally checking out an existing branch
+pub fn add_worktree(path: &str, branch: Option<&str>) -> Result<String> {
+    let mut args = vec!["worktree", "add", path];
+
+    if let Some(branch) = branch {
+        args.push(branch);
+    }
+
+    let output = Command::new("git")
+        .args(&args)
+        .output()
+        .context("Failed to execute git worktree add")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Adding worktree failed: {}", error);
+    }
+
+    Ok(format!("Added worktree at {}", path))
+}
+
+/// Fetch from remote
+pub fn fetch() -> Result<String> {
+    let output = Command::new("git")
+        .args(["fetch"])
+        .output()
+        .context("Failed to execute git fetch")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Fetch failed: {}", error);
+    }
+
+    Ok("Fetched from remote".to_string())
+}
+
+/// Fetch with --prune so remote branches deleted upstream disappear.
+/// Fetches every remote when `remote` is None.
+pub fn fetch_prune(remote: Option<&str>) -> Result<String> {
+    let mut args = vec!["fetch", "--prune"];
+    match remote {
+        Some(remote) => args.push(remote),
+        None => args.push("--all"),
+    }
+
+    let output = Command::new("git")
+        .args(&args)
+        .output()
+        .context("Failed to execute git fetch")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Fetch failed: {}", error);
+    }
+
+    Ok(match remote {
+        Some(remote) => format!("Fetched and pruned {}", remote),
+        None => "Fetched and pruned all remotes".to_string(),
+    })
+}
+
+/// List the names of the configured remotes
+pub fn get_remotes() -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .args(["remote"])
+        .output()
+        .context("Failed to execute git remote")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to list remotes: {}", error);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// Fetch URL of each remote, keyed by remote name
+pub fn get_remote_urls() -> Result<HashMap<String, String>> {
+    let output = Command::new("git")
+        .args(["remote", "-v"])
+        .output()
+        .context("Failed to execute git remote -v")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to list remotes: {}", error);
+    }
+
+    Ok(parse_remote_urls(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parses `git remote -v` lines ("origin\t<url> (fetch)"), keeping the fetch URLs
+fn parse_remote_urls(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, rest) = line.split_once('\t')?;
+            let url = rest.strip_suffix(" (fetch)")?;
+            Some((name.to_string(), url.to_string()))
+        })
+        .collect()
+}
+
+/// Point a remote at a new URL (`git remote set-url`)
+pub fn set_remote_url(remote: &str, url: &str) -> Result<String> {
+    let output = Command::new("git")
+        .args(["remote", "set-url", remote, url])
+        .output()
+        .context("Failed to execute git remote set-url")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to set URL of {}: {}", remote, error);
+    }
+
+    Ok(format!("{} now points to {}", remote, url))
+}
+
+/// The remote to push to when nothing else says: `origin`, or else the first one
+fn default_remote() -> Result<String> {
+    let remotes = get_remotes()?;
+    remotes
+        .iter()
+        .find(|r| *r == "origin")
+        .or_else(|| remotes.first())
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Push failed: no remote configured"))
+}
+
+/// Commits HEAD is (ahead, behind) its upstream, based on the last fetch.
+/// Returns None when the current branch has no upstream.
+pub fn get_ahead_behind() -> Result<Option<(usize, usize)>> {
+    let output = Command::new("git")
+        .args(["rev-list", "--left-right", "--count", "HEAD...@{u}"])
+        .output()
+        .context("Failed to execute git rev-list")?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(parse_ahead_behind(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_ahead_behind(output: &str) -> Option<(usize, usize)> {
+    let mut counts = output.split_whitespace().map(|n| n.parse::<usize>());
+    match (counts.next(), counts.next()) {
+        (Some(Ok(ahead)), Some(Ok(behind))) => Some((ahead, behind)),
+        _ => None,
+    }
+}
+
+/// Push to remote
+pub fn push(force: bool) -> Result<String> {
+    let mut args = vec!["push"];
+
+    if force {
+        args.push("--force-with-lease");
+    }
+
+    let output = Command::new("git")
+        .args(&args)
+        .output()
+        .context("Failed to execute git push")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Push failed: {}", error);
+    }
+
+    let msg = if force {
+        "Force pushed to remote"
+    } else {
+        "Pushed to remote"
+    };
+
+    Ok(msg.to_string())
+}
+
+/// Remote a branch tracks (`branch.<name>.remote`), if any
+pub fn get_upstream_remote(branch: &str) -> Option<String> {
+    get_config(&format!("branch.{}.remote", branch)).ok().flatten()
+}
+
+/// Push a branch to a remote other than its upstream (`git push <remote>
+/// <branch>`), e.g. to a fork while fetching from the main repository
+pub fn push_branch_to(remote: &str, branch: &str) -> Result<String> {
+    let output = Command::new("git")
+        .args(["push", remote, branch])
+        .output()
+        .context("Failed to execute git push")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Push to {} failed: {}", remote, error);
+    }
+
+    Ok(format!("Pushed {} to {}", branch, remote))
+}
+
+/// Every file in a commit's tree (`git ls-tree -r -z`), leaving out
+/// submodules, whose commits can't be opened as files
+pub fn list_commit_files(hash: &str) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .args(["ls-tree", "-r", "-z", hash])
+        .output()
+        .context("Failed to execute git ls-tree")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to list files of {}: {}", hash, error);
+    }
+
+    Ok(parse_ls_tree(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Paths of the blobs in NUL-terminated "mode type object<TAB>path" entries
+fn parse_ls_tree(output: &str) -> Vec<String> {
+    output
+        .split('\0')
+        .filter_map(|entry| {
+            let (info, path) = entry.split_once('\t')?;
+            (info.split(' ').nth(1) == Some("blob")).then(|| path.to_string())
+        })
+        .collect()
+}
+
+/// A file's content at a commit (`git show <hash>:<path>`).
+/// Returns None for binary files.
+pub fn get_file_at_commit(hash: &str, path: &str) -> Result<Option<String>> {
+    let output = Command::new("git")
+        .args(["show", &format!("{}:{}", hash, path)])
+        .output()
+        .context("Failed to execute git show")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to read {} at {}: {}", path, hash, error);
+    }
+
+    if output.stdout.contains(&0) {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).to_string()))
+}
+
+/// Name of the current branch's upstream and the commits on HEAD it lacks
+/// (`git log @{u}..HEAD`, newest first), i.e. what a push would send.
+/// With `incoming` it is the other way round (`HEAD..@{u}`): what a pull
+/// would bring. Returns None when the branch has no upstream.
+pub fn get_upstream_commits(incoming: bool) -> Result<Option<(String, Vec<Commit>)>> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"])
+        .output()
+        .context("Failed to execute git rev-parse")?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let upstream = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let range = if incoming { "HEAD..@{u}" } else { "@{u}..HEAD" };
+    Ok(Some((upstream, log_range(range)?)))
+}
+
+/// Commits only on `left` and only on `right`, newest first, e.g. two
+/// remotes' copies of the same branch
+pub fn compare_branches(left: &str, right: &str) -> Result<(Vec<Commit>, Vec<Commit>)> {
+    Ok((
+        log_range(&format!("{}..{}", right, left))?,
+        log_range(&format!("{}..{}", left, right))?,
+    ))
+}
+
+/// Commits in a range such as "a..b", in the Log panel's format
+fn log_range(range: &str) -> Result<Vec<Commit>> {
+    let output = Command::new("git")
+        .args(["log", "--decorate", "--format=%h %p%x09%cs%x09%d%x09%s", range])
+        .output()
+        .context("Failed to execute git log")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to compare {}: {}", range, error);
+    }
+
+    Ok(parse_log_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Push the current branch, setting its upstream on the first push
+pub fn push_current_branch() -> Result<String> {
+    let has_upstream = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"])
+        .output()
+        .context("Failed to execute git rev-parse")?
+        .status
+        .success();
+
+    if has_upstream {
+        return push(false);
+    }
+
+    let output = Command::new("git")
+        .args(["symbolic-ref", "--short", "HEAD"])
+        .output()
+        .context("Failed to execute git symbolic-ref")?;
+    if !output.status.success() {
+        anyhow::bail!("Push failed: HEAD is not on a branch");
+    }
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let remote = default_remote()?;
+
+    let output = Command::new("git")
+        .args(["push", "--set-upstream", &remote, &branch])
+        .output()
+        .context("Failed to execute git push")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Push failed: {}", error);
+    }
+
+    Ok(format!("Pushed to {}/{} (upstream set)", remote, branch))
+}
+
+/// Get the last commit message (for amend)
+pub fn get_last_commit_message() -> Result<String> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%s"])
+        .output()
+        .context("Failed to execute git log")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to get last commit message: {}", error);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Full message (subject and body) of the last commit
+pub fn get_last_commit_full_message() -> Result<String> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%B"])
+        .output()
+        .context("Failed to execute git log")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to get last commit message: {}", error);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+}
+
+/// The editor git would open for a commit message: GIT_EDITOR, core.editor,
+/// VISUAL or EDITOR, in that order (`git var GIT_EDITOR`)
+pub fn get_editor() -> Result<String> {
+    let output = Command::new("git")
+        .args(["var", "GIT_EDITOR"])
+        .output()
+        .context("Failed to execute git var")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("No editor configured (set $EDITOR or core.editor): {}", error);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The prefix git uses for comment lines in commit messages: `core.commentChar`,
+/// or "#" when it is unset or "auto"
+pub fn get_comment_char() -> String {
+    Command::new("git")
+        .args(["config", "core.commentChar"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|comment| !comment.is_empty() && comment != "auto")
+        .unwrap_or_else(|| "#".to_string())
+}
+
+/// Commit (or amend HEAD) with the message in a file, dropping `comment`
+/// lines the way `git commit` does for messages written in an editor
+pub fn commit_from_file(path: &Path, amend: bool, comment: &str) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-c")
+        .arg(format!("core.commentChar={}", comment))
+        .arg("commit")
+        .args(amend.then_some("--amend"))
+        .args(["--cleanup=strip", "-F"])
+        .arg(path)
+        .output()
+        .context("Failed to execute git commit")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Commit failed: {}", error);
+    }
+
+    Ok(if amend { "Amended commit successfully" } else { "Committed successfully" }.to_string())
+}
+
+/// A commit message file as git would read it: `comment` lines dropped
+/// and surrounding blank lines trimmed
+pub fn strip_message_comments(text: &str, comment: &str) -> String {
+    text.lines()
+        .filter(|line| !line.starts_with(comment))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Find the best common ancestor of two revisions
+pub fn merge_base(a: &str, b: &str) -> Result<String> {
+    let output = Command::new("git")
+        .args(["merge-base", a, b])
+        .output()
+        .context("Failed to execute git merge-base")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("No common ancestor found: {}", error);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Render a commit through a template such as "{hash} {subject}".
+/// Supported placeholders: {hash}, {full_hash}, {subject}, {author}, {date}.
+pub fn format_commit(hash: &str, template: &str) -> Result<String> {
+    let format = template_to_git_format(template);
+    let output = Command::new("git")
+        .args(["log", "-1", &format!("--format={}", format), hash])
+        .output()
+        .context("Failed to execute git log")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to format commit: {}", error);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+}
+
+/// Translates template placeholders into `git log --format` codes
+fn template_to_git_format(template: &str) -> String {
+    template
+        .replace('%', "%%")
+        .replace("{full_hash}", "%H")
+        .replace("{hash}", "%h")
+        .replace("{subject}", "%s")
+        .replace("{author}", "%an")
+        .replace("{date}", "%as")
+}
+
+/// How the current branch and its upstream have diverged
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UpstreamDivergence {
+    /// Commits on HEAD but not on the upstream (unpushed), abbreviated like
+    /// the hashes in the Log so a row is one lookup
+    pub ahead: HashSet<String>,
+    /// Commits on the upstream but not on HEAD (unpulled), abbreviated the same way
+    pub behind: HashSet<String>,
+    /// Where the two histories fork
+    pub merge_base: Option<String>,
+}
+
+/// Compare HEAD with its upstream. Returns None when there is no upstream.
+pub fn get_upstream_divergence() -> Result<Option<UpstreamDivergence>> {
+    let output = Command::new("git")
+        .args(["rev-list", "--left-right", "--abbrev-commit", "HEAD...@{u}"])
+        .output()
+        .context("Failed to execute git rev-list")?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let mut divergence = parse_left_right(&String::from_utf8_lossy(&output.stdout));
+    divergence.merge_base = merge_base("HEAD", "@{u}").ok();
+    Ok(Some(divergence))
+}
+
+/// Parses `rev-list --left-right` output: "<hash" is left-only, ">hash" right-only
+fn parse_left_right(output: &str) -> UpstreamDivergence {
+    let mut divergence = UpstreamDivergence::default();
+    for line in output.lines() {
+        if let Some(hash) = line.strip_prefix('<') {
+            divergence.ahead.insert(hash.to_string());
+        } else if let Some(hash) = line.strip_prefix('>') {
+            divergence.behind.insert(hash.to_string());
+        }
+    }
+    divergence
+}
+
+/// Short hash and subject of HEAD
+pub fn get_head() -> Result<(String, String)> {
+    get_commit_summary("HEAD")
+}
+
+/// Name of the checked-out branch, or None when HEAD is detached
+pub fn get_head_branch() -> Option<String> {
+    Command::new("git")
+        .args(["symbolic-ref", "--quiet", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Get the short hash and subject of a revision
+pub fn get_commit_summary(rev: &str) -> Result<(String, String)> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%h %s", rev])
+        .output()
+        .context("Failed to execute git log")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to describe {}: {}", rev, error);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (hash, subject) = stdout.trim().split_once(' ').unwrap_or((stdout.trim(), ""));
+    Ok((hash.to_string(), subject.to_string()))
+}
+
+/// Commit with amend. With `include_unstaged`, modified tracked files are
+/// staged too (`git commit --amend -a`).
+pub fn commit_amend(message: &str, include_unstaged: bool) -> Result<String> {
+    let mut args = vec!["commit", "--amend"];
+    if include_unstaged {
+        args.push("-a");
+    }
+    args.extend(["-m", message]);
+
+    let output = Command::new("git")
+        .args(&args)
+        .output()
+        .context("Failed to execute git commit --amend")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Amend failed: {}", error);
+    }
+
+    let msg = if include_unstaged {
+        "Amended commit with unstaged changes"
+    } else {
+        "Amended commit successfully"
+    };
+
+    Ok(msg.to_string())
+}
+
+/// Author (`Name <email>`) and author date (strict ISO 8601) of HEAD
+pub fn get_head_author_and_date() -> Result<(String, String)> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%an <%ae>%x09%aI"])
+        .output()
+        .context("Failed to execute git log")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to read HEAD author: {}", error);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (author, date) = stdout
+        .trim_end()
+        .split_once('\t')
+        .ok_or_else(|| anyhow::anyhow!("Unexpected git log output: {}", stdout))?;
+    Ok((author.to_string(), date.to_string()))
+}
+
+/// Whether text has the `Name <email>` form git expects for --author
+pub fn is_valid_author(author: &str) -> bool {
+    let Some((name, rest)) = author.trim().split_once('<') else {
+        return false;
+    };
+    let Some(email) = rest.strip_suffix('>') else {
+        return false;
+    };
+    !name.trim().is_empty() && !email.trim().is_empty() && !email.contains(['<', '>'])
+}
+
+/// Rewrites HEAD's author and author date, leaving its tree and message
+/// (and whatever is staged) alone
+pub fn amend_author_and_date(author: &str, date: &str) -> Result<String> {
+    let output = Command::new("git")
+        .args(["commit", "--amend", "--only", "--no-edit"])
+        .arg(format!("--author={}", author.trim()))
+        .arg(format!("--date={}", date.trim()))
+        .output()
+        .context("Failed to execute git commit --amend")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Amend failed: {}", error);
+    }
+
+    let (author, date) = get_head_author_and_date()?;
+    Ok(format!("Amended HEAD: {} on {}", author, date))
+}
+
+/// Replace the subject of a commit, keeping the rest of its message.
+/// HEAD is amended directly (leaving the index alone); older commits are
+/// rewritten with a scripted `git rebase -i` that marks the commit `reword`,
+/// which is refused when a merge sits above the commit: a plain rebase would
+/// flatten it.
+pub fn reword_commit(hash: &str, subject: &str) -> Result<String> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%B", hash])
+        .output()
+        .context("Failed to execute git log")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to read commit message: {}", error);
+    }
+
+    let message = replace_subject(&String::from_utf8_lossy(&output.stdout), subject);
+
+    if rev_parse("HEAD")? == rev_parse(hash)? {
+        let output = Command::new("git")
+            .args(["commit", "--amend", "--only", "-m", &message])
+            .output()
+            .context("Failed to execute git commit --amend")?;
+
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("Reword failed: {}", error);
+        }
+
+        return Ok("Reworded HEAD commit".to_string());
+    }
+
+    let is_ancestor = Command::new("git")
+        .args(["merge-base", "--is-ancestor", hash, "HEAD"])
+        .status()
+        .context("Failed to execute git merge-base")?
+        .success();
+    if !is_ancestor {
+        anyhow::bail!("Reword failed: {} is not on the checked-out branch", &hash[..7.min(hash.len())]);
+    }
+    let output = Command::new("git")
+        .args(["rev-list", "--merges", &format!("{}..HEAD", hash)])
+        .output()
+        .context("Failed to execute git rev-list")?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Reword failed: {}", error);
+    }
+    if !output.stdout.is_empty() {
+        anyhow::bail!(
+            "Reword failed: a merge sits between {} and HEAD, and rewording would flatten it",
+            &hash[..7.min(hash.len())]
+        );
+    }
+
+    let message_path = std::env::temp_dir().join(format!("gitu-reword-{}", std::process::id()));
+    std::fs::write(&message_path, &message).context("Failed to write reword message")?;
+
+    let has_parent = Command::new("git")
+        .args(["rev-parse", "--verify", "-q", &format!("{}^", hash)])
+        .output()
+        .context("Failed to execute git rev-parse")?
+        .status
+        .success();
+    let parent = format!("{}^", hash);
+    let mut args = vec!["rebase", "-i", "--autostash", "--no-autosquash"];
+    if has_parent {
+        args.push(&parent);
+    } else {
+        args.push("--root");
+    }
+
+    // The todo line is found by the commit's abbreviated hash (the same
+    // abbreviation the todo list uses); the editor that git opens for the
+    // reword just copies our message over COMMIT_EDITMSG
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", hash])
+        .output()
+        .context("Failed to execute git rev-parse")?;
+    let short = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let output = Command::new("git")
+        .args(&args)
+        .env("GIT_SEQUENCE_EDITOR", format!("sed -i.bak -e 's/^pick \\({}[0-9a-f]*\\) /reword \\1 /'", short))
+        .env("GIT_EDITOR", format!("cp '{}'", message_path.display()))
+        .output()
+        .context("Failed to execute git rebase");
+    let _ = std::fs::remove_file(&message_path);
+    let output = output?;
+
+    if !output.status.success() {
+        if is_rebasing() {
+            anyhow::bail!(
+                "Reword stopped on conflicts: resolve them and run `git rebase --continue` (or `git rebase --abort`)"
+            );
+        }
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Reword failed: {}", error);
+    }
+
+    Ok(format!("Reworded commit {}", &hash[..7.min(hash.len())]))
+}
+
+fn replace_subject(message: &str, subject: &str) -> String {
+    match message.trim_end().split_once("\n\n") {
+        Some((_, body)) => format!("{}\n\n{}\n", subject, body),
+        None => format!("{}\n", subject),
+    }
+}
+
+/// Resolve a hash, branch, tag or other revision to a full commit hash
+pub fn resolve_commit(rev: &str) -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)])
+        .output()
+        .context("Failed to execute git rev-parse")?;
+
+    if !output.status.success() {
+        anyhow::bail!("'{}' does not name a commit", rev);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn rev_parse(rev: &str) -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", rev])
+        .output()
+        .context("Failed to execute git rev-parse")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Unknown revision {}: {}", rev, error);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Check whether an interactive or regular rebase is in progress
+pub fn is_rebasing() -> bool {
+    ["rebase-merge", "rebase-apply"].iter().any(|dir| git_path_exists(dir))
+}
+
+/// Discard changes in a file (git checkout -- <path>)
+pub fn discard_file(path: &str) -> Result<String> {
+    let output = Command::new("git")
+        .args(["checkout", "--", path])
+        .output()
+        .context("Failed to execute git checkout")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Discard failed: {}", error);
+    }
+
+    Ok(format!("Discarded changes in {}", path))
+}
+
+/// Throw away all staged and unstaged changes to tracked files
+/// (`git reset --hard`), optionally deleting untracked files too (`git clean -fd`)
+pub fn discard_all_changes(remove_untracked: bool) -> Result<String> {
+    let output = Command::new("git")
+        .args(["reset", "--hard", "HEAD"])
+        .output()
+        .context("Failed to execute git reset --hard")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Discard failed: {}", error);
+    }
+
+    if remove_untracked {
+        let output = Command::new("git")
+            .args(["clean", "-fd"])
+            .output()
+            .context("Failed to execute git clean")?;
+
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("Discarded tracked changes, but removing untracked files failed: {}", error);
+        }
+        return Ok("Discarded all changes and removed untracked files".to_string());
+    }
+
+    Ok("Discarded all changes to tracked files".to_string())
+}
+
+/// Merge a branch into the current branch
+pub fn merge_branch(name: &str) -> Result<String> {
+    let output = Command::new("git")
+        .args(["merge", name])
+        .output()
+        .context("Failed to execute git merge")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+
+        if error.contains("conflict") || error.contains("CONFLICT") {
+            return Ok("Merge has conflicts. Resolve them and run 'git merge --continue'".to_string());
+        }
+
+        anyhow::bail!("Merge failed: {}", error);
+    }
+
+    Ok(format!("Merged branch '{}' into current branch", name))
+}
+
+/// Rebase the current branch onto another branch
+pub fn rebase_onto(name: &str) -> Result<String> {
+    let output = Command::new("git")
+        .args(["rebase", name])
+        .output()
+        .context("Failed to execute git rebase")?;
+
+    if !output.status.success() {
+        if is_rebasing() {
+            return Ok("Rebase has conflicts. Resolve them and run 'git rebase --continue'".to_string());
+        }
+
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Rebase failed: {}", error);
+    }
+
+    Ok(format!("Rebased current branch onto '{}'", name))
+}
+
+/// One-line summaries of the commits in a revision range (e.g. "HEAD..branch")
+pub fn get_range_commits(range: &str) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .args(["log", "--oneline", "--no-decorate", "--no-color", range])
+        .output()
+        .context("Failed to execute git log")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to list commits: {}", error);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// `git diff --stat` for a revision range, one line per file plus a summary
+pub fn get_range_diff_stat(range: &str) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .args(["diff", "--stat", "--no-color", range])
+        .output()
+        .context("Failed to execute git diff")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to compute diff stat: {}", error);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .collect())
+}
+
+/// Pull from remote
+pub fn pull(rebase: bool) -> Result<String> {
+    let mut args = vec!["pull"];
+
+    if rebase {
+        args.push("--rebase");
+    }
+
+    let output = Command::new("git")
+        .args(&args)
+        .output()
+        .context("Failed to execute git pull")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Pull failed: {}", error);
+    }
+
+    let msg = if rebase {
+        "Pulled with rebase from remote"
+    } else {
+        "Pulled from remote"
+    };
+
+    Ok(msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple_log() {
+        let input = "* abc1234 Initial commit\n* def5678 Second commit";
+        let commits = parse_log_output(input);
+
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].hash, "abc1234");
+        assert_eq!(commits[0].message, "Initial commit");
+    }
+
+    #[test]
+    fn test_parse_with_graph() {
+        let input = "* | abc1234 Merge commit\n|\\ \n| * def5678 Feature branch";
+        let commits = parse_log_output(input);
+
+        assert!(commits.len() >= 2);
+        assert_eq!(commits[0].hash, "abc1234");
+    }
+
+    #[test]
+    fn test_parse_log_with_parents() {
+        let input = "*   abc1234 def5678 9876fed\t (HEAD -> main) Merge branch 'x'\n|\\  \n| * 9876fed def5678\t Feature\n* def5678 \t Root";
+        let commits = parse_log_output(input);
+
+        assert_eq!(commits.len(), 3);
+        assert_eq!(commits[0].parents, vec!["def5678", "9876fed"]);
+        assert!(commits[0].is_merge());
+        assert_eq!(commits[0].message, "Merge branch 'x'");
+        assert_eq!(commits[0].decorations[0], Decoration::Head);
+        assert_eq!(commits[1].hash, "9876fed");
+        assert!(commits[2].parents.is_empty());
+        assert_eq!(commits[2].message, "Root");
+    }
+
+    #[test]
+    fn test_parse_remote_urls() {
+        let input = "origin\tgit@github.com:org/repo.git (fetch)\norigin\tgit@github.com:org/repo.git (push)\nfork\thttps://example.com/fork.git (fetch)\nfork\tssh://push.example.com/fork.git (push)\n";
+        let urls = parse_remote_urls(input);
+
+        assert_eq!(urls.len(), 2);
+        assert_eq!(urls["origin"], "git@github.com:org/repo.git");
+        assert_eq!(urls["fork"], "https://example.com/fork.git");
+    }
+
+    #[test]
+    fn test_is_valid_author() {
+        assert!(is_valid_author("Jane Doe <jane@example.com>"));
+        assert!(is_valid_author("  Jane <j@x> "));
+        assert!(!is_valid_author("Jane Doe"));
+        assert!(!is_valid_author("<jane@example.com>"));
+        assert!(!is_valid_author("Jane <>"));
+        assert!(!is_valid_author("Jane <jane@example.com"));
+        assert!(!is_valid_author("Jane <a<b>"));
+    }
+
+    #[test]
+    fn test_parse_log_with_dates() {
+        let input = "* abc1234 def5678\t2024-03-01\t (HEAD -> main) Second\n* def5678 \t2024-02-29\t First";
+        let commits = parse_log_output(input);
+
+        assert_eq!(commits[0].date, "2024-03-01");
+        assert_eq!(commits[0].message, "Second");
+        assert_eq!(commits[0].decorations[0], Decoration::Head);
+        assert_eq!(commits[1].date, "2024-02-29");
+        assert_eq!(commits[1].message, "First");
+    }
+
+    #[test]
+    fn test_parse_log_subject_starting_with_parenthesis() {
+        let input = "* abc1234 def5678\t2024-03-01\t (HEAD -> main)\t(bugfix) handle edge case\n* def5678 \t2024-02-29\t\t(wip) first";
+        let commits = parse_log_output(input);
+
+        assert_eq!(commits[0].decorations, vec![Decoration::Head, Decoration::Branch("main".to_string())]);
+        assert_eq!(commits[0].message, "(bugfix) handle edge case");
+        assert!(commits[1].decorations.is_empty());
+        assert_eq!(commits[1].message, "(wip) first");
+
+        // Without the tab, only ref-like lists count as decorations
+        let (decorations, message) = parse_decorations_and_message("(part 1, see #12) split parser");
+        assert!(decorations.is_empty());
+        assert_eq!(message, "(part 1, see #12) split parser");
+        let (decorations, message) = parse_decorations_and_message("(tag: v1.0, origin/main) Release");
+        assert_eq!(decorations.len(), 2);
+        assert_eq!(message, "Release");
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let content = "fn a() {\n<<<<<<< HEAD\n    1\n=======\n    2\n>>>>>>> feature\n}\n========\n<<<<<<< ours\n|||||||\nx\n=======\n>>>>>>> theirs\n<<<<<<< unclosed";
+        assert_eq!(find_conflicts(content), vec![1, 8]);
+        assert_eq!(ConflictMarker::parse("======="), Some(ConflictMarker::Separator));
+        assert_eq!(ConflictMarker::parse("========"), None);
+        assert_eq!(ConflictMarker::parse(">>>>>>> theirs"), Some(ConflictMarker::Theirs));
+    }
+
+    #[test]
+    fn test_parse_search_filter() {
+        assert_eq!(SearchFilter::parse("").unwrap(), None);
+        assert_eq!(SearchFilter::parse("@ann").unwrap(), Some(SearchFilter::Author("ann".to_string())));
+        assert_eq!(
+            SearchFilter::parse("S:get_commits(").unwrap(),
+            Some(SearchFilter::Pickaxe { pattern: "get_commits(".to_string(), regex: false })
+        );
+        assert_eq!(
+            SearchFilter::parse("G:fn \\w+_diff").unwrap(),
+            Some(SearchFilter::Pickaxe { pattern: "fn \\w+_diff".to_string(), regex: true })
+        );
+        assert_eq!(SearchFilter::parse("S:").unwrap_err().to_string(), "pickaxe pattern is empty");
+        assert!(SearchFilter::parse("G:").is_err());
+        assert_eq!(
+            SearchFilter::parse("fix S:").unwrap(),
+            Some(SearchFilter::Message("fix S:".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_diff_with_prefix() {
+        let input = "commit abc\n\ndiff --git a/src/main.rs b/src/main.rs\nindex 111..222 100644\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1 +1 @@\n-old\n+new\n";
+        let diff = parse_commit_diff(input);
+
+        assert_eq!(diff.files.len(), 1);
+        assert_eq!(diff.files[0].filename, "src/main.rs");
+        assert_eq!(diff.files[0].diff_content, "@@ -1 +1 @@\n-old\n+new\n");
+    }
+
+    #[test]
+    fn test_parse_combined_diff() {
+        let input = "commit abc\nMerge: 111 222\n\ndiff --cc src/main.rs\nindex 111,222..333\n--- a/src/main.rs\n+++ b/src/main.rs\n@@@ -1,1 -1,1 +1,1 @@@\n- a\n -b\n++c\n";
+        let diff = parse_commit_diff(input);
+
+        assert_eq!(diff.files.len(), 1);
+        assert_eq!(diff.files[0].filename, "src/main.rs");
+        assert_eq!(diff.files[0].diff_content, "@@@ -1,1 -1,1 +1,1 @@@\n- a\n -b\n++c\n");
+    }
+
+    #[test]
+    fn test_parse_diff_without_prefix() {
+        let input = "diff --git src/main.rs src/main.rs\nindex 111..222 100644\n--- src/main.rs\n+++ src/main.rs\n@@ -1 +1 @@\n-old\n+new\n";
+        let diff = parse_commit_diff(input);
+
+        assert_eq!(diff.files.len(), 1);
+        assert_eq!(diff.files[0].filename, "src/main.rs");
+    }
+
+    #[test]
+    fn test_parse_diff_without_prefix_single_letter_dir() {
+        let input = "diff --git a/lib.rs a/lib.rs\n--- a/lib.rs\n+++ a/lib.rs\n@@ -1 +1 @@\n-old\n+new\n";
+        let diff = parse_commit_diff(input);
+
+        assert_eq!(diff.files[0].filename, "a/lib.rs");
+    }
+
+    #[test]
+    fn test_parse_diff_mnemonic_prefix_and_deletion() {
+        let input = "diff --git c/keep.txt w/keep.txt\n--- c/keep.txt\n+++ w/keep.txt\n@@ -1 +1 @@\n-a\n+b\ndiff --git a/gone.txt b/gone.txt\ndeleted file mode 100644\n--- a/gone.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n--- not a header\n";
+        let diff = parse_commit_diff(input);
+
+        assert_eq!(diff.files.len(), 2);
+        assert_eq!(diff.files[0].filename, "keep.txt");
+        assert_eq!(diff.files[1].filename, "gone.txt");
+        assert!(diff.files[1].diff_content.contains("--- not a header"));
+    }
+
+    #[test]
+    fn test_parse_diff_notes_for_hunkless_files() {
+        let input = "diff --git a/run.sh b/run.sh\nold mode 100644\nnew mode 100755\ndiff --git a/logo.png b/logo.png\nindex 1234567..89abcde 100644\nBinary files a/logo.png and b/logo.png differ\ndiff --git a/main.rs b/main.rs\n--- a/main.rs\n+++ b/main.rs\n@@ -1 +1 @@\n-a\n+b\n";
+        let diff = parse_commit_diff(input);
+
+        assert_eq!(diff.files.len(), 3);
+        assert_eq!(
+            diff.files[0].note.as_deref(),
+            Some("(no textual changes — mode change 100644 → 100755)")
+        );
+        assert_eq!(diff.files[0].filename, "run.sh");
+        assert_eq!(diff.files[1].note.as_deref(), Some("(binary file — no textual diff)"));
+        assert_eq!(diff.files[2].note, None);
+    }
+
+    #[test]
+    fn test_parse_diff_mode_change() {
+        let input = "diff --git a/run.sh b/run.sh\nold mode 100644\nnew mode 100755\ndiff --git a/build.sh b/build.sh\nold mode 100755\nnew mode 100644\nindex 1234567..89abcde\n--- a/build.sh\n+++ b/build.sh\n@@ -1 +1 @@\n-a\n+b\n";
+        let diff = parse_commit_diff(input);
+
+        assert_eq!(diff.files.len(), 2);
+        assert_eq!(
+            diff.files[0].mode_change,
+            Some(("100644".to_string(), "100755".to_string()))
+        );
+        assert!(!diff.files[0].diff_content.contains("old mode"));
+        assert_eq!(
+            diff.files[1].mode_change,
+            Some(("100755".to_string(), "100644".to_string()))
+        );
+        assert_eq!(diff.files[1].note, None);
+        assert!(diff.files[1].diff_content.starts_with("@@ -1 +1 @@"));
+    }
+
+    #[test]
+    fn test_parse_worktrees() {
+        let input = "worktree /repo\nHEAD abc123\nbranch refs/heads/main\n\nworktree /repo-feature\nHEAD def456\ndetached\n\n";
+        let worktrees = parse_worktree_output(input);
+
+        assert_eq!(worktrees.len(), 2);
+        assert_eq!(worktrees[0].path, "/repo");
+        assert_eq!(worktrees[0].branch.as_deref(), Some("main"));
+        assert_eq!(worktrees[1].head, "def456");
+        assert_eq!(worktrees[1].branch, None);
+    }
+
+    #[test]
+    fn test_parse_submodule_status() {
+        let input = " 1234abcd libs/core (v1.0)\n-5678ef01 vendor/uninit\n+9abcdef0 libs/ui (heads/main)\n";
+        let paths = parse_submodule_status(input);
+
+        assert_eq!(paths, vec!["libs/core", "vendor/uninit", "libs/ui"]);
+    }
+
+    #[test]
+    fn test_parse_status_output() {
+        let files = parse_status_output("MM both.rs\n?? new.txt\nA  added.rs\nUU fight.rs\nAA twice.rs\n");
+        let rows: Vec<(&str, bool, FileStatus)> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.staged, f.status.clone()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("both.rs", true, FileStatus::Modified),
+                ("both.rs", false, FileStatus::Modified),
+                ("new.txt", false, FileStatus::Untracked),
+                ("added.rs", true, FileStatus::Added),
+                ("fight.rs", false, FileStatus::Conflicted),
+                ("twice.rs", false, FileStatus::Conflicted),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_tag_output() {
+        let tags = parse_tag_output("v1.1\tabc1234\tsigned\tRelease 1.1\nlight\tdef5678\t\t\n\n");
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].name, "v1.1");
+        assert_eq!(tags[0].target, "abc1234");
+        assert_eq!(tags[0].subject, "Release 1.1");
+        assert_eq!(tags[0].signature, TagSignature::Unchecked);
+        assert_eq!(tags[1].name, "light");
+        assert_eq!(tags[1].subject, "");
+        assert_eq!(tags[1].signature, TagSignature::Unsigned);
+    }
+
+    #[test]
+    fn test_parse_signer() {
+        let stderr = "gpg: Signature made Thu Oct 15 11:32:07 2026 UTC\ngpg:                issuer \"t@t\"\ngpg: Good signature from \"Jo Doe <jo@example.com>\" [ultimate]\n";
+        assert_eq!(parse_signer(stderr).as_deref(), Some("Jo Doe <jo@example.com>"));
+        assert_eq!(parse_signer("error: no signature found"), None);
+    }
+
+    #[test]
+    fn test_parse_verification() {
+        let good = "[GNUPG:] NEWSIG\n[GNUPG:] GOODSIG 0123456789ABCDEF Jo Doe <jo@example.com>\n";
+        assert_eq!(parse_verification(true, good), TagSignature::Good("Jo Doe <jo@example.com>".to_string()));
+        let bad = "[GNUPG:] NEWSIG\n[GNUPG:] BADSIG 0123456789ABCDEF Jo Doe <jo@example.com>\n";
+        assert_eq!(parse_verification(false, bad), TagSignature::Bad);
+        let no_key = "[GNUPG:] ERRSIG 0123456789ABCDEF 1 10 00 1700000000 9 -\n[GNUPG:] NO_PUBKEY 0123456789ABCDEF\n";
+        assert_eq!(parse_verification(false, no_key), TagSignature::Unverifiable("public key missing".to_string()));
+        assert_eq!(
+            parse_verification(false, "error: cannot run gpg: No such file or directory\n"),
+            TagSignature::Unverifiable("cannot run gpg: No such file or directory".to_string())
+        );
+        assert_eq!(
+            parse_verification(false, "error: v1: cannot verify a non-tag object of type commit.\n"),
+            TagSignature::Unverifiable("lightweight tag".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_rejected_tags() {
+        let stderr = "To /tmp/remote\n ! [rejected]        v1 -> v1 (already exists)\n ! [rejected]        main -> main (fetch first)\nerror: failed to push some refs to '/tmp/remote'\n";
+        assert_eq!(parse_rejected_tags(stderr), vec!["v1"]);
+        assert!(parse_rejected_tags("fatal: could not read from remote").is_empty());
+    }
+
+    #[test]
+    fn test_parse_bisect_output() {
+        let progress = "Bisecting: 2 revisions left to test after this (roughly 1 step)\n[295a92ef] c3\n";
+        assert_eq!(
+            parse_bisect_output(progress),
+            "Bisect: 2 revisions left to test after this (roughly 1 step)"
+        );
+
+        let done = "295a92ef3d5a is the first bad commit\ncommit 295a92ef3d5a\nAuthor: t <t@t>\n\n    Break the build\n";
+        assert_eq!(parse_bisect_output(done), "First bad commit: 295a92e Break the build");
+
+        let waiting = "status: waiting for good commit(s), bad commit known\n";
+        assert_eq!(parse_bisect_output(waiting), "Bisect: waiting for good commit(s), bad commit known");
+    }
+
+    #[test]
+    fn test_parse_reflog_output() {
+        let output = "abc1234\tmain@{0}\treset: moving to HEAD~1\ndef5678\tmain@{1}\tcommit: Add\tTab\n";
+        let entries = parse_reflog_output(output);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].hash, "abc1234");
+        assert_eq!(entries[0].selector, "main@{0}");
+        assert_eq!(entries[0].subject, "reset: moving to HEAD~1");
+        assert_eq!(entries[1].subject, "commit: Add\tTab");
+    }
+
+    #[test]
+    fn test_parse_ahead_behind() {
+        assert_eq!(parse_ahead_behind("2\t3\n"), Some((2, 3)));
+        assert_eq!(parse_ahead_behind("0\t0\n"), Some((0, 0)));
+        assert_eq!(parse_ahead_behind(""), None);
+    }
+
+    #[test]
+    fn test_replace_subject_keeps_body() {
+        assert_eq!(replace_subject("Old\n\nBody line\n", "New"), "New\n\nBody line\n");
+        assert_eq!(replace_subject("Old\n", "New"), "New\n");
+    }
+
+    #[test]
+    fn test_parse_numstat() {
+        let stats = parse_numstat("3\t1\tsrc/main.rs\n-\t-\tlogo.png\n");
+        assert_eq!(stats.get("src/main.rs"), Some(&Some((3, 1))));
+        assert_eq!(stats.get("logo.png"), Some(&None));
+    }
+
+    #[test]
+    fn test_parse_left_right() {
+        let divergence = parse_left_right("<aaa111\n<bbb222\n>ccc333\n");
+        assert_eq!(divergence.ahead, HashSet::from(["aaa111".to_string(), "bbb222".to_string()]));
+        assert_eq!(divergence.behind, HashSet::from(["ccc333".to_string()]));
+    }
+
+    #[test]
+    fn test_find_repo_root_from_subdirectory() {
+        let root = std::env::temp_dir().join(format!("gitu-root-test-{}", std::process::id()));
+        let subdir = root.join("src").join("nested");
+        std::fs::create_dir_all(&subdir).unwrap();
+        let init = Command::new("git").args(["init", "-q"]).arg(&root).status().unwrap();
+        assert!(init.success());
+        let expected = root.canonicalize().unwrap();
+
+        let found = find_repo_root(&subdir).map(|root| PathBuf::from(root).canonicalize().unwrap());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(found.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_reword_refuses_to_flatten_a_merge() {
+        use crate::test_repo::git;
+        crate::test_repo::in_scratch_repo("git::tests::test_reword_refuses_to_flatten_a_merge", || {
+            let commit = |file: &str, subject: &str| {
+                std::fs::write(file, subject).unwrap();
+                git(&["add", file]);
+                git(&["commit", "-q", "-m", subject]);
+            };
+            commit("target.txt", "Target");
+            let target = git(&["rev-parse", "HEAD"]);
+            git(&["switch", "-q", "-c", "side"]);
+            commit("side.txt", "Side");
+            git(&["switch", "-q", "main"]);
+            commit("main.txt", "Main");
+            git(&["merge", "-q", "--no-ff", "-m", "Merge side", "side"]);
+            let head = git(&["rev-parse", "HEAD"]);
+
+            let error = reword_commit(&target, "Reworded").unwrap_err().to_string();
+            assert!(error.contains("merge"), "{}", error);
+            assert_eq!(git(&["rev-parse", "HEAD"]), head);
+
+            // With the history above it linear again, the reword goes through
+            git(&["reset", "-q", "--hard", "HEAD^"]);
+            commit("above.txt", "Above");
+            reword_commit(&target, "Reworded").unwrap();
+            assert_eq!(git(&["log", "--format=%s", "-4"]), "Above\nMain\nReworded\nInitial");
+        });
+    }
+
+    #[test]
+    fn test_stash_patch_takes_only_the_chosen_hunks() {
+        use crate::test_repo::git;
+        crate::test_repo::in_scratch_repo("git::tests::test_stash_patch_takes_only_the_chosen_hunks", || {
+            let original: Vec<String> = (1..=20).map(|i| format!("line {}", i)).collect();
+            std::fs::write("f.txt", original.join("\n") + "\n").unwrap();
+            std::fs::write("g.txt", "g\n").unwrap();
+            git(&["add", "f.txt", "g.txt"]);
+            git(&["commit", "-q", "-m", "Files"]);
+
+            std::fs::write("g.txt", "g staged\n").unwrap();
+            git(&["add", "g.txt"]);
+            let mut edited = original.clone();
+            edited[0] = "first changed".to_string();
+            edited[19] = "last changed".to_string();
+            std::fs::write("f.txt", edited.join("\n") + "\n").unwrap();
+
+            // The diff header plus its first hunk only
+            let diff = git(&["diff", "f.txt"]) + "\n";
+            let second_hunk = diff.match_indices("\n@@").nth(1).unwrap().0;
+            let patch = &diff[..=second_hunk];
+            stash_patch(patch, "first line").unwrap();
+
+            assert_eq!(git(&["stash", "list", "--format=%s"]), "On main: first line");
+            let mut stashed = original.clone();
+            stashed[0] = "first changed".to_string();
+            assert_eq!(git(&["show", "stash@{0}:f.txt"]), stashed.join("\n"));
+            assert_eq!(git(&["show", "stash@{0}^2:g.txt"]), "g staged");
+
+            // The index is as it was
+            assert_eq!(git(&["diff", "--cached", "--name-only"]), "g.txt");
+            assert_eq!(git(&["show", ":g.txt"]), "g staged");
+
+            // Only the stashed hunk left the working tree
+            let mut remaining = original.clone();
+            remaining[19] = "last changed".to_string();
+            assert_eq!(std::fs::read_to_string("f.txt").unwrap(), remaining.join("\n") + "\n");
+        });
+    }
+
+    #[test]
+    fn test_switch_previous_branch_returns_to_a_detached_checkout() {
+        use crate::test_repo::git;
+        crate::test_repo::in_scratch_repo("git::tests::test_switch_previous_branch_returns_to_a_detached_checkout", || {
+            let error = switch_previous_branch().unwrap_err().to_string();
+            assert_eq!(error, "No previous branch to switch to");
+
+            git(&["commit", "-q", "--allow-empty", "-m", "Second"]);
+            git(&["checkout", "-q", "--detach", "HEAD^"]);
+            git(&["checkout", "-q", "main"]);
+            assert_eq!(
+                switch_previous_branch().unwrap(),
+                "Switched back to the previous checkout (detached HEAD)"
+            );
+            assert_eq!(switch_previous_branch().unwrap(), "Switched back to branch 'main'");
+        });
+    }
+
+    #[test]
+    fn test_get_config_bool() {
+        use crate::test_repo::git;
+        crate::test_repo::in_scratch_repo("git::tests::test_get_config_bool", || {
+            assert_eq!(get_config_bool("color.ui").unwrap(), None);
+            for (value, expected) in [("off", false), ("No", false), ("0", false), ("yes", true), ("1", true)] {
+                git(&["config", "color.ui", value]);
+                assert_eq!(get_config_bool("color.ui").unwrap(), Some(expected), "{}", value);
+            }
+            git(&["config", "color.ui", "never"]);
+            assert!(get_config_bool("color.ui").is_err());
+        });
+    }
+
+    #[test]
+    fn test_parse_diff_git_header_with_spaces() {
+        assert_eq!(parse_diff_git_header("a/my file.txt b/my file.txt"), ("my file.txt".to_string(), true));
+        assert_eq!(parse_diff_git_header("c/a b/c w/a b/c"), ("a b/c".to_string(), true));
+        assert_eq!(parse_diff_git_header("my file.txt my file.txt"), ("my file.txt".to_string(), false));
+        assert_eq!(parse_diff_git_header("a/old name.txt b/new name.txt"), ("new name.txt".to_string(), true));
+        assert_eq!(parse_diff_git_header("a/src/main.rs b/src/main.rs"), ("src/main.rs".to_string(), true));
+    }
+
+    #[test]
+    fn test_parse_ls_tree() {
+        let output = "100644 blob 1111111111111111111111111111111111111111\tsrc/main.rs\0\
+                      100644 blob 2222222222222222222222222222222222222222\tdocs/a \"quoted\"\tname.md\0\
+                      160000 commit 3333333333333333333333333333333333333333\tvendor/lib\0";
+        assert_eq!(parse_ls_tree(output), vec!["src/main.rs", "docs/a \"quoted\"\tname.md"]);
+    }
+
+    #[test]
+    fn test_strip_message_comments() {
+        assert_eq!(strip_message_comments("Fix\n\nBody\n# comment\n", "#"), "Fix\n\nBody");
+        assert_eq!(strip_message_comments("\n# Only comments\n#\n", "#"), "");
+        assert_eq!(strip_message_comments("Fix #12\n#12 too\n; comment\n", ";"), "Fix #12\n#12 too");
+    }
+
+    #[test]
+    fn test_commit_from_file_uses_comment_char() {
+        use crate::test_repo::git;
+        crate::test_repo::in_scratch_repo("git::tests::test_commit_from_file_uses_comment_char", || {
+            git(&["config", "core.commentChar", ";"]);
+            std::fs::write("a.txt", "a\n").unwrap();
+            git(&["add", "a.txt"]);
+            assert_eq!(get_comment_char(), ";");
+
+            let path = Path::new(&get_git_dir().unwrap()).join("COMMIT_EDITMSG");
+            std::fs::write(&path, "Add a\n\n#1 stays in the body\n; help line\n").unwrap();
+            commit_from_file(&path, false, ";").unwrap();
+            assert_eq!(git(&["log", "-1", "--format=%B"]), "Add a\n\n#1 stays in the body");
+        });
+    }
+
+    #[test]
+    fn test_parse_upstreams() {
+        let upstreams = parse_upstreams("main\0origin/main\0[ahead 1]\nwip\0\0\nold\0origin/old\0[gone]\n");
+        assert_eq!(upstreams.len(), 1);
+        assert_eq!(upstreams["main"], "origin/main");
+    }
+
+    #[test]
+    fn test_branch_without_upstream_keeps_bracketed_subject() {
+        use crate::test_repo::git;
+        crate::test_repo::in_scratch_repo("git::tests::test_branch_without_upstream_keeps_bracketed_subject", || {
+            git(&["switch", "-q", "-c", "a-much-longer-branch-name"]);
+            git(&["commit", "-q", "--allow-empty", "-m", "[WIP] subject"]);
+            git(&["remote", "add", "origin", "/nonexistent"]);
+            git(&["config", "branch.main.remote", "origin"]);
+            git(&["config", "branch.main.merge", "refs/heads/main"]);
+
+            let branches = get_branches().unwrap();
+            let wip = branches.iter().find(|b| b.name == "a-much-longer-branch-name").unwrap();
+            assert_eq!(wip.upstream, None);
+            assert_eq!(wip.commit_message, "[WIP] subject");
+            let main = branches.iter().find(|b| b.name == "main").unwrap();
+            assert_eq!(main.upstream, None, "origin/main was never fetched");
+            assert!(main.commit_message.ends_with("Initial"));
+            assert_eq!(main.commit_hash.len(), wip.commit_hash.len());
+        });
+    }
+
+    #[test]
+    fn test_template_to_git_format() {
+        assert_eq!(template_to_git_format("{hash} {subject}"), "%h %s");
+        assert_eq!(template_to_git_format("- {subject} ({author}, 100%)"), "- %s (%an, 100%%)");
     }
 }
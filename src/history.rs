@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+/// Number of search queries remembered across sessions
+pub const MAX_SEARCH_HISTORY: usize = 50;
+
+/// Reads saved search queries, oldest first. A missing or unreadable
+/// file just means there is no history yet.
+pub fn load_search_history() -> Vec<String> {
+    history_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|text| {
+            text.lines()
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn save_search_history(entries: &[String]) -> Result<()> {
+    let Some(path) = history_path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    let mut text = entries.join("\n");
+    text.push('\n');
+    std::fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Appends a query, skipping repeats of the latest entry and dropping
+/// the oldest ones past the limit
+pub fn push_search_history(entries: &mut Vec<String>, query: &str) {
+    let query = query.trim();
+    if query.is_empty() || query.contains('\n') {
+        return;
+    }
+    if entries.last().map(|last| last.as_str()) == Some(query) {
+        return;
+    }
+
+    entries.push(query.to_string());
+    if entries.len() > MAX_SEARCH_HISTORY {
+        let excess = entries.len() - MAX_SEARCH_HISTORY;
+        entries.drain(..excess);
+    }
+}
+
+/// `$XDG_STATE_HOME/gitu/search_history` (or `~/.local/state/gitu/search_history`)
+fn history_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(base.join("gitu").join("search_history"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_search_history() {
+        let mut entries = Vec::new();
+        push_search_history(&mut entries, "fix");
+        push_search_history(&mut entries, "fix");
+        push_search_history(&mut entries, "@alice");
+        push_search_history(&mut entries, "fix");
+        push_search_history(&mut entries, "  ");
+        assert_eq!(entries, vec!["fix", "@alice", "fix"]);
+
+        for i in 0..MAX_SEARCH_HISTORY {
+            push_search_history(&mut entries, &format!("query {}", i));
+        }
+        assert_eq!(entries.len(), MAX_SEARCH_HISTORY);
+        assert_eq!(entries[0], "query 0");
+    }
+}
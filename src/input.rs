@@ -1,4 +1,6 @@
-use crate::ui::{App, Panel};
+use crate::git::BisectMark;
+use crate::keymap::Macro;
+use crate::ui::{App, MessageType, Panel};
 use anyhow::Result;
 use crossterm::event::KeyCode;
 
@@ -13,16 +15,54 @@ pub fn handle_key_event(app: &mut App, key_code: KeyCode) -> Result<()> {
     }
 
     // Handle input modes
-    if app.search_mode {
+    if app.confirm.is_some() {
+        handle_confirm_popup(app, key_code);
+    } else if app.discard_all_input.is_some() {
+        handle_discard_all_popup(app, key_code);
+    } else if app.interactive_staging.is_some() {
+        handle_interactive_staging(app, key_code);
+    } else if app.line_staging.is_some() {
+        handle_line_staging(app, key_code);
+    } else if app.stash_target_visible {
+        handle_stash_target_popup(app, key_code);
+    } else if app.remote_url_input_mode {
+        handle_remote_url_input_mode(app, key_code);
+    } else if app.remotes_visible {
+        handle_remotes_popup(app, key_code);
+    } else if app.worktree_input_mode {
+        handle_worktree_input_mode(app, key_code);
+    } else if app.sync_upstream.is_some() {
+        handle_sync_popup(app, key_code);
+    } else if app.branch_compare.is_some() {
+        handle_branch_compare_popup(app, key_code);
+    } else if app.reflog_branch.is_some() {
+        handle_reflog_popup(app, key_code);
+    } else if app.worktrees_visible {
+        handle_worktrees_popup(app, key_code);
+    } else if app.search_mode {
         handle_search_mode(app, key_code)?;
     } else if app.branch_input_mode {
         handle_branch_input_mode(app, key_code);
+    } else if app.quick_commit_input.is_some() {
+        handle_quick_commit_mode(app, key_code);
     } else if app.commit_message_mode {
         handle_commit_message_mode(app, key_code);
     } else if app.stash_input_mode {
         handle_stash_input_mode(app, key_code);
     } else if app.new_branch_input_mode {
         handle_new_branch_mode(app, key_code);
+    } else if app.tag_input_mode {
+        handle_tag_input_mode(app, key_code);
+    } else if app.goto_input_mode {
+        handle_goto_input_mode(app, key_code)?;
+    } else if app.rename_source.is_some() {
+        handle_rename_mode(app, key_code);
+    } else if app.amend_metadata.is_some() {
+        handle_amend_metadata_mode(app, key_code);
+    } else if app.file_filter_input_mode {
+        handle_file_filter_mode(app, key_code);
+    } else if app.browse_hash.is_some() {
+        handle_commit_browser(app, key_code);
     } else if app.tree_view_mode {
         handle_tree_view_mode(app, key_code)?;
     } else {
@@ -32,11 +72,116 @@ pub fn handle_key_event(app: &mut App, key_code: KeyCode) -> Result<()> {
     Ok(())
 }
 
+fn handle_interactive_staging(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Char('y') => app.interactive_decide(true),
+        KeyCode::Char('n') => app.interactive_decide(false),
+        KeyCode::Char('s') => app.interactive_split(),
+        KeyCode::Char('q') => app.finish_interactive_staging(),
+        KeyCode::Esc => app.cancel_interactive_staging(),
+        _ => {}
+    }
+}
+
+fn handle_line_staging(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Down | KeyCode::Char('j') => app.line_staging_move(true, false),
+        KeyCode::Up | KeyCode::Char('k') => app.line_staging_move(false, false),
+        KeyCode::Char('J') => app.line_staging_move(true, true),
+        KeyCode::Char('K') => app.line_staging_move(false, true),
+        KeyCode::Char(' ') | KeyCode::Enter => app.apply_line_staging(),
+        KeyCode::Esc | KeyCode::Char('q') => app.cancel_line_staging(),
+        _ => {}
+    }
+}
+
+fn handle_worktrees_popup(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('W') => app.close_worktrees(),
+        KeyCode::Char('q') => app.quit(),
+        KeyCode::Char('n') => app.enter_worktree_input_mode(),
+        KeyCode::Enter => app.switch_to_selected_worktree(),
+        KeyCode::Down | KeyCode::Char('j') => app.next_worktree(),
+        KeyCode::Up | KeyCode::Char('k') => app.previous_worktree(),
+        _ => {}
+    }
+}
+
+fn handle_sync_popup(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => app.close_sync_commits(),
+        KeyCode::Enter => app.goto_sync_commit(),
+        KeyCode::Char('P') if !app.sync_incoming => app.sync_from_popup(),
+        KeyCode::Char('U') if app.sync_incoming => app.sync_from_popup(),
+        KeyCode::Down | KeyCode::Char('j') => app.next_sync_commit(),
+        KeyCode::Up | KeyCode::Char('k') => app.previous_sync_commit(),
+        _ => {}
+    }
+}
+
+fn handle_branch_compare_popup(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => app.close_branch_compare(),
+        KeyCode::Enter => app.goto_branch_compare_commit(),
+        KeyCode::Tab | KeyCode::Char('c') => app.next_branch_variant(),
+        KeyCode::Down | KeyCode::Char('j') => app.next_branch_compare_commit(),
+        KeyCode::Up | KeyCode::Char('k') => app.previous_branch_compare_commit(),
+        _ => {}
+    }
+}
+
+fn handle_reflog_popup(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => app.close_branch_reflog(),
+        KeyCode::Enter | KeyCode::Char('r') => app.reset_branch_to_reflog_entry(),
+        KeyCode::Down | KeyCode::Char('j') => app.next_reflog_entry(),
+        KeyCode::Up | KeyCode::Char('k') => app.previous_reflog_entry(),
+        _ => {}
+    }
+}
+
+fn handle_stash_target_popup(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc => app.close_stash_target_picker(),
+        KeyCode::Char('q') => app.quit(),
+        KeyCode::Enter => app.apply_stash_on_target_branch(false),
+        KeyCode::Char('p') => app.apply_stash_on_target_branch(true),
+        KeyCode::Down | KeyCode::Char('j') => app.next_stash_target(),
+        KeyCode::Up | KeyCode::Char('k') => app.previous_stash_target(),
+        _ => {}
+    }
+}
+
+fn handle_remotes_popup(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc => app.close_remotes(),
+        KeyCode::Char('q') => app.quit(),
+        KeyCode::Char('a') => app.fetch_all_remotes(),
+        KeyCode::Enter => app.fetch_selected_remote(),
+        KeyCode::Char('e') => app.enter_remote_url_input_mode(),
+        KeyCode::Down | KeyCode::Char('j') => app.next_remote(),
+        KeyCode::Up | KeyCode::Char('k') => app.previous_remote(),
+        _ => {}
+    }
+}
+
+fn handle_worktree_input_mode(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc => app.exit_worktree_input_mode(),
+        KeyCode::Enter => app.execute_add_worktree(),
+        KeyCode::Backspace => app.delete_worktree_char(),
+        KeyCode::Char(c) => app.add_worktree_char(c),
+        _ => {}
+    }
+}
+
 fn handle_search_mode(app: &mut App, key_code: KeyCode) -> Result<()> {
     match key_code {
         KeyCode::Esc => app.exit_search_mode(),
         KeyCode::Enter => app.execute_search()?,
         KeyCode::Backspace => app.delete_search_char(),
+        KeyCode::Up => app.previous_search_history(),
+        KeyCode::Down => app.next_search_history(),
         KeyCode::Char(c) => app.add_search_char(c),
         _ => {}
     }
@@ -53,10 +198,31 @@ fn handle_branch_input_mode(app: &mut App, key_code: KeyCode) {
     }
 }
 
+fn handle_confirm_popup(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Char('y') | KeyCode::Enter => app.accept_confirm(),
+        KeyCode::Char('n') | KeyCode::Esc => app.cancel_confirm(),
+        _ => {}
+    }
+}
+
+fn handle_discard_all_popup(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc => app.close_discard_all(),
+        KeyCode::Enter => app.execute_discard_all(),
+        KeyCode::Tab => app.toggle_discard_all_untracked(),
+        KeyCode::Backspace => app.delete_discard_all_char(),
+        KeyCode::Char(c) => app.add_discard_all_char(c),
+        _ => {}
+    }
+}
+
 fn handle_commit_message_mode(app: &mut App, key_code: KeyCode) {
     match key_code {
         KeyCode::Esc => app.exit_commit_message_mode(),
         KeyCode::Enter => app.execute_commit(),
+        KeyCode::Tab if app.amend_mode => app.toggle_amend_include_unstaged(),
+        KeyCode::Tab => app.toggle_commit_allow_empty(),
         KeyCode::Backspace => app.delete_commit_char(),
         KeyCode::Char(c) => app.add_commit_char(c),
         _ => {}
@@ -73,6 +239,26 @@ fn handle_stash_input_mode(app: &mut App, key_code: KeyCode) {
     }
 }
 
+fn handle_remote_url_input_mode(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc => app.exit_remote_url_input_mode(),
+        KeyCode::Enter => app.execute_set_remote_url(),
+        KeyCode::Backspace => app.delete_remote_url_char(),
+        KeyCode::Char(c) => app.add_remote_url_char(c),
+        _ => {}
+    }
+}
+
+fn handle_quick_commit_mode(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc => app.exit_quick_commit_mode(),
+        KeyCode::Enter => app.execute_quick_commit(),
+        KeyCode::Backspace => app.delete_quick_commit_char(),
+        KeyCode::Char(c) => app.add_quick_commit_char(c),
+        _ => {}
+    }
+}
+
 fn handle_new_branch_mode(app: &mut App, key_code: KeyCode) {
     match key_code {
         KeyCode::Esc => app.exit_new_branch_mode(),
@@ -83,22 +269,93 @@ fn handle_new_branch_mode(app: &mut App, key_code: KeyCode) {
     }
 }
 
+fn handle_goto_input_mode(app: &mut App, key_code: KeyCode) -> Result<()> {
+    match key_code {
+        KeyCode::Esc => app.exit_goto_input_mode(),
+        KeyCode::Enter => app.execute_goto_commit()?,
+        KeyCode::Backspace => app.delete_goto_char(),
+        KeyCode::Char(c) => app.add_goto_char(c),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_rename_mode(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc => app.exit_rename_mode(),
+        KeyCode::Enter => app.execute_rename(),
+        KeyCode::Backspace => app.delete_rename_char(),
+        KeyCode::Char(c) => app.add_rename_char(c),
+        _ => {}
+    }
+}
+
+fn handle_amend_metadata_mode(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc => app.exit_amend_metadata_mode(),
+        KeyCode::Enter => app.confirm_amend_metadata(),
+        KeyCode::Tab => app.toggle_amend_metadata_field(),
+        KeyCode::Backspace => app.delete_amend_metadata_char(),
+        KeyCode::Char(c) => app.add_amend_metadata_char(c),
+        _ => {}
+    }
+}
+
+fn handle_tag_input_mode(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc => app.exit_tag_input_mode(),
+        KeyCode::Enter => app.execute_create_tag(),
+        KeyCode::Tab => app.toggle_tag_sign(),
+        KeyCode::Backspace => app.delete_tag_char(),
+        KeyCode::Char(c) => app.add_tag_char(c),
+        _ => {}
+    }
+}
+
+fn handle_file_filter_mode(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Esc => app.clear_file_filter(),
+        KeyCode::Enter => app.confirm_file_filter(),
+        KeyCode::Backspace => app.delete_file_filter_char(),
+        KeyCode::Char(c) => app.add_file_filter_char(c),
+        _ => {}
+    }
+}
+
+fn handle_commit_browser(app: &mut App, key_code: KeyCode) {
+    let viewing_file = app.browse_file.is_some();
+    match key_code {
+        KeyCode::Char('q') => app.quit(),
+        KeyCode::Char('?') => app.help_visible = true,
+        KeyCode::Esc if viewing_file => app.browse_back(),
+        KeyCode::Esc | KeyCode::Char('e') => app.close_commit_browser(),
+        KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => app.browse_back(),
+        KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') if !viewing_file => app.open_browse_entry(),
+        KeyCode::Char('y') => app.copy_browse_path(),
+        KeyCode::PageUp if viewing_file => app.scroll_browse_file(-10),
+        KeyCode::PageDown if viewing_file => app.scroll_browse_file(10),
+        KeyCode::Down | KeyCode::Char('j') if viewing_file => app.scroll_browse_file(1),
+        KeyCode::Up | KeyCode::Char('k') if viewing_file => app.scroll_browse_file(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.next_browse_entry(),
+        KeyCode::Up | KeyCode::Char('k') => app.previous_browse_entry(),
+        _ => {}
+    }
+}
+
 fn handle_tree_view_mode(app: &mut App, key_code: KeyCode) -> Result<()> {
     match key_code {
         KeyCode::Char('q') => app.quit(),
         KeyCode::Char('?') => app.help_visible = true,
         KeyCode::Char('t') => app.toggle_tree_view()?,
+        KeyCode::Esc if app.file_filter.is_some() && !app.tree_file_selected => app.clear_file_filter(),
         KeyCode::Esc => app.exit_tree_view(),
-        KeyCode::PageUp => {
-            if app.tree_file_selected {
-                app.scroll_diff_page_up();
-            }
-        }
-        KeyCode::PageDown => {
-            if app.tree_file_selected {
-                app.scroll_diff_page_down();
-            }
-        }
+        KeyCode::Char('/') if !app.tree_file_selected => app.enter_file_filter_mode(),
+        KeyCode::Char('y') => app.copy_tree_file_path(),
+        KeyCode::Char('H') if app.tree_file_selected => app.copy_diff_hunk(),
+        KeyCode::Char('m') => app.cycle_diff_parent()?,
+        KeyCode::Char('I') => app.toggle_ignore_whitespace(),
+        KeyCode::PageUp if app.tree_file_selected => app.scroll_diff_page_up(),
+        KeyCode::PageDown if app.tree_file_selected => app.scroll_diff_page_down(),
         KeyCode::Down | KeyCode::Char('j') => {
             if app.tree_file_selected {
                 app.scroll_diff_down();
@@ -119,7 +376,103 @@ fn handle_tree_view_mode(app: &mut App, key_code: KeyCode) -> Result<()> {
     Ok(())
 }
 
+/// Whether a prompt or popup is waiting for keys; mirrors the mode chain
+/// in `handle_key_event`
+fn awaiting_input(app: &App) -> bool {
+    app.confirm.is_some()
+        || app.discard_all_input.is_some()
+        || app.interactive_staging.is_some()
+        || app.line_staging.is_some()
+        || app.stash_target_visible
+        || app.remote_url_input_mode
+        || app.remotes_visible
+        || app.worktree_input_mode
+        || app.sync_upstream.is_some()
+        || app.branch_compare.is_some()
+        || app.reflog_branch.is_some()
+        || app.worktrees_visible
+        || app.search_mode
+        || app.branch_input_mode
+        || app.quick_commit_input.is_some()
+        || app.commit_message_mode
+        || app.stash_input_mode
+        || app.new_branch_input_mode
+        || app.tag_input_mode
+        || app.goto_input_mode
+        || app.rename_source.is_some()
+        || app.amend_metadata.is_some()
+        || app.file_filter_input_mode
+        || app.browse_hash.is_some()
+        || app.tree_view_mode
+}
+
+/// Runs a macro's actions in order, each in its own panel, as if its key
+/// had been pressed there. Stops at the first action that fails, and at
+/// one that opens a prompt or popup, which is left open for the user.
+fn run_macro(app: &mut App, mac: Macro) -> Result<()> {
+    for (i, &action) in mac.actions.iter().enumerate() {
+        app.clear_status();
+        let result = match action {
+            "global.refresh" => {
+                app.refresh_all();
+                Ok(())
+            }
+            _ => {
+                let (scope, _) = action.split_once('.').unwrap_or_default();
+                let (Some(panel), Some(key)) = (Panel::from_name(scope), crate::keymap::default_key(action)) else {
+                    continue;
+                };
+                app.switch_to_panel(panel);
+                handle_panel_key(app, KeyCode::Char(key))
+            }
+        };
+
+        let error = match result {
+            Err(e) => Some(e.to_string()),
+            Ok(()) if app.status_message_type == MessageType::Error => app.status_message.clone(),
+            Ok(()) => None,
+        };
+        if let Some(error) = error {
+            app.set_status(
+                format!("Macro '{}' stopped at {}: {}", mac.name, action, error),
+                MessageType::Error,
+            );
+            return Ok(());
+        }
+        if awaiting_input(app) {
+            let skipped = mac.actions.len() - i - 1;
+            if skipped > 0 {
+                app.set_status(
+                    format!("Macro '{}' paused at {}; {} action(s) not run", mac.name, action, skipped),
+                    MessageType::Info,
+                );
+            }
+            return Ok(());
+        }
+    }
+
+    app.set_status(format!("Ran macro '{}'", mac.name), MessageType::Success);
+    Ok(())
+}
+
+fn handle_panel_key(app: &mut App, key_code: KeyCode) -> Result<()> {
+    match app.current_panel {
+        Panel::Status => handle_status_panel(app, key_code),
+        Panel::Log => handle_log_panel(app, key_code)?,
+        Panel::Stash => handle_stash_panel(app, key_code),
+        Panel::Branches => handle_branches_panel(app, key_code),
+        Panel::Tags => handle_tags_panel(app, key_code),
+    }
+    Ok(())
+}
+
 fn handle_normal_mode(app: &mut App, key_code: KeyCode) -> Result<()> {
+    if let KeyCode::Char(c) = key_code {
+        if let Some(mac) = app.keymap.macro_for(c).cloned() {
+            return run_macro(app, mac);
+        }
+    }
+
     // Global keybindings (work in all panels)
     match key_code {
         KeyCode::Char('q') => app.quit(),
@@ -128,9 +481,23 @@ fn handle_normal_mode(app: &mut App, key_code: KeyCode) -> Result<()> {
         KeyCode::Char('2') => app.switch_to_panel(Panel::Log),
         KeyCode::Char('3') => app.switch_to_panel(Panel::Stash),
         KeyCode::Char('4') => app.switch_to_panel(Panel::Branches),
+        KeyCode::Char('5') => app.switch_to_panel(Panel::Tags),
+        KeyCode::Tab => app.next_panel(),
+        KeyCode::BackTab => app.previous_panel(),
+        KeyCode::Char('W') => app.open_worktrees(),
+        KeyCode::Char('!') => app.open_shell(),
+        KeyCode::Char('T') => app.cycle_theme(),
+        KeyCode::Char('V') => app.toggle_simple_diff_colors(),
+        KeyCode::Char('E') => app.toggle_expand_long_lines(),
+        KeyCode::Char('N') => app.cycle_path_display(),
+        KeyCode::Char('K') => app.copy_head_branch_name(),
         KeyCode::Esc => {
             if app.status_message.is_some() {
                 app.clear_status();
+            } else if app.file_filter.is_some() {
+                app.clear_file_filter();
+            } else if app.range_anchor.is_some() {
+                app.range_anchor = None;
             } else if app.active_filter.is_some() {
                 app.clear_search()?;
             } else {
@@ -138,13 +505,21 @@ fn handle_normal_mode(app: &mut App, key_code: KeyCode) -> Result<()> {
             }
         }
         _ => {
+            // Bisect marks are fixed; everything else follows the user's [keys]
+            let bisect_mark = app.bisect_active
+                && app.current_panel == Panel::Log
+                && matches!(key_code, KeyCode::Char('g' | 'b' | 's'));
+            let key_code = if bisect_mark {
+                key_code
+            } else {
+                match app.keymap.translate(&app.current_panel, key_code) {
+                    Some(key_code) => key_code,
+                    None => return Ok(()),
+                }
+            };
+
             // Panel-specific keybindings
-            match app.current_panel {
-                Panel::Status => handle_status_panel(app, key_code),
-                Panel::Log => handle_log_panel(app, key_code)?,
-                Panel::Stash => handle_stash_panel(app, key_code),
-                Panel::Branches => handle_branches_panel(app, key_code),
-            }
+            handle_panel_key(app, key_code)?;
         }
     }
     Ok(())
@@ -156,20 +531,33 @@ fn handle_status_panel(app: &mut App, key_code: KeyCode) {
         KeyCode::Char('a') => app.stage_all_files(),
         KeyCode::Char('u') => app.unstage_all_files(),
         KeyCode::Char('c') => app.enter_commit_message_mode(),
+        KeyCode::Char('C') => app.enter_commit_and_push_mode(),
+        KeyCode::Char('w') => app.enter_quick_commit_mode(),
+        KeyCode::Char('e') => app.enter_commit_all_mode(),
         KeyCode::Char('A') => app.enter_amend_mode(),
         KeyCode::Char('x') => app.discard_selected_file(),
+        KeyCode::Char('X') => app.open_discard_all(),
         KeyCode::Char('s') => app.enter_stash_input_mode(),
+        KeyCode::Char('o') => app.reveal_selected_file(),
+        KeyCode::Char('y') => app.copy_status_file_path(),
+        KeyCode::Char('R') => app.enter_rename_mode(),
+        KeyCode::Char('p') => app.start_interactive_staging(),
+        KeyCode::Char('v') => app.start_line_staging(),
+        KeyCode::Char('Z') => app.start_interactive_stash(),
+        KeyCode::Char('S') => app.update_submodules(),
+        KeyCode::Char('t') => app.jump_status_section(),
+        KeyCode::Char('z') => app.toggle_status_section(),
+        KeyCode::Char('I') => app.toggle_ignore_whitespace(),
+        KeyCode::Char('H') if app.status_show_diff && app.status_conflicts.is_none() => app.copy_diff_hunk(),
+        KeyCode::Char(']') if app.status_conflicts.is_some() => app.next_conflict(),
+        KeyCode::Char('[') if app.status_conflicts.is_some() => app.previous_conflict(),
+        KeyCode::Enter if app.is_untracked_dir_selected() => app.toggle_untracked_dir(),
+        KeyCode::Enter if app.is_status_header_selected() => app.toggle_status_section(),
         KeyCode::Enter => app.toggle_status_diff(),
-        KeyCode::PageUp => {
-            if app.status_show_diff {
-                app.scroll_status_diff_page_up();
-            }
-        }
-        KeyCode::PageDown => {
-            if app.status_show_diff {
-                app.scroll_status_diff_page_down();
-            }
-        }
+        KeyCode::Left | KeyCode::Char('h') if app.status_show_diff => app.previous_status_diff_file(),
+        KeyCode::Right | KeyCode::Char('l') if app.status_show_diff => app.next_status_diff_file(),
+        KeyCode::PageUp if app.status_show_diff => app.scroll_status_diff_page_up(),
+        KeyCode::PageDown if app.status_show_diff => app.scroll_status_diff_page_down(),
         KeyCode::Down | KeyCode::Char('j') => {
             if app.status_show_diff {
                 app.scroll_status_diff_down();
@@ -191,49 +579,60 @@ fn handle_status_panel(app: &mut App, key_code: KeyCode) {
 fn handle_log_panel(app: &mut App, key_code: KeyCode) -> Result<()> {
     match key_code {
         KeyCode::Char('t') => app.toggle_tree_view()?,
+        KeyCode::Char('/') if app.show_diff => app.enter_file_filter_mode(),
         KeyCode::Char('/') => app.enter_search_mode(),
+        KeyCode::Char(':') => app.enter_goto_input_mode(),
         KeyCode::Char('y') => app.copy_commit_hash(),
+        KeyCode::Char('Y') => app.copy_commit_summary(),
+        KeyCode::Char('v') => app.toggle_range_mark(),
+        KeyCode::Char('M') => app.toggle_first_parent(),
+        KeyCode::Char('D') => app.toggle_group_by_day(),
+        KeyCode::Char('G') => app.cycle_merge_filter(),
+        KeyCode::Char('d') => app.toggle_worktree_diff()?,
         KeyCode::Char('c') => app.checkout_selected_commit(),
+        KeyCode::Char('B') => app.toggle_bisect(),
+        KeyCode::Char('g') if app.bisect_active => app.bisect_mark_selected(BisectMark::Good),
+        KeyCode::Char('b') if app.bisect_active => app.bisect_mark_selected(BisectMark::Bad),
+        KeyCode::Char('s') if app.bisect_active => app.bisect_mark_selected(BisectMark::Skip),
         KeyCode::Char('b') => app.enter_branch_input_mode(),
         KeyCode::Char('p') => app.cherry_pick_commit(),
         KeyCode::Char('r') => app.revert_selected_commit(),
+        KeyCode::Char('w') => app.enter_reword_mode(),
+        KeyCode::Char('a') => app.amend_selected_commit(),
+        KeyCode::Char('A') => app.enter_amend_metadata_mode(),
+        KeyCode::Char('[') if !app.show_diff => app.select_parent_commit(),
+        KeyCode::Char(']') if !app.show_diff => app.select_child_commit(),
+        KeyCode::Char('m') if app.show_diff => app.cycle_diff_parent()?,
+        KeyCode::Char('z') if app.show_diff => app.toggle_diff_fullscreen(),
+        KeyCode::Char('H') if app.show_diff => app.copy_diff_hunk(),
+        KeyCode::Char('I') => app.toggle_ignore_whitespace(),
         KeyCode::Char('f') => app.fetch_from_remote(),
+        KeyCode::Char('F') => app.fetch_prune(),
         KeyCode::Char('P') => app.push_to_remote(),
+        KeyCode::Char('o') => app.open_outgoing_commits(),
+        KeyCode::Char('e') => app.open_commit_browser(),
+        KeyCode::Char('i') => app.fetch_and_show_incoming(),
         KeyCode::Char('U') => app.pull_from_remote(),
-        KeyCode::PageUp => {
-            if app.show_diff {
-                app.scroll_diff_page_up();
-            }
-        }
-        KeyCode::PageDown => {
-            if app.show_diff {
-                app.scroll_diff_page_down();
-            }
-        }
+        KeyCode::Char('R') => app.open_remotes(),
+        KeyCode::PageUp if app.show_diff => app.scroll_diff_page_up(),
+        KeyCode::PageDown if app.show_diff => app.scroll_diff_page_down(),
+        KeyCode::Char('n') => app.toggle_auto_diff(),
         KeyCode::Down | KeyCode::Char('j') => {
-            if app.show_diff {
+            if app.show_diff && !app.auto_diff {
                 app.scroll_diff_down();
             } else {
                 app.next();
             }
         }
         KeyCode::Up | KeyCode::Char('k') => {
-            if app.show_diff {
+            if app.show_diff && !app.auto_diff {
                 app.scroll_diff_up();
             } else {
                 app.previous();
             }
         }
-        KeyCode::Left | KeyCode::Char('h') => {
-            if app.show_diff {
-                app.previous_file();
-            }
-        }
-        KeyCode::Right | KeyCode::Char('l') => {
-            if app.show_diff {
-                app.next_file();
-            }
-        }
+        KeyCode::Left | KeyCode::Char('h') if app.show_diff => app.previous_file(),
+        KeyCode::Right | KeyCode::Char('l') if app.show_diff => app.next_file(),
         KeyCode::Enter => app.toggle_diff()?,
         _ => {}
     }
@@ -244,7 +643,17 @@ fn handle_stash_panel(app: &mut App, key_code: KeyCode) {
     match key_code {
         KeyCode::Char('a') => app.apply_selected_stash(),
         KeyCode::Char('p') => app.pop_selected_stash(),
+        KeyCode::Char('i') => app.toggle_stash_restore_index(),
         KeyCode::Char('d') => app.drop_selected_stash(),
+        KeyCode::Char('b') => app.open_stash_target_picker(),
+        KeyCode::Char('r') => app.enter_restash_mode(),
+        KeyCode::Enter => app.toggle_stash_diff(),
+        KeyCode::Down | KeyCode::Char('j') if app.stash_diff.is_some() => app.scroll_stash_diff(1),
+        KeyCode::Up | KeyCode::Char('k') if app.stash_diff.is_some() => app.scroll_stash_diff(-1),
+        KeyCode::PageDown if app.stash_diff.is_some() => app.scroll_stash_diff(10),
+        KeyCode::PageUp if app.stash_diff.is_some() => app.scroll_stash_diff(-10),
+        KeyCode::Right | KeyCode::Char('l') if app.stash_diff.is_some() => app.next_stash_file(),
+        KeyCode::Left | KeyCode::Char('h') if app.stash_diff.is_some() => app.previous_stash_file(),
         KeyCode::Down | KeyCode::Char('j') => app.next_stash(),
         KeyCode::Up | KeyCode::Char('k') => app.previous_stash(),
         _ => {}
@@ -257,8 +666,26 @@ fn handle_branches_panel(app: &mut App, key_code: KeyCode) {
         KeyCode::Char('d') => app.delete_selected_branch(),
         KeyCode::Char('n') => app.enter_new_branch_mode(),
         KeyCode::Char('m') => app.merge_selected_branch(),
+        KeyCode::Char('r') => app.rebase_onto_selected_branch(),
+        KeyCode::Char('L') => app.open_branch_reflog(),
+        KeyCode::Char('c') => app.compare_selected_branch(),
+        KeyCode::Char('y') => app.copy_selected_branch_name(),
+        KeyCode::Char('-') => app.switch_to_previous_branch(),
         KeyCode::Down | KeyCode::Char('j') => app.next_branch(),
         KeyCode::Up | KeyCode::Char('k') => app.previous_branch(),
         _ => {}
     }
 }
+
+fn handle_tags_panel(app: &mut App, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Enter | KeyCode::Char('c') => app.checkout_selected_tag(),
+        KeyCode::Char('d') => app.delete_selected_tag(),
+        KeyCode::Char('P') => app.push_selected_tag(),
+        KeyCode::Char('A') => app.push_all_tags(),
+        KeyCode::Char('n') => app.enter_tag_input_mode(),
+        KeyCode::Down | KeyCode::Char('j') => app.next_tag(),
+        KeyCode::Up | KeyCode::Char('k') => app.previous_tag(),
+        _ => {}
+    }
+}
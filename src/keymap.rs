@@ -0,0 +1,345 @@
+use crate::config::Config;
+use crate::ui::Panel;
+use anyhow::Result;
+use crossterm::event::KeyCode;
+use std::collections::HashMap;
+
+/// Panel actions that can be rebound in the `[keys]` section of the config,
+/// with their default keys. Names are `<panel>.<action>`.
+pub const ACTIONS: &[(&str, char)] = &[
+    ("status.stage", ' '),
+    ("status.stage_all", 'a'),
+    ("status.unstage_all", 'u'),
+    ("status.commit", 'c'),
+    ("status.commit_and_push", 'C'),
+    ("status.quick_commit", 'w'),
+    ("status.commit_all", 'e'),
+    ("status.amend", 'A'),
+    ("status.discard", 'x'),
+    ("status.discard_all", 'X'),
+    ("status.stash", 's'),
+    ("status.reveal", 'o'),
+    ("status.copy_path", 'y'),
+    ("status.rename", 'R'),
+    ("status.stage_hunks", 'p'),
+    ("status.stage_lines", 'v'),
+    ("status.stash_hunks", 'Z'),
+    ("status.update_submodules", 'S'),
+    ("status.jump_section", 't'),
+    ("status.fold_section", 'z'),
+    ("status.ignore_whitespace", 'I'),
+    ("status.copy_hunk", 'H'),
+    ("status.next_conflict", ']'),
+    ("status.previous_conflict", '['),
+    ("log.tree_view", 't'),
+    ("log.browse", 'e'),
+    ("log.search", '/'),
+    ("log.goto", ':'),
+    ("log.copy_hash", 'y'),
+    ("log.copy_summary", 'Y'),
+    ("log.mark_range", 'v'),
+    ("log.diff_worktree", 'd'),
+    ("log.first_parent", 'M'),
+    ("log.group_by_day", 'D'),
+    ("log.merge_filter", 'G'),
+    ("log.checkout", 'c'),
+    ("log.bisect", 'B'),
+    ("log.branch", 'b'),
+    ("log.cherry_pick", 'p'),
+    ("log.revert", 'r'),
+    ("log.reword", 'w'),
+    ("log.amend", 'a'),
+    ("log.amend_author", 'A'),
+    ("log.parent", '['),
+    ("log.child", ']'),
+    ("log.next_parent", 'm'),
+    ("log.fullscreen", 'z'),
+    ("log.pin_diff", 'n'),
+    ("log.ignore_whitespace", 'I'),
+    ("log.copy_hunk", 'H'),
+    ("log.fetch", 'f'),
+    ("log.fetch_prune", 'F'),
+    ("log.incoming", 'i'),
+    ("log.push", 'P'),
+    ("log.outgoing", 'o'),
+    ("log.pull", 'U'),
+    ("log.remotes", 'R'),
+    ("stash.apply", 'a'),
+    ("stash.pop", 'p'),
+    ("stash.restore_index", 'i'),
+    ("stash.drop", 'd'),
+    ("stash.apply_on_branch", 'b'),
+    ("stash.rename", 'r'),
+    ("branches.delete", 'd'),
+    ("branches.new", 'n'),
+    ("branches.merge", 'm'),
+    ("branches.rebase", 'r'),
+    ("branches.reflog", 'L'),
+    ("branches.compare", 'c'),
+    ("branches.copy_name", 'y'),
+    ("branches.switch_previous", '-'),
+    ("tags.checkout", 'c'),
+    ("tags.new", 'n'),
+    ("tags.delete", 'd'),
+    ("tags.push", 'P'),
+    ("tags.push_all", 'A'),
+];
+
+/// Actions that only macros can use
+pub const MACRO_ACTIONS: &[&str] = &["global.refresh"];
+
+/// Keys handled in every panel before the panel's own actions
+const GLOBAL_KEYS: &[char] = &['q', '?', '1', '2', '3', '4', '5', 'T', 'V', 'E', 'N', 'K', 'W', '!'];
+
+/// Navigation keys the lists and diffs handle themselves (Enter and Esc
+/// aren't characters, so they can't be bound at all)
+const NAVIGATION_KEYS: &[char] = &['j', 'k', 'h', 'l'];
+
+/// A named chain of actions from the `[macros]` section, such as
+/// `sync = "log.fetch, log.pull, global.refresh"`, run by the key bound to
+/// `macro.<name>` in `[keys]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Macro {
+    pub name: String,
+    pub key: char,
+    pub actions: Vec<&'static str>,
+}
+
+/// Key bindings after applying the user's `[keys]` overrides
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    overrides: HashMap<&'static str, char>,
+    macros: Vec<Macro>,
+}
+
+impl Keymap {
+    /// Reads `[keys]` entries such as `stash.apply = "A"` (use "space" for Space)
+    pub fn from_config(config: &Config) -> Result<Keymap> {
+        let mut overrides = HashMap::new();
+        let mut macro_keys = HashMap::new();
+        for (name, value) in config.section("keys") {
+            let key = parse_key(name, value)?;
+            if let Some(macro_name) = name.strip_prefix("macro.") {
+                macro_keys.insert(macro_name, key);
+                continue;
+            }
+            let Some(&(action, _)) = ACTIONS.iter().find(|(action, _)| *action == name) else {
+                anyhow::bail!("unknown action '{}' in [keys]", name);
+            };
+            check_reserved(&format!("'{}'", action), key)?;
+            overrides.insert(action, key);
+        }
+
+        let mut keymap = Keymap {
+            overrides,
+            macros: Vec::new(),
+        };
+        keymap.macros = keymap.parse_macros(config, macro_keys)?;
+        for (i, &(action, _)) in ACTIONS.iter().enumerate() {
+            let scope = scope_of(action);
+            let clash = ACTIONS[i + 1..]
+                .iter()
+                .find(|(other, _)| scope_of(other) == scope && keymap.key(other) == keymap.key(action));
+            if let Some((other, _)) = clash {
+                anyhow::bail!("'{}' and '{}' are both bound to '{}'", action, other, keymap.key(action));
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// Reads `[macros]`, checking every action name and that each macro
+    /// has a key of its own
+    fn parse_macros(&self, config: &Config, mut keys: HashMap<&str, char>) -> Result<Vec<Macro>> {
+        let mut macros = Vec::new();
+        for (name, value) in config.section("macros") {
+            let mut actions = Vec::new();
+            for word in value.split([',', ' ']).filter(|word| !word.is_empty()) {
+                let known = ACTIONS
+                    .iter()
+                    .map(|&(action, _)| action)
+                    .chain(MACRO_ACTIONS.iter().copied())
+                    .find(|action| *action == word);
+                match known {
+                    Some(action) => actions.push(action),
+                    None => anyhow::bail!("unknown action '{}' in macro '{}'", word, name),
+                }
+            }
+            if actions.is_empty() {
+                anyhow::bail!("macro '{}' has no actions", name);
+            }
+            let Some(key) = keys.remove(name) else {
+                anyhow::bail!("macro '{}' has no key; bind one with macro.{} in [keys]", name, name);
+            };
+
+            check_reserved(&format!("macro '{}'", name), key)?;
+            if let Some(&(action, _)) = ACTIONS.iter().find(|(action, _)| self.key(action) == key) {
+                anyhow::bail!("macro '{}' and '{}' are both bound to '{}'", name, action, key);
+            }
+            if let Some(other) = macros.iter().find(|m: &&Macro| m.key == key) {
+                anyhow::bail!("macros '{}' and '{}' are both bound to '{}'", other.name, name, key);
+            }
+            macros.push(Macro {
+                name: name.to_string(),
+                key,
+                actions,
+            });
+        }
+
+        if let Some(name) = keys.keys().next() {
+            anyhow::bail!("unknown macro '{}' in [keys]", name);
+        }
+        Ok(macros)
+    }
+
+    /// The macro bound to a key
+    pub fn macro_for(&self, key: char) -> Option<&Macro> {
+        self.macros.iter().find(|m| m.key == key)
+    }
+
+    pub fn macros(&self) -> &[Macro] {
+        &self.macros
+    }
+
+    /// The key currently bound to an action
+    pub fn key(&self, action: &str) -> char {
+        self.overrides.get(action).copied().unwrap_or_else(|| {
+            ACTIONS
+                .iter()
+                .find(|(name, _)| *name == action)
+                .map(|&(_, key)| key)
+                .unwrap_or(' ')
+        })
+    }
+
+    /// The key bound to an action, formatted for help text
+    pub fn hint(&self, action: &str) -> String {
+        match self.key(action) {
+            ' ' => "Space".to_string(),
+            key => key.to_string(),
+        }
+    }
+
+    /// Maps a key pressed in a panel back to the default key of the action
+    /// bound to it, so the input handlers can keep matching on the defaults.
+    /// Returns None for a default key whose action was moved elsewhere.
+    pub fn translate(&self, panel: &Panel, key_code: KeyCode) -> Option<KeyCode> {
+        let KeyCode::Char(c) = key_code else {
+            return Some(key_code);
+        };
+        if self.overrides.is_empty() {
+            return Some(key_code);
+        }
+
+        let scope = panel_scope(panel);
+        let in_scope = ACTIONS.iter().filter(|(action, _)| scope_of(action) == scope);
+        if let Some(&(_, default)) = in_scope.clone().find(|(action, _)| self.key(action) == c) {
+            return Some(KeyCode::Char(default));
+        }
+        if in_scope.clone().any(|&(_, default)| default == c) {
+            return None;
+        }
+        Some(key_code)
+    }
+}
+
+/// Default key of an action, which the input handlers match on
+pub fn default_key(action: &str) -> Option<char> {
+    ACTIONS.iter().find(|(name, _)| *name == action).map(|&(_, key)| key)
+}
+
+/// Rejects a key that global keys or navigation would always take first
+fn check_reserved(what: &str, key: char) -> Result<()> {
+    if GLOBAL_KEYS.contains(&key) {
+        anyhow::bail!("{} can't use '{}', a global key", what, key);
+    }
+    if NAVIGATION_KEYS.contains(&key) {
+        anyhow::bail!("{} can't use '{}', a navigation key", what, key);
+    }
+    Ok(())
+}
+
+/// A single character, or "space"
+fn parse_key(name: &str, value: &str) -> Result<char> {
+    if value == "space" {
+        return Ok(' ');
+    }
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => anyhow::bail!("key for '{}' must be a single character", name),
+    }
+}
+
+fn scope_of(action: &str) -> &str {
+    action.split_once('.').map(|(scope, _)| scope).unwrap_or(action)
+}
+
+fn panel_scope(panel: &Panel) -> &'static str {
+    match panel {
+        Panel::Status => "status",
+        Panel::Log => "log",
+        Panel::Stash => "stash",
+        Panel::Branches => "branches",
+        Panel::Tags => "tags",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remapped_keys_translate_to_defaults() {
+        let config = Config::parse("[keys]\nstash.apply = \"A\"\nstatus.stage = \"space\"\n").unwrap();
+        let keymap = Keymap::from_config(&config).unwrap();
+
+        assert_eq!(keymap.hint("stash.apply"), "A");
+        assert_eq!(keymap.hint("status.stage"), "Space");
+        assert_eq!(keymap.translate(&Panel::Stash, KeyCode::Char('A')), Some(KeyCode::Char('a')));
+        // The old key no longer applies, other keys pass through
+        assert_eq!(keymap.translate(&Panel::Stash, KeyCode::Char('a')), None);
+        assert_eq!(keymap.translate(&Panel::Stash, KeyCode::Char('j')), Some(KeyCode::Char('j')));
+        assert_eq!(keymap.translate(&Panel::Tags, KeyCode::Char('A')), Some(KeyCode::Char('A')));
+    }
+
+    #[test]
+    fn test_invalid_key_bindings() {
+        let unknown = Config::parse("[keys]\nstash.explode = \"e\"\n").unwrap();
+        assert!(Keymap::from_config(&unknown).is_err());
+
+        let clash = Config::parse("[keys]\nstash.apply = \"d\"\n").unwrap();
+        assert!(Keymap::from_config(&clash).is_err());
+
+        let long = Config::parse("[keys]\nstash.apply = \"ab\"\n").unwrap();
+        assert!(Keymap::from_config(&long).is_err());
+
+        let global = Config::parse("[keys]\nstash.apply = \"q\"\n").unwrap();
+        assert!(Keymap::from_config(&global).is_err());
+
+        let navigation = Config::parse("[keys]\nstatus.stage = \"j\"\n").unwrap();
+        assert!(Keymap::from_config(&navigation).is_err());
+    }
+
+    #[test]
+    fn test_macros() {
+        let config = Config::parse(
+            "[macros]\nsync = \"log.fetch, log.pull global.refresh\"\n[keys]\nmacro.sync = \"O\"\n",
+        )
+        .unwrap();
+        let keymap = Keymap::from_config(&config).unwrap();
+        let sync = keymap.macro_for('O').unwrap();
+        assert_eq!(sync.name, "sync");
+        assert_eq!(sync.actions, vec!["log.fetch", "log.pull", "global.refresh"]);
+
+        for bad in [
+            "[macros]\nsync = \"log.explode\"\n[keys]\nmacro.sync = \"O\"\n",
+            "[macros]\nsync = \"log.fetch\"\n",
+            "[macros]\nsync = \"log.fetch\"\n[keys]\nmacro.sync = \"f\"\n",
+            "[macros]\nsync = \"log.fetch\"\n[keys]\nmacro.sync = \"q\"\n",
+            "[macros]\nsync = \"log.fetch\"\n[keys]\nmacro.sync = \"k\"\n",
+            "[keys]\nmacro.nothing = \"S\"\n",
+        ] {
+            assert!(Keymap::from_config(&Config::parse(bad).unwrap()).is_err(), "{}", bad);
+        }
+    }
+}
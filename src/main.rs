@@ -1,53 +1,142 @@
+mod config;
 mod git;
+mod history;
 mod input;
+mod keymap;
+mod patch;
+mod platform;
 mod syntax;
+#[cfg(test)]
+mod test_repo;
 mod ui;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::io;
+use std::io::{self, IsTerminal};
 use ui::{ui, App};
 
 #[derive(Parser)]
 #[command(name = "gitu", version, about = "A blazingly fast TUI for Git")]
-struct Cli {}
+struct Cli {
+    /// Disable colors and rely on bold/reverse styling only (same as --color=never)
+    #[arg(long)]
+    no_color: bool,
+
+    /// When to use colors; auto drops them for NO_COLOR/GITU_NO_COLOR, a
+    /// non-terminal stdout or git's color.ui=never
+    #[arg(long, value_enum, default_value_t = ColorMode::Auto)]
+    color: ColorMode,
+
+    /// Draw in the main screen buffer so the last frame stays in scrollback
+    #[arg(long)]
+    no_alt_screen: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+/// Returns true if NO_COLOR or GITU_NO_COLOR is set to a non-empty value
+fn no_color_env() -> bool {
+    ["NO_COLOR", "GITU_NO_COLOR"]
+        .iter()
+        .any(|name| std::env::var_os(name).is_some_and(|v| !v.is_empty()))
+}
+
+/// Returns true if git's `color.ui` turns colors off: `never`, or any
+/// spelling git accepts for false
+fn git_color_disabled() -> bool {
+    match git::get_config("color.ui") {
+        Ok(Some(value)) if value.eq_ignore_ascii_case("never") => true,
+        Ok(Some(_)) => matches!(git::get_config_bool("color.ui"), Ok(Some(false))),
+        _ => false,
+    }
+}
+
+/// Decides whether to draw in color from the flags, the environment and git config
+fn use_color(cli: &Cli) -> bool {
+    if cli.no_color {
+        return false;
+    }
+    match cli.color {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => !no_color_env() && io::stdout().is_terminal() && !git_color_disabled(),
+    }
+}
+
+/// Restores the terminal before a panic message is printed, so a crash
+/// leaves a usable shell instead of raw mode and the alternate screen
+fn install_panic_hook(alternate_screen: bool) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let mut stdout = io::stdout();
+        if alternate_screen {
+            let _ = execute!(stdout, LeaveAlternateScreen);
+        }
+        let _ = execute!(stdout, DisableMouseCapture, crossterm::cursor::Show);
+        default_hook(info);
+    }));
+}
 
 fn main() -> Result<()> {
     // Parse CLI arguments (handles --version, --help automatically)
-    Cli::parse();
+    let cli = Cli::parse();
+
+    // Run git from the repository root, whichever subdirectory we started in
+    git::enter_repo_root()?;
 
     // Load commits from git (no filter initially)
-    let commits = git::get_commits(None)?;
+    let commits = git::get_commits(None, false, git::MergeFilter::All)?;
 
     if commits.is_empty() {
         eprintln!("No commits found in the current repository.");
         return Ok(());
     }
 
+    // Create app (this also reads the user and repo config the terminal setup depends on)
+    let mut app = App::new(commits);
+    app.no_color = !use_color(&cli);
+    let alternate_screen = !cli.no_alt_screen && app.config.alternate_screen();
+
     // Setup terminal
+    install_panic_hook(alternate_screen);
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    if alternate_screen {
+        execute!(stdout, EnterAlternateScreen)?;
+    }
+    execute!(stdout, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
+    if !alternate_screen {
+        // Start from a blank screen; earlier output scrolls into history
+        terminal.clear()?;
+    }
 
-    // Create app and run
-    let mut app = App::new(commits);
-    let res = run_app(&mut terminal, &mut app);
+    let res = run_app(&mut terminal, &mut app, alternate_screen);
 
     // Restore terminal
     disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    if alternate_screen {
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    } else {
+        // Leave the last frame in place and put the prompt below it
+        let height = terminal.size()?.height;
+        terminal.set_cursor_position((0, height.saturating_sub(1)))?;
+        println!();
+    }
+    execute!(terminal.backend_mut(), DisableMouseCapture)?;
     terminal.show_cursor()?;
 
     if let Err(err) = res {
@@ -60,6 +149,7 @@ fn main() -> Result<()> {
 fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
+    alternate_screen: bool,
 ) -> Result<()> {
     loop {
         terminal.draw(|f| ui(f, app))?;
@@ -68,15 +158,44 @@ fn run_app<B: ratatui::backend::Backend>(
             return Ok(());
         }
 
+        if let Some(command) = app.external_command.take() {
+            // Give the terminal back while the external program runs
+            disable_raw_mode()?;
+            if alternate_screen {
+                execute!(io::stdout(), LeaveAlternateScreen)?;
+            }
+            execute!(io::stdout(), DisableMouseCapture)?;
+            terminal.show_cursor()?;
+            if !alternate_screen {
+                terminal.clear()?;
+            }
+
+            app.run_external_command(command);
+
+            enable_raw_mode()?;
+            if alternate_screen {
+                execute!(io::stdout(), EnterAlternateScreen)?;
+            }
+            execute!(io::stdout(), EnableMouseCapture)?;
+            terminal.clear()?;
+            continue;
+        }
+
         if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                // Only handle key press events, not release
-                if key.kind != KeyEventKind::Press {
-                    continue;
-                }
+            match event::read()? {
+                Event::Key(key) => {
+                    // Only handle key press events, not release
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
 
-                // Delegate to input handler
-                input::handle_key_event(app, key.code)?;
+                    // Delegate to input handler
+                    input::handle_key_event(app, key.code)?;
+                }
+                // Pick up the new size now so the next draw lays everything
+                // out (and re-windows the commit list) against it
+                Event::Resize(_, _) => terminal.autoresize()?,
+                _ => {}
             }
         }
     }
@@ -0,0 +1,537 @@
+use std::ops::Range;
+
+/// Number of context lines shown around a split hunk
+const DISPLAY_CONTEXT: usize = 3;
+
+/// A single hunk of a unified diff
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_count: usize,
+    pub new_start: usize,
+    pub new_count: usize,
+    /// Text after the closing "@@" (usually the enclosing function)
+    pub section: String,
+    /// Hunk body lines, each starting with ' ', '+', '-' or '\'
+    pub lines: Vec<String>,
+}
+
+/// A parsed single-file diff: the file header plus its hunks
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilePatch {
+    pub header: Vec<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+fn is_change(line: &str) -> bool {
+    line.starts_with('+') || line.starts_with('-')
+}
+
+impl Hunk {
+    /// Returns the "@@ -a,b +c,d @@" header line
+    pub fn header(&self) -> String {
+        let mut header = format!(
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_count, self.new_start, self.new_count
+        );
+        if !self.section.is_empty() {
+            header.push(' ');
+            header.push_str(&self.section);
+        }
+        header
+    }
+
+    /// Splits the hunk body into ranges, one per run of consecutive changed lines
+    pub fn change_runs(&self) -> Vec<Range<usize>> {
+        let mut runs = Vec::new();
+        let mut start: Option<usize> = None;
+
+        for (i, line) in self.lines.iter().enumerate() {
+            let changed = is_change(line) || (line.starts_with('\\') && start.is_some());
+            match (changed, start) {
+                (true, None) => start = Some(i),
+                (false, Some(s)) => {
+                    runs.push(s..i);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(s) = start {
+            runs.push(s..self.lines.len());
+        }
+
+        runs
+    }
+}
+
+/// Parses "@@ -a,b +c,d @@ section" into its numbers and section text
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize, usize, String)> {
+    let rest = line.strip_prefix("@@ -")?;
+    let (ranges, section) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+
+    let parse_range = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+
+    let (old_start, old_count) = parse_range(old)?;
+    let (new_start, new_count) = parse_range(new)?;
+    Some((old_start, old_count, new_start, new_count, section.trim().to_string()))
+}
+
+impl FilePatch {
+    /// Parses the output of `git diff` for a single file
+    pub fn parse(diff: &str) -> FilePatch {
+        let mut header = Vec::new();
+        let mut hunks: Vec<Hunk> = Vec::new();
+
+        for line in diff.lines() {
+            if let Some((old_start, old_count, new_start, new_count, section)) = parse_hunk_header(line) {
+                hunks.push(Hunk {
+                    old_start,
+                    old_count,
+                    new_start,
+                    new_count,
+                    section,
+                    lines: Vec::new(),
+                });
+            } else if let Some(hunk) = hunks.last_mut() {
+                hunk.lines.push(line.to_string());
+            } else {
+                header.push(line.to_string());
+            }
+        }
+
+        FilePatch { header, hunks }
+    }
+
+    /// Returns an empty selection (nothing chosen) shaped like this patch
+    pub fn empty_selection(&self) -> Vec<Vec<bool>> {
+        self.hunks.iter().map(|h| vec![false; h.lines.len()]).collect()
+    }
+
+    /// Builds a patch that contains only the selected changed lines.
+    ///
+    /// Unselected changes are turned back into context so the patch still
+    /// applies: when staging, an unselected '-' stays as context and an
+    /// unselected '+' is dropped. With `reverse` (for `git apply -R`, i.e.
+    /// unstaging) the roles flip, since the "new" side is what exists.
+    /// Returns None if no changed line is selected.
+    pub fn build(&self, selected: &[Vec<bool>], reverse: bool) -> Option<String> {
+        let mut out: Vec<String> = self.header.clone();
+        let mut offset: isize = 0;
+        let mut any = false;
+
+        for (hunk, sel) in self.hunks.iter().zip(selected) {
+            if !hunk.lines.iter().zip(sel).any(|(line, &s)| s && is_change(line)) {
+                continue;
+            }
+
+            let mut body = Vec::new();
+            let mut old_count = 0;
+            let mut new_count = 0;
+            let mut last_kept = false;
+
+            for (line, &chosen) in hunk.lines.iter().zip(sel) {
+                let (kind, text) = line.split_at(line.len().min(1));
+                let emitted = match (kind, chosen, reverse) {
+                    ("+", true, _) | ("-", true, _) => Some(line.clone()),
+                    ("-", false, false) | ("+", false, true) => Some(format!(" {}", text)),
+                    ("-", false, true) | ("+", false, false) => None,
+                    ("\\", _, _) => {
+                        if last_kept {
+                            body.push(line.clone());
+                        }
+                        continue;
+                    }
+                    _ => Some(line.clone()),
+                };
+
+                last_kept = emitted.is_some();
+                if let Some(emitted) = emitted {
+                    match emitted.chars().next() {
+                        Some('+') => new_count += 1,
+                        Some('-') => old_count += 1,
+                        _ => {
+                            old_count += 1;
+                            new_count += 1;
+                        }
+                    }
+                    body.push(emitted);
+                }
+            }
+
+            // Anchor on the side that exists (old when staging, new when
+            // unstaging) and shift the other side by earlier hunks' growth
+            let (old_first, new_first) = if reverse {
+                let new_first = first_line(hunk.new_start, hunk.new_count) as isize;
+                (new_first - offset, new_first)
+            } else {
+                let old_first = first_line(hunk.old_start, hunk.old_count) as isize;
+                (old_first, old_first + offset)
+            };
+            offset += new_count as isize - old_count as isize;
+
+            let rebuilt = Hunk {
+                old_start: start_line(old_first, old_count),
+                old_count,
+                new_start: start_line(new_first, new_count),
+                new_count,
+                section: hunk.section.clone(),
+                lines: Vec::new(),
+            };
+
+            out.push(rebuilt.header());
+            out.extend(body);
+            any = true;
+        }
+
+        if !any {
+            return None;
+        }
+
+        let mut patch = out.join("\n");
+        patch.push('\n');
+        Some(patch)
+    }
+}
+
+/// First line a hunk side covers. Empty sides name the line before them.
+fn first_line(start: usize, count: usize) -> usize {
+    if count == 0 {
+        start + 1
+    } else {
+        start
+    }
+}
+
+/// Inverse of `first_line` for writing a hunk header
+fn start_line(first: isize, count: usize) -> usize {
+    let start = if count == 0 { first - 1 } else { first };
+    start.max(0) as usize
+}
+
+/// Walks through the hunks of a file like `git add -p`, recording which
+/// ones to stage. Hunks can be split into one chunk per run of changes.
+#[derive(Debug, Clone)]
+pub struct HunkPicker {
+    pub patch: FilePatch,
+    /// Chunks to decide on: (hunk index, range of lines within that hunk)
+    chunks: Vec<(usize, Range<usize>)>,
+    pub current: usize,
+    pub selected: Vec<Vec<bool>>,
+}
+
+impl HunkPicker {
+    pub fn new(patch: FilePatch) -> Self {
+        let chunks = patch
+            .hunks
+            .iter()
+            .enumerate()
+            .map(|(i, h)| (i, 0..h.lines.len()))
+            .collect();
+        let selected = patch.empty_selection();
+
+        Self {
+            patch,
+            chunks,
+            current: 0,
+            selected,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.current >= self.chunks.len()
+    }
+
+    /// Lines of the current chunk with a little surrounding context
+    pub fn current_lines(&self) -> Vec<String> {
+        let Some((hunk_idx, range)) = self.chunks.get(self.current) else {
+            return Vec::new();
+        };
+        let hunk = &self.patch.hunks[*hunk_idx];
+
+        let start = if range.start == 0 {
+            0
+        } else {
+            range.start.saturating_sub(DISPLAY_CONTEXT)
+        };
+        let end = (range.end + DISPLAY_CONTEXT).min(hunk.lines.len());
+        let end = if range.end == hunk.lines.len() { range.end } else { end };
+
+        let mut lines = vec![hunk.header()];
+        lines.extend(hunk.lines[start..end].iter().cloned());
+        lines
+    }
+
+    /// Whether the current chunk contains more than one run of changes
+    pub fn can_split(&self) -> bool {
+        self.current_runs().len() > 1
+    }
+
+    fn current_runs(&self) -> Vec<Range<usize>> {
+        let Some((hunk_idx, range)) = self.chunks.get(self.current) else {
+            return Vec::new();
+        };
+        self.patch.hunks[*hunk_idx]
+            .change_runs()
+            .into_iter()
+            .filter(|run| run.start >= range.start && run.end <= range.end)
+            .collect()
+    }
+
+    /// Splits the current chunk into one chunk per run of changes
+    pub fn split(&mut self) -> bool {
+        if !self.can_split() {
+            return false;
+        }
+        let hunk_idx = self.chunks[self.current].0;
+        let runs = self.current_runs();
+        let replacement: Vec<(usize, Range<usize>)> = runs.into_iter().map(|run| (hunk_idx, run)).collect();
+        self.chunks.splice(self.current..=self.current, replacement);
+        true
+    }
+
+    /// Records the decision for the current chunk and advances
+    pub fn decide(&mut self, stage: bool) {
+        if let Some((hunk_idx, range)) = self.chunks.get(self.current).cloned() {
+            for i in range {
+                self.selected[hunk_idx][i] = stage;
+            }
+            self.current += 1;
+        }
+    }
+
+    /// Number of chunks the user chose to stage
+    pub fn chosen_count(&self) -> usize {
+        self.chunks[..self.current.min(self.chunks.len())]
+            .iter()
+            .filter(|(hunk_idx, range)| range.clone().any(|i| self.selected[*hunk_idx][i]))
+            .count()
+    }
+}
+
+/// Cursor and visual selection over the lines of a file's diff, for
+/// staging a contiguous range of lines rather than whole hunks
+#[derive(Debug, Clone)]
+pub struct LinePicker {
+    pub patch: FilePatch,
+    /// Displayed rows: (hunk index, None for the hunk header or Some(line index))
+    rows: Vec<(usize, Option<usize>)>,
+    /// Row the cursor is on; never a hunk header
+    pub cursor: usize,
+    /// Row where the selection started; equals `cursor` when nothing is extended
+    pub anchor: usize,
+}
+
+impl LinePicker {
+    pub fn new(patch: FilePatch) -> Self {
+        let mut rows = Vec::new();
+        for (hunk_idx, hunk) in patch.hunks.iter().enumerate() {
+            rows.push((hunk_idx, None));
+            rows.extend((0..hunk.lines.len()).map(|i| (hunk_idx, Some(i))));
+        }
+
+        let mut picker = Self {
+            patch,
+            rows,
+            cursor: 0,
+            anchor: 0,
+        };
+        // Start on the first change rather than on context
+        picker.cursor = (0..picker.rows.len())
+            .find(|&row| picker.line(row).is_some_and(is_change))
+            .unwrap_or(1.min(picker.rows.len().saturating_sub(1)));
+        picker.anchor = picker.cursor;
+        picker
+    }
+
+    fn line(&self, row: usize) -> Option<&str> {
+        let (hunk_idx, line_idx) = *self.rows.get(row)?;
+        Some(self.patch.hunks[hunk_idx].lines[line_idx?].as_str())
+    }
+
+    /// Every displayed row: hunk headers and body lines
+    pub fn display_lines(&self) -> Vec<String> {
+        self.rows
+            .iter()
+            .map(|&(hunk_idx, line_idx)| {
+                let hunk = &self.patch.hunks[hunk_idx];
+                match line_idx {
+                    Some(i) => hunk.lines[i].clone(),
+                    None => hunk.header(),
+                }
+            })
+            .collect()
+    }
+
+    /// First and last selected rows
+    pub fn selected_rows(&self) -> (usize, usize) {
+        (self.anchor.min(self.cursor), self.anchor.max(self.cursor))
+    }
+
+    /// Moves the cursor by one body line, skipping hunk headers. With
+    /// `extend` the selection grows from the anchor, otherwise it collapses
+    /// onto the cursor.
+    pub fn move_cursor(&mut self, down: bool, extend: bool) {
+        let next = if down {
+            (self.cursor + 1..self.rows.len()).find(|&row| self.rows[row].1.is_some())
+        } else {
+            (0..self.cursor).rev().find(|&row| self.rows[row].1.is_some())
+        };
+        if let Some(row) = next {
+            self.cursor = row;
+        }
+        if !extend {
+            self.anchor = self.cursor;
+        }
+    }
+
+    /// The selection in the shape `FilePatch::build` expects
+    pub fn selection(&self) -> Vec<Vec<bool>> {
+        let mut selected = self.patch.empty_selection();
+        let (first, last) = self.selected_rows();
+        for &(hunk_idx, line_idx) in &self.rows[first..=last] {
+            if let Some(i) = line_idx {
+                selected[hunk_idx][i] = true;
+            }
+        }
+        selected
+    }
+
+    /// Number of changed lines in the selection
+    pub fn selected_changes(&self) -> usize {
+        let (first, last) = self.selected_rows();
+        (first..=last).filter(|&row| self.line(row).is_some_and(is_change)).count()
+    }
+}
+
+/// The hunk shown at `line` (0-based) of a diff: the hunk it falls in, or
+/// the next one when `line` is in a file header. Lines are returned with
+/// the "@@" header first.
+pub fn hunk_at_line(diff: &str, line: usize) -> Option<Vec<&str>> {
+    let lines: Vec<&str> = diff.lines().collect();
+    let starts: Vec<usize> = (0..lines.len()).filter(|&i| lines[i].starts_with("@@")).collect();
+    let end_of = |start: usize| {
+        (start + 1..lines.len())
+            .find(|&i| lines[i].starts_with("@@") || lines[i].starts_with("diff "))
+            .unwrap_or(lines.len())
+    };
+
+    let start = starts
+        .iter()
+        .rev()
+        .find(|&&start| start <= line && line < end_of(start))
+        .or_else(|| starts.iter().find(|&&start| start > line))?;
+    Some(lines[*start..end_of(*start)].to_vec())
+}
+
+/// Text of a hunk for the clipboard. Without markers it reads like the
+/// code after the change: no "@@" header, removed lines left out and the
+/// leading ' '/'+' dropped.
+pub fn hunk_text(hunk: &[&str], markers: bool) -> String {
+    if markers {
+        return hunk.join("\n");
+    }
+    hunk.iter()
+        .skip(1)
+        .filter_map(|line| line.strip_prefix('+').or_else(|| line.strip_prefix(' ')))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/f.txt b/f.txt\nindex 1..2 100644\n--- a/f.txt\n+++ b/f.txt\n@@ -1,5 +1,5 @@ fn main\n one\n-two\n+TWO\n three\n four\n-five\n+FIVE\n";
+
+    #[test]
+    fn test_parse_file_patch() {
+        let patch = FilePatch::parse(DIFF);
+
+        assert_eq!(patch.header.len(), 4);
+        assert_eq!(patch.hunks.len(), 1);
+        assert_eq!(patch.hunks[0].section, "fn main");
+        assert_eq!(patch.hunks[0].change_runs(), vec![1..3, 5..7]);
+    }
+
+    #[test]
+    fn test_build_partial_patch() {
+        let patch = FilePatch::parse(DIFF);
+        let mut selected = patch.empty_selection();
+        selected[0][5] = true;
+        selected[0][6] = true;
+
+        let built = patch.build(&selected, false).unwrap();
+        assert!(built.contains("@@ -1,5 +1,5 @@ fn main\n one\n two\n three\n four\n-five\n+FIVE\n"));
+
+        // Unstaging keeps the unselected '+' line as context instead
+        let reversed = patch.build(&selected, true).unwrap();
+        assert!(reversed.contains(" one\n TWO\n three\n"));
+    }
+
+    #[test]
+    fn test_hunk_at_line() {
+        let diff = format!("{}@@ -9,2 +9,2 @@\n ten\n-eleven\n+ELEVEN\n", DIFF);
+
+        // In the file header: the first hunk
+        let first = hunk_at_line(&diff, 1).unwrap();
+        assert_eq!(first[0], "@@ -1,5 +1,5 @@ fn main");
+        assert_eq!(first.len(), 8);
+        assert_eq!(hunk_at_line(&diff, 11), Some(first));
+
+        let second = hunk_at_line(&diff, 14).unwrap();
+        assert_eq!(hunk_text(&second, true), "@@ -9,2 +9,2 @@\n ten\n-eleven\n+ELEVEN");
+        assert_eq!(hunk_text(&second, false), "ten\nELEVEN");
+        assert_eq!(hunk_at_line("", 0), None);
+    }
+
+    #[test]
+    fn test_build_nothing_selected() {
+        let patch = FilePatch::parse(DIFF);
+        assert!(patch.build(&patch.empty_selection(), false).is_none());
+    }
+
+    #[test]
+    fn test_picker_split_and_decide() {
+        let mut picker = HunkPicker::new(FilePatch::parse(DIFF));
+        assert_eq!(picker.len(), 1);
+        assert!(picker.split());
+        assert_eq!(picker.len(), 2);
+
+        picker.decide(false);
+        picker.decide(true);
+        assert!(picker.is_done());
+        assert_eq!(picker.chosen_count(), 1);
+        assert!(picker.patch.build(&picker.selected, false).unwrap().contains("-five\n+FIVE"));
+    }
+
+    #[test]
+    fn test_line_picker_selects_a_range() {
+        let mut picker = LinePicker::new(FilePatch::parse(DIFF));
+        // Row 0 is the hunk header; the cursor starts on "-two"
+        assert_eq!(picker.cursor, 2);
+
+        picker.move_cursor(true, true);
+        assert_eq!(picker.selected_rows(), (2, 3));
+        assert_eq!(picker.selected_changes(), 2);
+
+        let built = picker.patch.build(&picker.selection(), false).unwrap();
+        assert!(built.contains("@@ -1,5 +1,5 @@ fn main\n one\n-two\n+TWO\n three\n four\n five\n"));
+
+        // Stage just the removal: the added line is left out
+        picker.move_cursor(false, false);
+        let built = picker.patch.build(&picker.selection(), false).unwrap();
+        assert!(built.contains("@@ -1,5 +1,4 @@ fn main\n one\n-two\n three\n"));
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Reveals a file in the OS file manager (Finder, Explorer, or the
+/// default handler for its containing directory via xdg-open)
+pub fn reveal_in_file_manager(path: &str) -> Result<String> {
+    let path = Path::new(path);
+    let absolute = std::env::current_dir()
+        .context("Failed to resolve current directory")?
+        .join(path);
+
+    let (program, args): (&str, Vec<String>) = if cfg!(target_os = "macos") {
+        ("open", vec!["-R".to_string(), absolute.display().to_string()])
+    } else if cfg!(target_os = "windows") {
+        ("explorer", vec![format!("/select,{}", absolute.display())])
+    } else {
+        // xdg-open cannot select a file, so open the containing directory
+        let dir = if absolute.is_dir() {
+            absolute.clone()
+        } else {
+            absolute
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| absolute.clone())
+        };
+        ("xdg-open", vec![dir.display().to_string()])
+    };
+
+    let mut child = Command::new(program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("No file manager available ('{}' could not be started)", program))?;
+    // Reap the opener once it exits so it doesn't linger as a zombie
+    std::thread::spawn(move || child.wait());
+
+    Ok(format!("Revealed {} in file manager", path.display()))
+}
+
+/// Opens `path` in `editor` (a command line such as "code --wait", run by
+/// the shell like git does) and waits for it to exit. The caller must
+/// release the terminal first.
+pub fn run_editor(editor: &str, path: &Path) -> Result<()> {
+    let status = if cfg!(target_os = "windows") {
+        Command::new("cmd")
+            .arg("/C")
+            .arg(format!("{} \"{}\"", editor, path.display()))
+            .status()
+    } else {
+        Command::new("sh")
+            .args(["-c", &format!("{} \"$@\"", editor), editor])
+            .arg(path)
+            .status()
+    }
+    .with_context(|| format!("Failed to start editor '{}'", editor))?;
+
+    if !status.success() {
+        anyhow::bail!("Editor '{}' exited with {}", editor, status);
+    }
+    Ok(())
+}
+
+/// Runs the user's interactive shell ($SHELL, or COMSPEC on Windows) in
+/// `dir` and waits for it to exit. The caller must release the terminal first.
+pub fn run_shell(dir: &str) -> Result<()> {
+    let shell = if cfg!(target_os = "windows") {
+        std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string())
+    } else {
+        std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+    };
+
+    Command::new(&shell)
+        .current_dir(dir)
+        .status()
+        .with_context(|| format!("Failed to start shell '{}'", shell))?;
+
+    Ok(())
+}
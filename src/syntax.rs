@@ -1,6 +1,9 @@
-use ratatui::style::{Color, Style};
+use crate::git::ConflictMarker;
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Mutex;
 use syntect::easy::HighlightLines;
 use syntect::highlighting::{Theme, ThemeSet};
 use syntect::parsing::{SyntaxReference, SyntaxSet};
@@ -12,15 +15,30 @@ fn get_syntax_set() -> &'static SyntaxSet {
     SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
 }
 
-/// Lazy-loaded theme
-fn get_theme() -> &'static Theme {
+/// A dark theme that works well in terminals
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// Lazy-loaded bundled themes
+fn get_theme_set() -> &'static ThemeSet {
     use std::sync::OnceLock;
-    static THEME: OnceLock<Theme> = OnceLock::new();
-    THEME.get_or_init(|| {
-        let theme_set = ThemeSet::load_defaults();
-        // Use a dark theme that works well in terminals
-        theme_set.themes["base16-ocean.dark"].clone()
-    })
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    THEME_SET.get_or_init(ThemeSet::load_defaults)
+}
+
+/// Looks up a theme by name, falling back to the default
+fn get_theme(name: &str) -> &'static Theme {
+    let themes = &get_theme_set().themes;
+    themes.get(name).unwrap_or(&themes[DEFAULT_THEME])
+}
+
+/// Names of the bundled themes, in a stable order
+pub fn theme_names() -> Vec<&'static str> {
+    get_theme_set().themes.keys().map(|name| name.as_str()).collect()
+}
+
+/// Whether a theme with this name exists
+pub fn has_theme(name: &str) -> bool {
+    get_theme_set().themes.contains_key(name)
 }
 
 /// Extracts the file extension from a filename
@@ -28,24 +46,36 @@ fn get_extension(filename: &str) -> Option<&str> {
     Path::new(filename).extension()?.to_str()
 }
 
+/// Syntaxes already resolved, keyed by extension so a commit touching many
+/// files of the same type searches the syntax set once per type. Files
+/// without an extension are looked up each time rather than filling the
+/// cache with one entry per name
+fn get_syntax_cache() -> &'static Mutex<HashMap<String, &'static SyntaxReference>> {
+    use std::sync::OnceLock;
+    static SYNTAX_CACHE: OnceLock<Mutex<HashMap<String, &'static SyntaxReference>>> = OnceLock::new();
+    SYNTAX_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
 /// Gets the syntax reference for a filename
 fn get_syntax_for_file(filename: &str) -> &'static SyntaxReference {
-    let syntax_set = get_syntax_set();
-
-    // Try to find syntax by extension
-    if let Some(extension) = get_extension(filename) {
-        if let Some(syntax) = syntax_set.find_syntax_by_extension(extension) {
-            return syntax;
-        }
-    }
-
-    // Try to find syntax by filename (for files like Makefile, Dockerfile, etc.)
-    if let Some(syntax) = syntax_set.find_syntax_by_first_line(filename) {
+    let extension = get_extension(filename);
+    let mut cache = get_syntax_cache().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(syntax) = extension.and_then(|extension| cache.get(extension)) {
         return syntax;
     }
 
-    // Fallback to plain text
-    syntax_set.find_syntax_plain_text()
+    let syntax_set = get_syntax_set();
+
+    // Try to find syntax by extension, then by filename (for files like
+    // Makefile, Dockerfile, etc.), falling back to plain text
+    let syntax = extension
+        .and_then(|extension| syntax_set.find_syntax_by_extension(extension))
+        .or_else(|| syntax_set.find_syntax_by_first_line(filename))
+        .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+    if let Some(extension) = extension {
+        cache.insert(extension.to_string(), syntax);
+    }
+    syntax
 }
 
 /// Converts syntect color to ratatui color
@@ -55,9 +85,9 @@ fn syntect_to_ratatui_color(color: syntect::highlighting::Color) -> Color {
 
 /// Highlights diff content with syntax highlighting
 /// Returns a vector of ratatui Lines with both syntax and diff coloring
-pub fn highlight_diff(diff_content: &str, filename: &str) -> Vec<Line<'static>> {
+pub fn highlight_diff(diff_content: &str, filename: &str, theme_name: &str) -> Vec<Line<'static>> {
     let syntax = get_syntax_for_file(filename);
-    let theme = get_theme();
+    let theme = get_theme(theme_name);
     let mut highlighter = HighlightLines::new(syntax, theme);
 
     let mut result_lines = Vec::new();
@@ -87,6 +117,133 @@ pub fn highlight_diff(diff_content: &str, filename: &str) -> Vec<Line<'static>>
     result_lines
 }
 
+/// Highlights a whole file (no diff markers)
+pub fn highlight_file(content: &str, filename: &str, theme_name: &str) -> Vec<Line<'static>> {
+    let syntax_set = get_syntax_set();
+    let mut highlighter = HighlightLines::new(get_syntax_for_file(filename), get_theme(theme_name));
+
+    content
+        .lines()
+        .map(|line| {
+            let highlighted = highlighter.highlight_line(line, syntax_set).unwrap_or_default();
+            Line::from(
+                highlighted
+                    .into_iter()
+                    .map(|(style, text)| {
+                        Span::styled(text.to_string(), Style::default().fg(syntect_to_ratatui_color(style.foreground)))
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+/// Marks the conflict marker lines of a highlighted working-tree file, so
+/// each conflict stands out from the code around it
+pub fn mark_conflicts(lines: &mut [Line<'static>], content: &str) {
+    for (line, text) in lines.iter_mut().zip(content.lines()) {
+        let style = match ConflictMarker::parse(text) {
+            Some(ConflictMarker::Ours) => Style::default().fg(Color::Black).bg(Color::Green),
+            Some(ConflictMarker::Base) => Style::default().fg(Color::Black).bg(Color::Gray),
+            Some(ConflictMarker::Separator) => Style::default().fg(Color::Black).bg(Color::Yellow),
+            Some(ConflictMarker::Theirs) => Style::default().fg(Color::Black).bg(Color::Blue),
+            None => continue,
+        };
+        *line = Line::from(Span::styled(text.to_string(), style.add_modifier(Modifier::BOLD)));
+    }
+}
+
+/// Renders diff content with colored text only: green additions, red
+/// deletions, no backgrounds and no syntax highlighting
+pub fn simple_diff(diff_content: &str) -> Vec<Line<'static>> {
+    diff_content
+        .lines()
+        .map(|line| {
+            let style = if line.starts_with("@@") {
+                Style::default().fg(Color::Cyan)
+            } else if line.starts_with('+') {
+                Style::default().fg(Color::Green)
+            } else if line.starts_with('-') {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            Line::from(Span::styled(line.to_string(), style))
+        })
+        .collect()
+}
+
+/// Cuts lines longer than `limit` characters (minified code, base64 blobs)
+/// so they don't wrap into thousands of rows. Returns the shortened text
+/// and, for each cut line, its index and the number of characters hidden.
+/// A limit of 0 never cuts.
+pub fn truncate_long_lines(diff_content: &str, limit: usize) -> (String, Vec<(usize, usize)>) {
+    if limit == 0 {
+        return (diff_content.to_string(), Vec::new());
+    }
+    let mut cut = Vec::new();
+    let lines: Vec<&str> = diff_content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| match line.char_indices().nth(limit) {
+            Some((end, _)) => {
+                cut.push((i, line[end..].chars().count()));
+                &line[..end]
+            }
+            None => line,
+        })
+        .collect();
+    (lines.join("\n"), cut)
+}
+
+/// Expands tabs to spaces so indentation lines up whatever the terminal's
+/// tab stops are. Tab stops are counted from after the +/-/space marker of
+/// diff lines, so code keeps the alignment it has in the file; a width of 0
+/// leaves tabs alone.
+pub fn expand_diff_tabs(diff_content: &str, tab_width: usize) -> String {
+    if tab_width == 0 || !diff_content.contains('\t') {
+        return diff_content.to_string();
+    }
+    diff_content
+        .lines()
+        .map(|line| match line.chars().next() {
+            Some(marker @ ('+' | '-' | ' ')) if !line.starts_with("@@") => {
+                format!("{}{}", marker, expand_tabs(&line[1..], tab_width))
+            }
+            _ => expand_tabs(line, tab_width),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Expands the tabs of a single line, starting at column 0
+pub fn expand_tabs(line: &str, tab_width: usize) -> String {
+    if tab_width == 0 || !line.contains('\t') {
+        return line.to_string();
+    }
+    let mut expanded = String::with_capacity(line.len());
+    let mut column = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = tab_width - column % tab_width;
+            expanded.extend(std::iter::repeat_n(' ', spaces));
+            column += spaces;
+        } else {
+            expanded.push(c);
+            column += 1;
+        }
+    }
+    expanded
+}
+
+/// Renders diff content as unstyled text (used when colors are disabled)
+pub fn plain_diff(diff_content: &str) -> Vec<Line<'static>> {
+    diff_content
+        .lines()
+        .map(|line| Line::from(line.to_string()))
+        .collect()
+}
+
 /// Highlights a single line and applies diff marker color
 fn highlight_line_with_diff_marker(
     code: &str,
@@ -126,3 +283,43 @@ fn highlight_line_with_diff_marker(
 
     Line::from(spans)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_diff_tabs() {
+        let diff = "@@ -1,2 +1,2 @@\n-\tif x {\n+ab\tc\n \t\treturn;";
+        assert_eq!(
+            expand_diff_tabs(diff, 4),
+            "@@ -1,2 +1,2 @@\n-    if x {\n+ab  c\n         return;"
+        );
+        assert_eq!(expand_diff_tabs(diff, 0), diff);
+    }
+
+    #[test]
+    fn test_truncate_long_lines() {
+        let diff = "+short\n+ééééé\n-abcdefgh";
+        assert_eq!(truncate_long_lines(diff, 0), (diff.to_string(), Vec::new()));
+
+        // Cuts count characters, so multibyte ones are never split
+        let (text, cut) = truncate_long_lines(diff, 4);
+        assert_eq!(text, "+sho\n+ééé\n-abc");
+        assert_eq!(cut, vec![(0, 2), (1, 2), (2, 5)]);
+
+        let (text, cut) = truncate_long_lines(diff, 6);
+        assert_eq!(text, "+short\n+ééééé\n-abcde");
+        assert_eq!(cut, vec![(2, 3)]);
+    }
+
+    #[test]
+    fn test_syntax_cache_is_keyed_by_extension() {
+        assert_eq!(get_syntax_for_file("src/main.rs").name, "Rust");
+        assert_eq!(get_syntax_for_file("src/lib.rs").name, "Rust");
+        get_syntax_for_file("Makefile-without-extension");
+        let cache = get_syntax_cache().lock().unwrap();
+        assert!(cache.contains_key("rs"));
+        assert!(!cache.keys().any(|key| key.contains('/') || key.starts_with("Makefile")));
+    }
+}
@@ -0,0 +1,82 @@
+//! Scratch repositories for tests that run git end to end
+
+use std::path::Path;
+use std::process::Command;
+
+/// Set in the child test process that runs inside the scratch repository
+const CHILD_ENV: &str = "GITU_TEST_REPO";
+
+/// Runs `body` inside a fresh repository on branch `main` holding one empty
+/// commit. Git runs in the process's working directory, which all tests
+/// share, so the body runs in a child test process started in the
+/// repository; `test` is the test's path, e.g. "git::tests::test_reword".
+/// The child gets an empty HOME, so neither git nor gitu read the
+/// developer's config or search history.
+pub fn in_scratch_repo(test: &str, body: impl FnOnce()) {
+    if std::env::var_os(CHILD_ENV).is_some() {
+        body();
+        return;
+    }
+
+    let dir = std::env::temp_dir().join(format!("gitu-test-{}-{}", std::process::id(), test.replace("::", "-")));
+    let home = dir.with_extension("home");
+    for dir in [&dir, &home] {
+        let _ = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(dir).unwrap();
+    }
+    for args in [
+        &["init", "-q", "-b", "main"][..],
+        &["config", "user.name", "gitu"],
+        &["config", "user.email", "gitu@example.com"],
+        &["config", "commit.gpgsign", "false"],
+        &["commit", "-q", "--allow-empty", "-m", "Initial"],
+    ] {
+        git_in(&dir, args, Some(&home));
+    }
+
+    let mut child = Command::new(std::env::current_exe().unwrap());
+    child
+        .args([test, "--exact", "--nocapture", "--test-threads=1"])
+        .env(CHILD_ENV, "1")
+        .current_dir(&dir);
+    let output = isolate(&mut child, &home).output().unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    let _ = std::fs::remove_dir_all(&home);
+    assert!(
+        output.status.success(),
+        "{} failed in its scratch repository:\n{}{}",
+        test,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Runs git in the scratch repository and returns its trimmed stdout
+pub fn git(args: &[&str]) -> String {
+    git_in(Path::new("."), args, None)
+}
+
+/// Points HOME at `home` and drops the variables that would bypass it
+fn isolate<'a>(command: &'a mut Command, home: &Path) -> &'a mut Command {
+    command
+        .env("HOME", home)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("XDG_STATE_HOME")
+}
+
+fn git_in(dir: &Path, args: &[&str], home: Option<&Path>) -> String {
+    let mut command = Command::new("git");
+    command.args(args).current_dir(dir);
+    if let Some(home) = home {
+        isolate(&mut command, home);
+    }
+    let output = command.output().unwrap();
+    assert!(
+        output.status.success(),
+        "git {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
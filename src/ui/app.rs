@@ -1,6 +1,18 @@
-use crate::git::{get_commit_diff, get_commits, Branch, Commit, CommitDiff, SearchFilter, StatusFile, StashEntry};
-use anyhow::Result;
+use crate::git::{
+    get_commit_diff, get_commit_diff_against, get_commits, BisectMark, Branch, Commit, CommitDiff, Decoration, FileDiff, MergeFilter,
+    InProgress, SearchFilter,
+    ReflogEntry, StatusFile, StashEntry, Tag, TagSignature, UpstreamDivergence, Worktree,
+};
+use crate::config::Config;
+use crate::keymap::Keymap;
+use crate::patch::{FilePatch, HunkPicker, LinePicker};
+use anyhow::{Context, Result};
+use ratatui::text::Line;
 use ratatui::widgets::ListState;
+use std::collections::{HashMap, HashSet};
+
+/// Tab width when neither `tab_width` nor git's `core.tabwidth` is set
+const DEFAULT_TAB_WIDTH: usize = 4;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Panel {
@@ -8,6 +20,21 @@ pub enum Panel {
     Log,
     Stash,
     Branches,
+    Tags,
+}
+
+impl Panel {
+    /// Parses a panel name as used in the config file
+    pub fn from_name(name: &str) -> Option<Panel> {
+        match name.to_ascii_lowercase().as_str() {
+            "status" => Some(Panel::Status),
+            "log" => Some(Panel::Log),
+            "stash" => Some(Panel::Stash),
+            "branches" => Some(Panel::Branches),
+            "tags" => Some(Panel::Tags),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -17,52 +44,337 @@ pub enum MessageType {
     Info,
 }
 
+/// An operation that waits for a yes/no answer in the confirm popup
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfirmAction {
+    /// The upstream has new commits: pull them, then push
+    PullThenPush,
+    /// Merge the named branch into the current one
+    Merge(String),
+    /// Rebase the current branch onto the named branch
+    Rebase(String),
+    /// Delete the named local tag
+    DeleteTag(String),
+    /// Push a newly created tag to the default remote
+    PushTag(String),
+    /// Move a local branch to a commit from its reflog
+    ResetBranch { branch: String, target: String, is_current: bool },
+    /// Store a stash again under a new message
+    Restash { index: usize, branch: String, message: String },
+    /// Quit while a git operation is still running
+    Quit,
+}
+
+/// A program that needs the whole terminal. The main loop suspends the
+/// TUI, calls `App::run_external_command`, and then restores it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExternalCommand {
+    Shell,
+    /// Write the commit message in the editor; `amend` rewrites HEAD and
+    /// `push` pushes once committed
+    CommitEditor { amend: bool, push: bool },
+}
+
+/// Where a Log commit stands relative to the upstream of the current branch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpstreamState {
+    Unpushed,
+    Unpulled,
+    ForkPoint,
+}
+
+/// State of the confirm popup
+pub struct ConfirmPrompt {
+    pub message: String,
+    /// Extra lines shown under the message, e.g. a preview of affected commits
+    pub details: Vec<String>,
+    pub action: ConfirmAction,
+}
+
+/// State of the `git add -p` style interactive staging popup
+pub struct InteractiveStaging {
+    pub path: String,
+    /// True when walking a staged file's hunks to unstage them
+    pub unstage: bool,
+    /// True when the chosen hunks go into a new stash instead of the index
+    pub stash: bool,
+    pub picker: HunkPicker,
+}
+
+/// State of the line staging popup, where a range of lines is selected
+/// visually and staged (or unstaged) on its own
+pub struct LineStaging {
+    pub path: String,
+    /// True when selecting lines of a staged file to unstage them
+    pub unstage: bool,
+    pub picker: LinePicker,
+}
+
+/// Prompts for a new author and date of HEAD, filled in with the current
+/// values; the author is edited first, then the date
+pub struct AmendMetadata {
+    pub author: String,
+    pub date: String,
+    pub editing_date: bool,
+}
+
+/// How file paths are shown in the Status and changed-file lists
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathDisplay {
+    /// Relative to the repository root, as git prints them
+    Full,
+    /// Relative to the deepest directory all listed files share
+    Relative,
+    /// File name only
+    Name,
+}
+
+/// A row of the Status list
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StatusRow {
+    /// Section header; `staged` tells which section
+    Header { staged: bool },
+    File(usize),
+}
+
 pub struct App {
     // Panel system
     pub current_panel: Panel,
 
+    /// Short hash and subject of HEAD, shown next to the tabs
+    pub head: Option<(String, String)>,
+    /// Checked-out branch; None when HEAD is detached
+    pub head_branch: Option<String>,
+
+    // Settings from the config file
+    pub config: Config,
+    pub keymap: Keymap,
+    /// Syntax highlighting theme used for diffs
+    pub theme: String,
+    /// Columns a tab expands to in diffs and viewed files
+    pub tab_width: usize,
+    /// Show diffs as plain green/red text instead of syntax highlighting
+    /// on colored backgrounds (for this session only)
+    pub simple_diff_colors: bool,
+    /// Show diff lines past `max_diff_line_length` in full
+    pub expand_long_lines: bool,
+    pub path_display: PathDisplay,
+
     // Log panel (existing functionality)
     pub commits: Vec<Commit>,
     pub list_state: ListState,
     pub show_diff: bool,
+    /// Pinned diff: moving through the log loads each commit's diff
+    pub auto_diff: bool,
+    /// Hide the commit and file lists so the diff gets the whole width
+    pub diff_fullscreen: bool,
     pub current_diff: Option<CommitDiff>,
+    /// `git notes` of the commit whose diff is open
+    pub commit_notes: Option<String>,
     pub diff_scroll: u16,
+    /// For merge commits, which parent the diff is taken against; one past
+    /// the last parent is git's combined diff
+    pub diff_parent: usize,
+    /// Set when the open diff is the working tree against this commit
+    /// rather than the commit's own changes
+    pub worktree_diff_base: Option<String>,
     pub file_list_state: ListState,
+    /// Diffs are generated with --ignore-all-space
+    pub ignore_whitespace: bool,
+    /// Narrows the changed files of the open diff (glob or substring);
+    /// `file_list_state` then indexes the matching files
+    pub file_filter: Option<String>,
+    pub file_filter_input_mode: bool,
+    /// Unpushed/unpulled commits relative to the upstream, for the graph
+    pub upstream_divergence: Option<UpstreamDivergence>,
     pub search_mode: bool,
     pub search_query: String,
+    /// Previous search queries, oldest first
+    pub search_history: Vec<String>,
+    /// Entry of `search_history` currently recalled with Up/Down
+    pub search_history_index: Option<usize>,
     pub active_filter: Option<SearchFilter>,
+    /// Log lists only HEAD's first-parent history
+    pub first_parent: bool,
+    /// Log lists only merges, or hides them
+    pub merge_filter: MergeFilter,
+    /// Log shows a header row above each calendar day's commits
+    pub group_by_day: bool,
     pub tree_view_mode: bool,
     pub tree_file_selected: bool,
 
+    // Commit browser: every file of a commit, not just the changed ones.
+    // Open while `browse_hash` is set.
+    pub browse_hash: Option<String>,
+    pub browse_files: Vec<String>,
+    /// Directory being listed, "" for the root, otherwise ending in '/'
+    pub browse_dir: String,
+    /// (name, is_dir) of the entries in `browse_dir`, directories first
+    pub browse_entries: Vec<(String, bool)>,
+    pub browse_list_state: ListState,
+    /// Path and highlighted lines of the file being viewed (None for a
+    /// binary file's lines)
+    pub browse_file: Option<(String, Option<Vec<Line<'static>>>)>,
+    pub browse_scroll: u16,
+
     // Status panel
     pub status_files: Vec<StatusFile>,
     pub status_list_state: ListState,
     pub commit_message_mode: bool,
     pub commit_message_input: String,
+    /// Message of the last failed commit, restored when the input reopens
+    pub commit_message_draft: Option<String>,
+    /// Lines added/removed per staged file, shown above the commit input
+    pub staged_stats: HashMap<String, Option<(usize, usize)>>,
     pub status_show_diff: bool,
     pub status_diff_content: Option<String>,
+    pub status_diff_path: Option<String>,
     pub status_diff_scroll: u16,
+    /// Set when the diff pane shows a conflicted file's working-tree content
+    /// instead of a diff: the lines where its conflicts start
+    pub status_conflicts: Option<Vec<usize>>,
+    /// The conflicted file highlighted with its markers picked out, once
+    /// when it is loaded
+    pub status_conflict_lines: Vec<Line<'static>>,
+    pub expanded_untracked_dirs: HashSet<String>,
+    pub untracked_dir_counts: HashMap<String, usize>,
+    pub interactive_staging: Option<InteractiveStaging>,
+    pub line_staging: Option<LineStaging>,
+    // Status sections folded down to their header
+    pub staged_collapsed: bool,
+    pub unstaged_collapsed: bool,
 
     // Stash panel
     pub stashes: Vec<StashEntry>,
     pub stash_list_state: ListState,
+    /// `git stash show --stat` summary of the selected stash
+    pub stash_stat: Option<String>,
+    /// Per-file diff of the selected stash while it is open
+    pub stash_diff: Option<CommitDiff>,
+    pub stash_file_state: ListState,
+    pub stash_diff_scroll: u16,
+    /// Apply and pop with `--index`, restaging what was staged
+    pub stash_restore_index: bool,
     pub stash_input_mode: bool,
     pub stash_message_input: String,
+    /// Index of the stash being given a new message; the stash prompt
+    /// re-stashes instead of creating a stash while this is set
+    pub restash_index: Option<usize>,
+    // Branch picker for applying the selected stash on another branch
+    pub stash_target_visible: bool,
+    pub stash_target_branches: Vec<String>,
+    pub stash_target_list_state: ListState,
 
     // Branches panel
     pub branches: Vec<Branch>,
     pub branch_list_state: ListState,
+    pub tags: Vec<Tag>,
+    pub tag_list_state: ListState,
+    pub branch_merge_base: Option<(String, String)>,
     pub new_branch_input_mode: bool,
     pub new_branch_name_input: String,
 
+    // Jump-to-commit prompt in the Log panel (hash or any ref)
+    pub goto_input_mode: bool,
+    pub goto_input: String,
+
+    // Tag input mode ("name [annotation message]")
+    pub tag_input_mode: bool,
+    pub tag_input: String,
+    /// Create the tag signed (`git tag -s`)
+    pub tag_sign: bool,
+
+    // Reflog popup
+    /// Branch whose reflog popup is open
+    pub reflog_branch: Option<String>,
+    pub reflog: Vec<ReflogEntry>,
+    pub reflog_list_state: ListState,
+
+    // Outgoing/incoming commits popup: the upstream name while it is open
+    pub sync_upstream: Option<String>,
+    /// True when listing the upstream's commits that HEAD lacks
+    pub sync_incoming: bool,
+    pub sync_commits: Vec<Commit>,
+    pub sync_list_state: ListState,
+
+    // Branch comparison popup: the selected branch and the copy of it
+    // (same name, another remote or the local branch) it is compared with
+    pub branch_compare: Option<(String, String)>,
+    /// Commits only on the left branch (false) or only on the right one (true)
+    pub branch_compare_commits: Vec<(bool, Commit)>,
+    pub branch_compare_list_state: ListState,
+    pub branch_compare_merge_base: Option<(String, String)>,
+
+    // Worktrees popup
+    pub worktrees_visible: bool,
+    pub worktrees: Vec<Worktree>,
+    pub worktree_list_state: ListState,
+    pub current_worktree: Option<String>,
+    pub worktree_input_mode: bool,
+    pub worktree_input: String,
+
+    // Remote picker (prune-fetch, or push when `remotes_push` is set)
+    pub remotes_visible: bool,
+    /// The remotes popup picks where to push HEAD's branch
+    pub remotes_push: bool,
+    pub remotes: Vec<String>,
+    /// Fetch URL of each remote listed in the remotes popup
+    pub remote_urls: HashMap<String, String>,
+    /// Editing the URL of the remote selected in the remotes popup
+    pub remote_url_input_mode: bool,
+    pub remote_url_input: String,
+    pub remote_list_state: ListState,
+
+    // Bisect
+    pub bisect_active: bool,
+    /// Merge, rebase, cherry-pick or revert waiting to be finished
+    pub in_progress: Option<InProgress>,
+
+    /// Hash of the commit where a Log range mark starts; the range runs to
+    /// the selected commit
+    pub range_anchor: Option<String>,
+
     // Amend mode
     pub amend_mode: bool,
+    /// Also stage modified tracked files when amending (`commit --amend -a`)
+    pub amend_include_unstaged: bool,
+
+    // Push right after the commit succeeds
+    pub commit_and_push: bool,
+    /// Pass `--allow-empty` so a commit can be made with nothing staged
+    pub commit_allow_empty: bool,
+
+    // Commit whose subject the commit message input rewords
+    pub reword_target: Option<String>,
+    pub amend_metadata: Option<AmendMetadata>,
+    /// One-line commit message typed on the status line
+    pub quick_commit_input: Option<String>,
+    /// Stage everything (`git add .`) before the quick commit
+    pub quick_commit_all: bool,
+    // Status file being renamed; the input holds the new path
+    pub rename_source: Option<String>,
+    pub rename_input: String,
 
     // Help popup
     pub help_visible: bool,
 
+    // Confirm popup
+    pub confirm: Option<ConfirmPrompt>,
+    /// Typed confirmation for discarding every change; Some while the popup is open
+    pub discard_all_input: Option<String>,
+    /// Also delete untracked files when discarding everything
+    pub discard_all_untracked: bool,
+
+    // Display
+    pub no_color: bool,
+
+    // Set to hand the terminal to an external program
+    pub external_command: Option<ExternalCommand>,
+
     // Common
     pub should_quit: bool,
+    /// Set while a git operation runs in the background; quitting asks first
+    pub operation_in_flight: bool,
     pub branch_input_mode: bool,
     pub branch_name_input: String,
     pub status_message: Option<String>,
@@ -76,14 +388,34 @@ impl App {
             list_state.select(Some(0));
         }
 
+        let (mut config, config_error) = match Config::load() {
+            Ok(config) => (config, None),
+            Err(e) => (Config::default(), Some(format!("{:#}", e))),
+        };
+
+        // Repository settings take precedence (but not over the theme the
+        // user saved); a broken file is only a warning
+        let repo_config_error = match Config::load_repo() {
+            Ok(Some(repo_config)) => {
+                config.merge_repo(repo_config);
+                None
+            }
+            Ok(None) => None,
+            Err(e) => Some(format!("{:#}", e)),
+        };
+
         // Try to load status, stash, and branch data
         let status_files = crate::git::get_status().unwrap_or_default();
         let stashes = crate::git::get_stashes().unwrap_or_default();
         let branches = crate::git::get_branches().unwrap_or_default();
+        let tags = crate::git::get_tags().unwrap_or_default();
+        let head = crate::git::get_head().ok();
+        let head_branch = crate::git::get_head_branch();
 
+        // Row 0 is a section header, so start on the first file
         let mut status_list_state = ListState::default();
         if !status_files.is_empty() {
-            status_list_state.select(Some(0));
+            status_list_state.select(Some(1));
         }
 
         let mut stash_list_state = ListState::default();
@@ -96,55 +428,263 @@ impl App {
             branch_list_state.select(Some(0));
         }
 
-        Self {
+        let mut tag_list_state = ListState::default();
+        if !tags.is_empty() {
+            tag_list_state.select(Some(0));
+        }
+
+        let mut app = Self {
             current_panel: Panel::Status,
+            head,
+            head_branch,
+            config,
+            keymap: Keymap::default(),
+            theme: crate::syntax::DEFAULT_THEME.to_string(),
+            tab_width: DEFAULT_TAB_WIDTH,
+            simple_diff_colors: false,
+            expand_long_lines: false,
+            path_display: PathDisplay::Full,
 
             // Log panel
             commits,
             list_state,
             show_diff: false,
+            auto_diff: false,
+            diff_fullscreen: false,
             current_diff: None,
+            commit_notes: None,
             diff_scroll: 0,
+            diff_parent: 0,
+            worktree_diff_base: None,
             file_list_state: ListState::default(),
+            ignore_whitespace: false,
+            file_filter: None,
+            file_filter_input_mode: false,
+            upstream_divergence: crate::git::get_upstream_divergence().unwrap_or_default(),
             search_mode: false,
             search_query: String::new(),
+            search_history: crate::history::load_search_history(),
+            search_history_index: None,
             active_filter: None,
+            first_parent: false,
+            merge_filter: MergeFilter::All,
+            group_by_day: false,
             tree_view_mode: false,
             tree_file_selected: false,
 
+            browse_hash: None,
+            browse_files: Vec::new(),
+            browse_dir: String::new(),
+            browse_entries: Vec::new(),
+            browse_list_state: ListState::default(),
+            browse_file: None,
+            browse_scroll: 0,
+
             // Status panel
             status_files,
             status_list_state,
             commit_message_mode: false,
             commit_message_input: String::new(),
+            commit_message_draft: None,
+            staged_stats: HashMap::new(),
             status_show_diff: false,
             status_diff_content: None,
+            status_diff_path: None,
             status_diff_scroll: 0,
+            status_conflicts: None,
+            status_conflict_lines: Vec::new(),
+            expanded_untracked_dirs: HashSet::new(),
+            untracked_dir_counts: HashMap::new(),
+            interactive_staging: None,
+            line_staging: None,
+            staged_collapsed: false,
+            unstaged_collapsed: false,
 
             // Stash panel
             stashes,
             stash_list_state,
+            stash_stat: None,
+            stash_diff: None,
+            stash_file_state: ListState::default(),
+            stash_diff_scroll: 0,
+            stash_restore_index: false,
             stash_input_mode: false,
             stash_message_input: String::new(),
+            restash_index: None,
+            stash_target_visible: false,
+            stash_target_branches: Vec::new(),
+            stash_target_list_state: ListState::default(),
 
             // Branches panel
             branches,
             branch_list_state,
+            tags,
+            tag_list_state,
+            branch_merge_base: None,
             new_branch_input_mode: false,
             new_branch_name_input: String::new(),
+            goto_input_mode: false,
+            goto_input: String::new(),
+            tag_input_mode: false,
+            tag_input: String::new(),
+            tag_sign: false,
+
+            // Reflog popup
+            reflog_branch: None,
+            reflog: Vec::new(),
+            reflog_list_state: ListState::default(),
+
+            sync_upstream: None,
+            sync_incoming: false,
+            sync_commits: Vec::new(),
+            sync_list_state: ListState::default(),
+            branch_compare: None,
+            branch_compare_commits: Vec::new(),
+            branch_compare_list_state: ListState::default(),
+            branch_compare_merge_base: None,
+
+            // Worktrees popup
+            worktrees_visible: false,
+            worktrees: Vec::new(),
+            worktree_list_state: ListState::default(),
+            current_worktree: None,
+            worktree_input_mode: false,
+            worktree_input: String::new(),
+
+            // Remote picker (prune-fetch)
+            remotes_visible: false,
+            remotes_push: false,
+            remotes: Vec::new(),
+            remote_urls: HashMap::new(),
+            remote_url_input_mode: false,
+            remote_url_input: String::new(),
+            remote_list_state: ListState::default(),
+
+            // Bisect
+            bisect_active: crate::git::is_bisecting(),
+            in_progress: crate::git::get_in_progress(),
+
+            range_anchor: None,
 
             // Amend mode
             amend_mode: false,
+            amend_include_unstaged: false,
+
+            commit_and_push: false,
+            commit_allow_empty: false,
+            reword_target: None,
+            amend_metadata: None,
+            quick_commit_input: None,
+            quick_commit_all: false,
+            rename_source: None,
+            rename_input: String::new(),
 
             // Help popup
             help_visible: false,
 
+            // Confirm popup
+            confirm: None,
+            discard_all_input: None,
+            discard_all_untracked: false,
+
+            // Display
+            no_color: false,
+
+            external_command: None,
+
             // Common
             should_quit: false,
+            operation_in_flight: false,
             branch_input_mode: false,
             branch_name_input: String::new(),
             status_message: None,
             status_message_type: MessageType::Info,
+        };
+        app.load_untracked_dirs();
+        app.update_branch_merge_base();
+        app.update_stash_stat();
+        app.verify_selected_tag();
+        app.apply_config();
+        if let Some(e) = config_error {
+            app.set_status(format!("Config error: {}", e), MessageType::Error);
+        } else if let Some(e) = repo_config_error {
+            app.set_status(format!("Ignoring repo config: {}", e), MessageType::Error);
+        }
+        app.announce_in_progress();
+        app
+    }
+
+    /// Opened in the middle of a merge or rebase: shows the Status panel
+    /// with the first conflicted file selected and says how to go on, after
+    /// any config error already reported
+    fn announce_in_progress(&mut self) {
+        let Some(op) = self.in_progress else {
+            return;
+        };
+        let conflicts: Vec<usize> = (0..self.status_files.len())
+            .filter(|&i| self.status_files[i].status == crate::git::FileStatus::Conflicted)
+            .collect();
+
+        let msg = if conflicts.is_empty() {
+            format!(
+                "{} in progress: no conflicts left; finish with `{} --continue` or undo with `{} --abort`",
+                op.label(),
+                op.command(),
+                op.command()
+            )
+        } else {
+            self.current_panel = Panel::Status;
+            self.unstaged_collapsed = false;
+            let first = conflicts[0];
+            let row = self.status_rows().iter().position(|row| *row == StatusRow::File(first));
+            self.status_list_state.select(row);
+            format!(
+                "{} in progress: {} conflicted file(s) marked [U]. Resolve and stage them ({}), then `{} --continue` (or `--abort`)",
+                op.label(),
+                conflicts.len(),
+                self.keymap.hint("status.stage"),
+                op.command()
+            )
+        };
+        let msg = match self.status_message.take() {
+            Some(problem) => format!("{}. {}", problem, msg),
+            None => msg,
+        };
+        self.set_status(msg, MessageType::Error);
+    }
+
+    /// Applies the startup settings from the config file
+    fn apply_config(&mut self) {
+        if let Some(name) = self.config.default_panel() {
+            match Panel::from_name(name) {
+                Some(panel) => self.current_panel = panel,
+                None => {
+                    let msg = format!("Config error: unknown default_panel '{}'", name);
+                    self.set_status(msg, MessageType::Error);
+                }
+            }
+        }
+
+        if let Some(name) = self.config.theme() {
+            if crate::syntax::has_theme(name) {
+                self.theme = name.to_string();
+            } else {
+                let msg = format!("Config error: unknown theme '{}'", name);
+                self.set_status(msg, MessageType::Error);
+            }
+        }
+
+        self.tab_width = self.config.tab_width().unwrap_or_else(|| {
+            crate::git::get_config("core.tabwidth")
+                .ok()
+                .flatten()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(DEFAULT_TAB_WIDTH)
+        });
+
+        match Keymap::from_config(&self.config) {
+            Ok(keymap) => self.keymap = keymap,
+            Err(e) => self.set_status(format!("Config error: {}", e), MessageType::Error),
         }
     }
 
@@ -165,6 +705,9 @@ impl App {
         };
         self.list_state.select(Some(i));
         self.diff_scroll = 0;
+        if self.auto_diff {
+            self.show_selected_commit_diff();
+        }
     }
 
     pub fn previous(&mut self) {
@@ -184,6 +727,50 @@ impl App {
         };
         self.list_state.select(Some(i));
         self.diff_scroll = 0;
+        if self.auto_diff {
+            self.show_selected_commit_diff();
+        }
+    }
+
+    /// Pins the diff: j/k then move through the commits and each one's diff
+    /// is shown right away, staying on the same file when it changed too
+    pub fn toggle_auto_diff(&mut self) {
+        self.auto_diff = !self.auto_diff;
+        if self.auto_diff {
+            self.show_selected_commit_diff();
+            self.set_status("Diff pinned: j/k move between commits, PgUp/PgDn scroll".to_string(), MessageType::Info);
+        } else {
+            self.set_status("Diff unpinned".to_string(), MessageType::Info);
+        }
+    }
+
+    /// Loads the selected commit's diff into the open (or newly opened)
+    /// diff view, keeping the selected file when the commit touches it
+    fn show_selected_commit_diff(&mut self) {
+        let Some(commit) = self.list_state.selected().and_then(|i| self.commits.get(i)) else {
+            return;
+        };
+        let selected = self.selected_diff_file().map(|file| file.filename.clone());
+        self.diff_parent = 0;
+        let diff = match self.load_commit_diff(commit) {
+            Ok(diff) => diff,
+            Err(e) => {
+                self.set_status(format!("Error: {}", e), MessageType::Error);
+                return;
+            }
+        };
+        self.commit_notes = crate::git::get_commit_notes(&commit.hash).unwrap_or(None);
+        self.current_diff = Some(diff);
+        self.worktree_diff_base = None;
+        self.show_diff = true;
+        self.diff_scroll = 0;
+
+        let visible = self.visible_diff_files();
+        let index = selected
+            .and_then(|name| visible.iter().position(|file| file.filename == name))
+            .or((!visible.is_empty()).then_some(0));
+        self.file_list_state = ListState::default();
+        self.file_list_state.select(index);
     }
 
     pub fn scroll_diff_up(&mut self) {
@@ -203,14 +790,15 @@ impl App {
     }
 
     pub fn next_file(&mut self) {
-        if let Some(ref diff) = self.current_diff {
-            if diff.files.is_empty() {
+        let count = self.visible_diff_files().len();
+        if self.current_diff.is_some() {
+            if count == 0 {
                 return;
             }
 
             let i = match self.file_list_state.selected() {
                 Some(i) => {
-                    if i >= diff.files.len() - 1 {
+                    if i >= count - 1 {
                         0
                     } else {
                         i + 1
@@ -224,15 +812,16 @@ impl App {
     }
 
     pub fn previous_file(&mut self) {
-        if let Some(ref diff) = self.current_diff {
-            if diff.files.is_empty() {
+        let count = self.visible_diff_files().len();
+        if self.current_diff.is_some() {
+            if count == 0 {
                 return;
             }
 
             let i = match self.file_list_state.selected() {
                 Some(i) => {
                     if i == 0 {
-                        diff.files.len() - 1
+                        count - 1
                     } else {
                         i - 1
                     }
@@ -244,15 +833,99 @@ impl App {
         }
     }
 
+    /// Files of the open diff that match the file filter
+    pub fn visible_diff_files(&self) -> Vec<&FileDiff> {
+        let Some(ref diff) = self.current_diff else {
+            return Vec::new();
+        };
+        match self.file_filter {
+            Some(ref filter) => diff
+                .files
+                .iter()
+                .filter(|file| matches_file_filter(filter, &file.filename))
+                .collect(),
+            None => diff.files.iter().collect(),
+        }
+    }
+
+    /// The file selected in the (filtered) file list of the open diff
+    pub fn selected_diff_file(&self) -> Option<&FileDiff> {
+        let index = self.file_list_state.selected()?;
+        self.visible_diff_files().get(index).copied()
+    }
+
+    /// Opens the file filter input for the open diff or tree view
+    pub fn enter_file_filter_mode(&mut self) {
+        if self.current_diff.is_some() {
+            self.file_filter_input_mode = true;
+        }
+    }
+
+    /// Keeps the typed filter and returns to the file list
+    pub fn confirm_file_filter(&mut self) {
+        self.file_filter_input_mode = false;
+    }
+
+    pub fn add_file_filter_char(&mut self, c: char) {
+        self.file_filter.get_or_insert_with(String::new).push(c);
+        self.apply_file_filter();
+    }
+
+    pub fn delete_file_filter_char(&mut self) {
+        if let Some(ref mut filter) = self.file_filter {
+            filter.pop();
+        }
+        self.apply_file_filter();
+    }
+
+    /// Re-selects the first matching file after the filter changed
+    fn apply_file_filter(&mut self) {
+        if self.file_filter.as_ref().is_some_and(|filter| filter.is_empty()) {
+            self.file_filter = None;
+        }
+        let any = !self.visible_diff_files().is_empty();
+        self.file_list_state = ListState::default();
+        self.file_list_state.select(any.then_some(0));
+        self.diff_scroll = 0;
+    }
+
+    /// Shows every file again, keeping the selected one selected
+    pub fn clear_file_filter(&mut self) {
+        let selected = self.selected_diff_file().map(|file| file.filename.clone());
+        self.file_filter = None;
+        self.file_filter_input_mode = false;
+
+        let index = selected.and_then(|name| {
+            self.current_diff
+                .as_ref()
+                .and_then(|diff| diff.files.iter().position(|file| file.filename == name))
+        });
+        let any = !self.visible_diff_files().is_empty();
+        self.file_list_state = ListState::default();
+        self.file_list_state.select(index.or(any.then_some(0)));
+    }
+
+    pub fn toggle_diff_fullscreen(&mut self) {
+        if self.show_diff {
+            self.diff_fullscreen = !self.diff_fullscreen;
+        }
+    }
+
     pub fn toggle_diff(&mut self) -> Result<()> {
         if self.show_diff {
             self.show_diff = false;
+            self.auto_diff = false;
+            self.diff_fullscreen = false;
+            self.worktree_diff_base = None;
+            self.file_filter = None;
             self.current_diff = None;
+            self.commit_notes = None;
             self.diff_scroll = 0;
             self.file_list_state.select(None);
         } else if let Some(index) = self.list_state.selected() {
-            let commit = &self.commits[index];
-            let diff = get_commit_diff(&commit.hash)?;
+            self.diff_parent = 0;
+            let diff = self.load_commit_diff(&self.commits[index])?;
+            self.commit_notes = crate::git::get_commit_notes(&self.commits[index].hash).unwrap_or(None);
 
             // Select the first file by default
             let mut file_state = ListState::default();
@@ -268,12 +941,307 @@ impl App {
         Ok(())
     }
 
-    pub fn quit(&mut self) {
+    /// Opens the diff of the working tree against the selected commit,
+    /// answering "what changed since this commit"
+    pub fn toggle_worktree_diff(&mut self) -> Result<()> {
         if self.show_diff {
+            return self.toggle_diff();
+        }
+        let Some(hash) = self.list_state.selected().and_then(|i| self.commits.get(i)).map(|c| c.hash.clone()) else {
+            return Ok(());
+        };
+
+        let diff = crate::git::get_worktree_diff(&hash, self.ignore_whitespace)?;
+        if diff.is_empty() {
+            self.set_status(format!("Working tree matches {}", hash), MessageType::Info);
+            return Ok(());
+        }
+
+        let mut file_state = ListState::default();
+        file_state.select(Some(0));
+        self.current_diff = Some(diff);
+        self.commit_notes = None;
+        self.worktree_diff_base = Some(hash);
+        self.show_diff = true;
+        self.diff_scroll = 0;
+        self.file_list_state = file_state;
+        Ok(())
+    }
+
+    /// Regenerates the open diffs with or without whitespace changes,
+    /// keeping the selected file
+    pub fn toggle_ignore_whitespace(&mut self) {
+        self.ignore_whitespace = !self.ignore_whitespace;
+        let msg = if self.ignore_whitespace {
+            "Ignoring whitespace changes in diffs"
+        } else {
+            "Showing whitespace changes in diffs"
+        };
+        self.set_status(msg.to_string(), MessageType::Info);
+
+        if self.status_show_diff {
+            self.load_status_diff();
+        }
+        if self.stash_diff.is_some() {
+            self.load_stash_diff();
+        }
+        if self.current_diff.is_none() {
+            return;
+        }
+
+        let selected = self.selected_diff_file().map(|file| file.filename.clone());
+        let diff = match self.worktree_diff_base {
+            Some(ref base) => crate::git::get_worktree_diff(base, self.ignore_whitespace),
+            None => match self.list_state.selected().and_then(|i| self.commits.get(i)) {
+                Some(commit) => self.load_commit_diff(commit),
+                None => return,
+            },
+        };
+        let diff = match diff {
+            Ok(diff) => diff,
+            Err(e) => {
+                self.set_status(format!("Error: {}", e), MessageType::Error);
+                return;
+            }
+        };
+        self.current_diff = Some(diff);
+        self.diff_scroll = 0;
+
+        let visible = self.visible_diff_files();
+        let index = selected
+            .and_then(|name| visible.iter().position(|file| file.filename == name))
+            .or((!visible.is_empty()).then_some(0));
+        self.file_list_state = ListState::default();
+        self.file_list_state.select(index);
+    }
+
+    /// Loads a commit's changes. Merge commits are diffed against the parent
+    /// chosen with `cycle_diff_parent` (the first parent by default), or
+    /// shown as a combined diff after the last parent.
+    fn load_commit_diff(&self, commit: &Commit) -> Result<CommitDiff> {
+        match commit.parents.get(self.diff_parent) {
+            Some(parent) if commit.is_merge() => get_commit_diff_against(&commit.hash, parent, self.ignore_whitespace),
+            _ => get_commit_diff(&commit.hash, self.ignore_whitespace),
+        }
+    }
+
+    /// For an open merge commit, switches the diff to the next parent, then
+    /// to the combined diff, then back to the first parent
+    pub fn cycle_diff_parent(&mut self) -> Result<()> {
+        let Some(commit) = self.list_state.selected().and_then(|i| self.commits.get(i)) else {
+            return Ok(());
+        };
+        if !commit.is_merge() || self.current_diff.is_none() || self.worktree_diff_base.is_some() {
+            return Ok(());
+        }
+
+        self.diff_parent = (self.diff_parent + 1) % (commit.parents.len() + 1);
+        let diff = self.load_commit_diff(commit)?;
+        if diff.is_empty() && self.diff_parent == commit.parents.len() {
+            self.set_status(
+                "Combined diff is empty: the merge kept one side of every change".to_string(),
+                MessageType::Info,
+            );
+        }
+
+        let mut file_state = ListState::default();
+        if !diff.files.is_empty() {
+            file_state.select(Some(0));
+        }
+        self.current_diff = Some(diff);
+        self.file_list_state = file_state;
+        self.diff_scroll = 0;
+        Ok(())
+    }
+
+    /// Label for the diff title when viewing a merge, e.g. "vs parent 1/2 (abc1234)"
+    pub fn merge_parent_label(&self) -> Option<String> {
+        let commit = self.list_state.selected().and_then(|i| self.commits.get(i))?;
+        if !commit.is_merge() || self.worktree_diff_base.is_some() {
+            return None;
+        }
+        let Some(parent) = commit.parents.get(self.diff_parent) else {
+            return Some("merge: combined".to_string());
+        };
+        Some(format!(
+            "merge: vs parent {}/{} ({})",
+            self.diff_parent + 1,
+            commit.parents.len(),
+            parent
+        ))
+    }
+
+    /// Switches diffs to the next bundled syntax theme and remembers it in
+    /// the user config
+    pub fn cycle_theme(&mut self) {
+        let names = crate::syntax::theme_names();
+        let next = names
+            .iter()
+            .position(|name| *name == self.theme)
+            .map_or(0, |i| (i + 1) % names.len());
+        self.theme = names[next].to_string();
+
+        match self.config.save_user_value("theme", &self.theme) {
+            Ok(()) => self.set_status(format!("Theme: {}", self.theme), MessageType::Info),
+            Err(e) => self.set_status(format!("Theme: {} (not saved: {:#})", self.theme, e), MessageType::Error),
+        }
+    }
+
+    pub fn toggle_simple_diff_colors(&mut self) {
+        self.simple_diff_colors = !self.simple_diff_colors;
+        let msg = if self.simple_diff_colors {
+            "Diff colors: plain green/red text"
+        } else {
+            "Diff colors: syntax highlighting"
+        };
+        self.set_status(msg.to_string(), MessageType::Info);
+    }
+
+    pub fn toggle_expand_long_lines(&mut self) {
+        self.expand_long_lines = !self.expand_long_lines;
+        let msg = if self.expand_long_lines {
+            "Showing long diff lines in full".to_string()
+        } else {
+            format!("Cutting diff lines at {} characters", self.config.max_diff_line_length())
+        };
+        self.set_status(msg, MessageType::Info);
+    }
+
+    /// Cycles file lists between full paths, paths below the shared
+    /// directory and bare file names
+    pub fn cycle_path_display(&mut self) {
+        let (next, msg) = match self.path_display {
+            PathDisplay::Full => (PathDisplay::Relative, "Paths: relative to the shared directory"),
+            PathDisplay::Relative => (PathDisplay::Name, "Paths: file names only"),
+            PathDisplay::Name => (PathDisplay::Full, "Paths: from the repository root"),
+        };
+        self.path_display = next;
+        self.set_status(msg.to_string(), MessageType::Info);
+    }
+
+    /// A path as the file lists show it; `common` is the lists' shared
+    /// directory from `common_dir`
+    pub fn display_path<'a>(&self, path: &'a str, common: &str) -> &'a str {
+        match self.path_display {
+            PathDisplay::Full => path,
+            PathDisplay::Relative => path.strip_prefix(common).filter(|rest| !rest.is_empty()).unwrap_or(path),
+            PathDisplay::Name => {
+                // Keep the trailing '/' of untracked directories
+                let trimmed = path.strip_suffix('/').unwrap_or(path);
+                trimmed.rfind('/').map_or(path, |i| &path[i + 1..])
+            }
+        }
+    }
+
+    /// Asks the main loop to suspend the TUI and open a shell in the repo root
+    pub fn open_shell(&mut self) {
+        self.external_command = Some(ExternalCommand::Shell);
+    }
+
+    /// Runs an external program while the TUI is suspended, then reloads
+    /// everything since it may have changed the repository
+    pub fn run_external_command(&mut self, command: ExternalCommand) {
+        let result = match command {
+            ExternalCommand::Shell => crate::git::get_repo_root()
+                .and_then(|root| crate::platform::run_shell(&root))
+                .map(|_| "Returned from shell".to_string()),
+            ExternalCommand::CommitEditor { amend, push } => {
+                self.commit_in_editor(amend, push);
+                return;
+            }
+        };
+
+        self.refresh_all();
+        match result {
+            Ok(msg) => self.set_status(msg, MessageType::Info),
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Asks the main loop to open the commit message in the editor, when
+    /// `commit_in_editor` is set; returns false to use the built-in input
+    fn request_commit_editor(&mut self, amend: bool, push: bool) -> bool {
+        if !self.config.commit_in_editor() {
+            return false;
+        }
+        if !amend && !crate::git::has_staged_changes().unwrap_or(true) {
+            self.set_status("Nothing staged to commit".to_string(), MessageType::Error);
+        } else {
+            self.external_command = Some(ExternalCommand::CommitEditor { amend, push });
+        }
+        true
+    }
+
+    /// Writes `<git-dir>/COMMIT_EDITMSG` as git does (HEAD's message when
+    /// amending, else the last failed message), opens it in the editor and
+    /// commits what was saved. An empty message cancels.
+    fn commit_in_editor(&mut self, amend: bool, push: bool) {
+        let path = match crate::git::get_git_dir() {
+            Ok(dir) => std::path::Path::new(&dir).join("COMMIT_EDITMSG"),
+            Err(e) => {
+                self.set_status(format!("Error: {}", e), MessageType::Error);
+                return;
+            }
+        };
+        let comment = crate::git::get_comment_char();
+        let message = if amend {
+            crate::git::get_last_commit_full_message().unwrap_or_default()
+        } else {
+            self.commit_message_draft.clone().unwrap_or_default()
+        };
+        let template = format!(
+            "{}\n\n{c} Write the commit message{}. Lines starting with '{c}' are ignored,\n{c} and an empty message cancels the commit.\n",
+            message,
+            if amend { " for the amended commit" } else { "" },
+            c = comment
+        );
+
+        let result = match edit_in_editor(&path, &template, &comment) {
+            Ok(message) if message.is_empty() => Ok(None),
+            Ok(message) => {
+                let committed = crate::git::commit_from_file(&path, amend, &comment);
+                if committed.is_err() && !amend {
+                    // Offered again the next time the message is written
+                    self.commit_message_draft = Some(message);
+                }
+                committed.map(Some)
+            }
+            Err(e) => Err(e),
+        };
+
+        self.refresh_all();
+        match result {
+            Ok(None) => self.set_status("Commit cancelled: empty message".to_string(), MessageType::Info),
+            Ok(Some(msg)) => {
+                if !amend {
+                    self.commit_message_draft = None;
+                }
+                self.set_status(msg, MessageType::Success);
+                if push {
+                    self.push_after_commit();
+                }
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    pub fn quit(&mut self) {
+        if self.current_panel == Panel::Stash && self.stash_diff.is_some() {
+            self.stash_diff = None;
+        } else if self.show_diff {
             self.show_diff = false;
+            self.auto_diff = false;
+            self.diff_fullscreen = false;
+            self.worktree_diff_base = None;
             self.current_diff = None;
             self.diff_scroll = 0;
             self.file_list_state.select(None);
+        } else if self.operation_in_flight {
+            self.confirm = Some(ConfirmPrompt {
+                message: "A git operation is still running. Quit anyway?".to_string(),
+                details: Vec::new(),
+                action: ConfirmAction::Quit,
+            });
         } else {
             self.should_quit = true;
         }
@@ -282,6 +1250,35 @@ impl App {
     pub fn enter_search_mode(&mut self) {
         self.search_mode = true;
         self.search_query.clear();
+        self.search_history_index = None;
+    }
+
+    /// Recalls the previous (older) search query
+    pub fn previous_search_history(&mut self) {
+        if self.search_history.is_empty() {
+            return;
+        }
+        let i = match self.search_history_index {
+            Some(i) => i.saturating_sub(1),
+            None => self.search_history.len() - 1,
+        };
+        self.search_history_index = Some(i);
+        self.search_query = self.search_history[i].clone();
+    }
+
+    /// Recalls the next (newer) search query, or an empty one past the newest
+    pub fn next_search_history(&mut self) {
+        match self.search_history_index {
+            Some(i) if i + 1 < self.search_history.len() => {
+                self.search_history_index = Some(i + 1);
+                self.search_query = self.search_history[i + 1].clone();
+            }
+            Some(_) => {
+                self.search_history_index = None;
+                self.search_query.clear();
+            }
+            None => {}
+        }
     }
 
     pub fn exit_search_mode(&mut self) {
@@ -297,20 +1294,25 @@ impl App {
     }
 
     pub fn execute_search(&mut self) -> Result<()> {
-        if self.search_query.is_empty() {
-            // Empty query = clear filter
-            self.active_filter = None;
-        } else if self.search_query.starts_with('@') {
-            // Author search
-            let author = self.search_query[1..].to_string();
-            self.active_filter = Some(SearchFilter::Author(author));
-        } else {
-            // Message search
-            self.active_filter = Some(SearchFilter::Message(self.search_query.clone()));
+        self.search_history_index = None;
+        // An empty query clears the filter; a bad one keeps the search open
+        let filter = match SearchFilter::parse(&self.search_query) {
+            Ok(filter) => filter,
+            Err(e) => {
+                self.set_status(format!("Error: {}", e), MessageType::Error);
+                return Ok(());
+            }
+        };
+        if !self.search_query.trim().is_empty() {
+            crate::history::push_search_history(&mut self.search_history, &self.search_query);
+            // Failing to save history shouldn't get in the way of searching
+            let _ = crate::history::save_search_history(&self.search_history);
         }
 
+        self.active_filter = filter;
+
         // Reload commits with the filter
-        self.commits = get_commits(self.active_filter.as_ref())?;
+        self.commits = get_commits(self.active_filter.as_ref(), self.first_parent, self.merge_filter)?;
 
         // Reset selection
         let mut list_state = ListState::default();
@@ -326,7 +1328,7 @@ impl App {
     pub fn clear_search(&mut self) -> Result<()> {
         self.active_filter = None;
         self.search_query.clear();
-        self.commits = get_commits(None)?;
+        self.commits = get_commits(None, self.first_parent, self.merge_filter)?;
 
         // Reset selection
         let mut list_state = ListState::default();
@@ -338,19 +1340,67 @@ impl App {
         Ok(())
     }
 
+    /// Switches the Log between all branches and HEAD's first-parent
+    /// mainline, keeping the search filter and, if still listed, the
+    /// selected commit
+    pub fn toggle_first_parent(&mut self) {
+        let selected = self.list_state.selected().and_then(|i| self.commits.get(i)).map(|c| c.hash.clone());
+        self.first_parent = !self.first_parent;
+        self.refresh_commits();
+
+        if let Some(index) = selected.and_then(|hash| self.commits.iter().position(|c| c.hash == hash)) {
+            self.list_state.select(Some(index));
+        }
+        let msg = if self.first_parent {
+            "Showing first-parent history of HEAD"
+        } else {
+            "Showing all branches"
+        };
+        self.set_status(msg.to_string(), MessageType::Info);
+    }
+
+    /// Cycles the Log through all commits, merges only and no merges,
+    /// keeping the search filter and, if still listed, the selected commit
+    pub fn cycle_merge_filter(&mut self) {
+        let selected = self.list_state.selected().and_then(|i| self.commits.get(i)).map(|c| c.hash.clone());
+        self.merge_filter = self.merge_filter.next();
+        self.refresh_commits();
+
+        if let Some(index) = selected.and_then(|hash| self.commits.iter().position(|c| c.hash == hash)) {
+            self.list_state.select(Some(index));
+        }
+        let msg = match self.merge_filter {
+            MergeFilter::All => "Showing all commits",
+            MergeFilter::MergesOnly => "Showing merge commits only",
+            MergeFilter::NoMerges => "Hiding merge commits",
+        };
+        self.set_status(msg.to_string(), MessageType::Info);
+    }
+
+    pub fn toggle_group_by_day(&mut self) {
+        self.group_by_day = !self.group_by_day;
+        let msg = if self.group_by_day {
+            "Grouping commits by day"
+        } else {
+            "Day grouping off"
+        };
+        self.set_status(msg.to_string(), MessageType::Info);
+    }
+
     pub fn toggle_tree_view(&mut self) -> Result<()> {
         if self.tree_view_mode {
             // Already in tree view, exit it
             self.tree_view_mode = false;
             self.tree_file_selected = false;
+            self.file_filter = None;
             self.current_diff = None;
             self.file_list_state.select(None);
             self.diff_scroll = 0;
         } else {
             // Enter tree view mode
             if let Some(index) = self.list_state.selected() {
-                let commit = &self.commits[index];
-                let diff = get_commit_diff(&commit.hash)?;
+                self.diff_parent = 0;
+                let diff = self.load_commit_diff(&self.commits[index])?;
 
                 // Select the first file by default
                 let mut file_state = ListState::default();
@@ -368,36 +1418,167 @@ impl App {
         Ok(())
     }
 
-    pub fn next_tree_file(&mut self) {
-        if let Some(ref diff) = self.current_diff {
-            if diff.files.is_empty() {
-                return;
+    /// Opens the file tree of the selected commit
+    pub fn open_commit_browser(&mut self) {
+        let Some(hash) = self.list_state.selected().and_then(|i| self.commits.get(i)).map(|c| c.hash.clone()) else {
+            return;
+        };
+        match crate::git::list_commit_files(&hash) {
+            Ok(files) => {
+                self.browse_files = files;
+                self.browse_hash = Some(hash);
+                self.browse_file = None;
+                self.enter_browse_dir(String::new());
             }
-
-            let i = match self.file_list_state.selected() {
-                Some(i) => {
-                    if i >= diff.files.len() - 1 {
-                        0
-                    } else {
-                        i + 1
-                    }
-                }
-                None => 0,
-            };
-            self.file_list_state.select(Some(i));
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
         }
     }
 
-    pub fn previous_tree_file(&mut self) {
-        if let Some(ref diff) = self.current_diff {
-            if diff.files.is_empty() {
-                return;
+    pub fn close_commit_browser(&mut self) {
+        self.browse_hash = None;
+        self.browse_files.clear();
+        self.browse_entries.clear();
+        self.browse_file = None;
+    }
+
+    fn enter_browse_dir(&mut self, dir: String) {
+        self.browse_entries = dir_entries(&self.browse_files, &dir);
+        self.browse_dir = dir;
+        self.browse_list_state = ListState::default();
+        self.browse_list_state.select((!self.browse_entries.is_empty()).then_some(0));
+    }
+
+    pub fn next_browse_entry(&mut self) {
+        if !self.browse_entries.is_empty() {
+            let i = self.browse_list_state.selected().map_or(0, |i| (i + 1) % self.browse_entries.len());
+            self.browse_list_state.select(Some(i));
+        }
+    }
+
+    pub fn previous_browse_entry(&mut self) {
+        if !self.browse_entries.is_empty() {
+            let i = self
+                .browse_list_state
+                .selected()
+                .map_or(0, |i| if i == 0 { self.browse_entries.len() - 1 } else { i - 1 });
+            self.browse_list_state.select(Some(i));
+        }
+    }
+
+    /// Enters the selected directory, or shows the selected file as it was
+    /// in the commit
+    pub fn open_browse_entry(&mut self) {
+        let Some((name, is_dir)) = self.browse_list_state.selected().and_then(|i| self.browse_entries.get(i)).cloned()
+        else {
+            return;
+        };
+        let path = format!("{}{}", self.browse_dir, name);
+        if is_dir {
+            self.enter_browse_dir(path);
+            return;
+        }
+
+        let Some(hash) = self.browse_hash.clone() else {
+            return;
+        };
+        match crate::git::get_file_at_commit(&hash, &path) {
+            Ok(content) => {
+                let lines = content.map(|content| self.highlight_browse_file(&content, &path));
+                self.browse_file = Some((path, lines));
+                self.browse_scroll = 0;
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Highlighted once when opened, since files can be much longer than diffs
+    fn highlight_browse_file(&self, content: &str, path: &str) -> Vec<Line<'static>> {
+        let content = content
+            .lines()
+            .map(|line| crate::syntax::expand_tabs(line, self.tab_width))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if self.no_color {
+            content.lines().map(|line| Line::from(line.to_string())).collect()
+        } else {
+            crate::syntax::highlight_file(&content, path, &self.theme)
+        }
+    }
+
+    /// Closes the file being viewed, or goes up one directory
+    pub fn browse_back(&mut self) {
+        if self.browse_file.take().is_some() {
+            return;
+        }
+        if self.browse_dir.is_empty() {
+            return;
+        }
+        let trimmed = self.browse_dir.trim_end_matches('/');
+        let parent = trimmed.rfind('/').map_or(String::new(), |i| trimmed[..=i].to_string());
+        let child = trimmed[parent.len()..].to_string();
+        self.enter_browse_dir(parent);
+        // Keep the directory we came from selected
+        let index = self.browse_entries.iter().position(|(name, is_dir)| *is_dir && *name == child);
+        if index.is_some() {
+            self.browse_list_state.select(index);
+        }
+    }
+
+    pub fn scroll_browse_file(&mut self, lines: i32) {
+        self.browse_scroll = if lines < 0 {
+            self.browse_scroll.saturating_sub(lines.unsigned_abs() as u16)
+        } else {
+            self.browse_scroll.saturating_add(lines as u16)
+        };
+    }
+
+    /// Copies the path of the viewed file, or of the selected entry
+    pub fn copy_browse_path(&mut self) {
+        let path = match self.browse_file {
+            Some((ref path, _)) => Some(path.clone()),
+            None => self
+                .browse_list_state
+                .selected()
+                .and_then(|i| self.browse_entries.get(i))
+                .map(|(name, _)| format!("{}{}", self.browse_dir, name)),
+        };
+        if let Some(path) = path {
+            self.copy_to_clipboard(&path, "path");
+        }
+    }
+
+    pub fn next_tree_file(&mut self) {
+        let count = self.visible_diff_files().len();
+        if self.current_diff.is_some() {
+            if count == 0 {
+                return;
+            }
+
+            let i = match self.file_list_state.selected() {
+                Some(i) => {
+                    if i >= count - 1 {
+                        0
+                    } else {
+                        i + 1
+                    }
+                }
+                None => 0,
+            };
+            self.file_list_state.select(Some(i));
+        }
+    }
+
+    pub fn previous_tree_file(&mut self) {
+        let count = self.visible_diff_files().len();
+        if self.current_diff.is_some() {
+            if count == 0 {
+                return;
             }
 
             let i = match self.file_list_state.selected() {
                 Some(i) => {
                     if i == 0 {
-                        diff.files.len() - 1
+                        count - 1
                     } else {
                         i - 1
                     }
@@ -422,6 +1603,7 @@ impl App {
         } else {
             // If viewing file list, exit tree view entirely
             self.tree_view_mode = false;
+            self.file_filter = None;
             self.current_diff = None;
             self.file_list_state.select(None);
         }
@@ -436,30 +1618,195 @@ impl App {
         self.status_message = None;
     }
 
+    /// Starts a range mark at the selected commit, or drops the current one
+    pub fn toggle_range_mark(&mut self) {
+        if self.range_anchor.take().is_some() {
+            self.set_status("Range mark cleared".to_string(), MessageType::Info);
+            return;
+        }
+        let Some(commit) = self.list_state.selected().and_then(|i| self.commits.get(i)) else {
+            return;
+        };
+        let msg = format!("Range starts at {}; move to the other end and press y to copy", commit.hash);
+        self.range_anchor = Some(commit.hash.clone());
+        self.set_status(msg, MessageType::Info);
+    }
+
+    /// Log rows (first, last) covered by the range mark, if one is set
+    pub fn marked_range(&self) -> Option<(usize, usize)> {
+        let anchor = self.range_anchor.as_ref()?;
+        let anchor_index = self.commits.iter().position(|c| &c.hash == anchor)?;
+        let selected = self.list_state.selected()?;
+        Some((anchor_index.min(selected), anchor_index.max(selected)))
+    }
+
+    /// Copies the hashes in the marked range, oldest first, one per line
+    fn copy_range_hashes(&mut self, first: usize, last: usize) {
+        let hashes: Vec<&str> = self.commits[first..=last].iter().rev().map(|c| c.hash.as_str()).collect();
+        let text = hashes.join("\n");
+        self.copy_with_message(&text, format!("Copied {} hashes", hashes.len()));
+        self.range_anchor = None;
+    }
+
     pub fn copy_commit_hash(&mut self) {
+        if let Some((first, last)) = self.marked_range() {
+            self.copy_range_hashes(first, last);
+            return;
+        }
         if let Some(index) = self.list_state.selected() {
-            let commit = &self.commits[index];
-            match arboard::Clipboard::new() {
-                Ok(mut clipboard) => {
-                    if let Err(e) = clipboard.set_text(&commit.hash) {
-                        self.set_status(
-                            format!("Failed to copy to clipboard: {}", e),
-                            MessageType::Error,
-                        );
-                    } else {
-                        self.set_status(
-                            format!("Copied hash: {}", commit.hash),
-                            MessageType::Success,
-                        );
-                    }
-                }
-                Err(e) => {
+            let hash = self.commits[index].hash.clone();
+            self.copy_to_clipboard(&hash, "hash");
+        }
+    }
+
+    /// Copies the selected commit formatted with the `commit_copy_format` template
+    pub fn copy_commit_summary(&mut self) {
+        let Some(hash) = self.list_state.selected().and_then(|i| self.commits.get(i)).map(|c| c.hash.clone()) else {
+            return;
+        };
+        match crate::git::format_commit(&hash, self.config.commit_copy_format()) {
+            Ok(summary) => self.copy_to_clipboard(&summary, "summary"),
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Copies the name of the branch selected in the Branches panel
+    pub fn copy_selected_branch_name(&mut self) {
+        let Some(branch) = self.branch_list_state.selected().and_then(|i| self.branches.get(i)) else {
+            return;
+        };
+        let name = branch.name.clone();
+        self.copy_to_clipboard(&name, "branch name");
+    }
+
+    /// Copies the name of the checked-out branch
+    pub fn copy_head_branch_name(&mut self) {
+        match self.head_branch.clone() {
+            Some(name) => self.copy_to_clipboard(&name, "branch name"),
+            None => self.set_status("HEAD is detached; no branch to copy".to_string(), MessageType::Info),
+        }
+    }
+
+    /// Copies the selected Status file's path
+    pub fn copy_status_file_path(&mut self) {
+        if let Some(path) = self
+            .selected_status_file_index()
+            .and_then(|file_idx| self.status_files.get(file_idx))
+            .map(|file| file.path.clone())
+        {
+            self.copy_to_clipboard(&path, "path");
+        }
+    }
+
+    /// Opens the rename input for the selected file, filled with its path
+    pub fn enter_rename_mode(&mut self) {
+        let Some(file) = self
+            .selected_status_file_index()
+            .and_then(|file_idx| self.status_files.get(file_idx))
+        else {
+            return;
+        };
+        if file.status == crate::git::FileStatus::Untracked {
+            self.set_status("Only tracked files can be renamed".to_string(), MessageType::Error);
+            return;
+        }
+
+        // A staged rename is listed as "old -> new"; rename the new path
+        let path = file.path.rsplit(" -> ").next().unwrap_or(&file.path).to_string();
+        self.rename_input = path.clone();
+        self.rename_source = Some(path);
+    }
+
+    pub fn exit_rename_mode(&mut self) {
+        self.rename_source = None;
+    }
+
+    pub fn add_rename_char(&mut self, c: char) {
+        self.rename_input.push(c);
+    }
+
+    pub fn delete_rename_char(&mut self) {
+        self.rename_input.pop();
+    }
+
+    pub fn execute_rename(&mut self) {
+        let Some(source) = self.rename_source.take() else {
+            return;
+        };
+        let dest = self.rename_input.trim().to_string();
+        if dest.is_empty() || dest == source {
+            return;
+        }
+
+        match crate::git::move_file(&source, &dest) {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.refresh_status();
+            }
+            Err(e) => {
+                // Keep the input open so another path can be tried
+                self.set_status(format!("Error: {}", e), MessageType::Error);
+                self.rename_source = Some(source);
+            }
+        }
+    }
+
+    /// Copies the path of the file selected in the commit tree view
+    pub fn copy_tree_file_path(&mut self) {
+        if let Some(path) = self.selected_diff_file().map(|file| file.filename.clone()) {
+            self.copy_to_clipboard(&path, "path");
+        }
+    }
+
+    /// Copies the hunk at the top of the open diff (Status or Log panel,
+    /// or the tree view), with or without markers per `copy_hunk_markers`
+    pub fn copy_diff_hunk(&mut self) {
+        let (diff, line) = if self.current_panel == Panel::Status {
+            let Some(diff) = self.status_diff_content.clone().filter(|_| self.status_show_diff) else {
+                return;
+            };
+            (diff, self.status_diff_scroll as usize)
+        } else {
+            let Some(file) = self.selected_diff_file() else {
+                return;
+            };
+            // A note or mode change takes the first row of the diff view
+            let offset = usize::from(file.note.is_some() || file.mode_change.is_some());
+            (file.diff_content.clone(), (self.diff_scroll as usize).saturating_sub(offset))
+        };
+
+        let Some(hunk) = crate::patch::hunk_at_line(&diff, line) else {
+            self.set_status("No hunk to copy in this diff".to_string(), MessageType::Info);
+            return;
+        };
+        let text = crate::patch::hunk_text(&hunk, self.config.copy_hunk_markers());
+        let lines = hunk.len() - 1;
+        self.copy_with_message(&text, format!("Copied hunk {} ({} lines)", hunk[0], lines));
+    }
+
+    fn copy_to_clipboard(&mut self, text: &str, label: &str) {
+        self.copy_with_message(text, format!("Copied {}: {}", label, text));
+    }
+
+    /// Copies text, reporting `message` on success
+    fn copy_with_message(&mut self, text: &str, message: String) {
+        match arboard::Clipboard::new() {
+            Ok(mut clipboard) => {
+                if let Err(e) = clipboard.set_text(text) {
                     self.set_status(
-                        format!("Failed to access clipboard: {}", e),
+                        format!("Failed to copy to clipboard: {}", e),
                         MessageType::Error,
                     );
+                } else {
+                    self.set_status(message, MessageType::Success);
                 }
             }
+            Err(e) => {
+                self.set_status(
+                    format!("Failed to access clipboard: {}", e),
+                    MessageType::Error,
+                );
+            }
         }
     }
 
@@ -467,7 +1814,10 @@ impl App {
         if let Some(index) = self.list_state.selected() {
             let commit = &self.commits[index];
             match crate::git::checkout_commit(&commit.hash) {
-                Ok(msg) => self.set_status(msg, MessageType::Success),
+                Ok(msg) => {
+                    self.set_status(msg, MessageType::Success);
+                    self.refresh_branches();
+                }
                 Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
             }
         }
@@ -516,7 +1866,10 @@ impl App {
         if let Some(index) = self.list_state.selected() {
             let commit = &self.commits[index];
             match crate::git::cherry_pick(&commit.hash) {
-                Ok(msg) => self.set_status(msg, MessageType::Info),
+                Ok(msg) => {
+                    self.set_status(msg, MessageType::Info);
+                    self.refresh_head();
+                }
                 Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
             }
         }
@@ -526,158 +1879,590 @@ impl App {
         if let Some(index) = self.list_state.selected() {
             let commit = &self.commits[index];
             match crate::git::revert_commit(&commit.hash) {
-                Ok(msg) => self.set_status(msg, MessageType::Info),
+                Ok(msg) => {
+                    self.set_status(msg, MessageType::Info);
+                    self.refresh_head();
+                }
                 Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
             }
         }
     }
 
-    // Panel navigation
-    pub fn switch_to_panel(&mut self, panel: Panel) {
-        self.current_panel = panel;
-    }
-
-    pub fn refresh_status(&mut self) {
-        match crate::git::get_status() {
-            Ok(files) => {
-                self.status_files = files;
-                let mut state = ListState::default();
-                if !self.status_files.is_empty() {
-                    state.select(Some(0));
+    /// Starts a bisect session, or ends the running one
+    pub fn toggle_bisect(&mut self) {
+        if self.bisect_active {
+            match crate::git::bisect_reset() {
+                Ok(msg) => {
+                    self.bisect_active = false;
+                    self.set_status(msg, MessageType::Success);
+                    self.refresh_all();
                 }
-                self.status_list_state = state;
+                Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+            }
+        } else {
+            match crate::git::bisect_start() {
+                Ok(_) => {
+                    self.bisect_active = true;
+                    self.set_status(
+                        "Bisect started: mark commits with g (good) and b (bad)".to_string(),
+                        MessageType::Info,
+                    );
+                }
+                Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
             }
-            Err(e) => self.set_status(format!("Failed to refresh status: {}", e), MessageType::Error),
         }
     }
 
-    pub fn refresh_stashes(&mut self) {
-        match crate::git::get_stashes() {
-            Ok(stashes) => {
-                self.stashes = stashes;
-                let mut state = ListState::default();
-                if !self.stashes.is_empty() {
-                    state.select(Some(0));
-                }
-                self.stash_list_state = state;
+    /// Marks the selected commit during a bisect and jumps to the next one to test
+    pub fn bisect_mark_selected(&mut self, mark: BisectMark) {
+        let Some(hash) = self.list_state.selected().and_then(|i| self.commits.get(i)).map(|c| c.hash.clone()) else {
+            return;
+        };
+
+        match crate::git::bisect_mark(mark, &hash) {
+            Ok(msg) => {
+                self.refresh_all();
+                self.select_head_commit();
+                self.set_status(msg, MessageType::Info);
             }
-            Err(e) => self.set_status(format!("Failed to refresh stashes: {}", e), MessageType::Error),
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
         }
     }
 
-    // Status panel operations
+    /// Moves the Log selection to the first parent of the selected commit
+    pub fn select_parent_commit(&mut self) {
+        let Some(commit) = self.list_state.selected().and_then(|i| self.commits.get(i)) else {
+            return;
+        };
+        let Some(parent) = commit.parents.first().cloned() else {
+            self.set_status("Root commit has no parent".to_string(), MessageType::Info);
+            return;
+        };
 
-    /// Maps a list index (which includes headers) to the actual file index
-    /// Returns None if the index points to a header or is out of bounds
-    fn list_index_to_file_index(&self, list_idx: usize) -> Option<usize> {
-        let (staged, unstaged): (Vec<&StatusFile>, Vec<&StatusFile>) =
-            self.status_files.iter().partition(|f| f.staged);
+        match self.commits.iter().position(|c| same_hash(&c.hash, &parent)) {
+            Some(index) => self.list_state.select(Some(index)),
+            None => self.set_status(format!("Parent {} is not in the loaded log", parent), MessageType::Info),
+        }
+    }
 
-        let mut file_idx = 0;
-        let mut current_list_idx = 0;
+    pub fn enter_goto_input_mode(&mut self) {
+        self.goto_input_mode = true;
+        self.goto_input.clear();
+    }
 
-        // Account for "Staged Changes:" header
-        if !staged.is_empty() {
-            if list_idx == current_list_idx {
-                return None; // This is the header
-            }
-            current_list_idx += 1;
+    pub fn exit_goto_input_mode(&mut self) {
+        self.goto_input_mode = false;
+    }
 
-            // Check if we're in the staged files section
-            for _ in &staged {
-                if list_idx == current_list_idx {
-                    return Some(file_idx);
-                }
-                current_list_idx += 1;
-                file_idx += 1;
-            }
+    pub fn add_goto_char(&mut self, c: char) {
+        self.goto_input.push(c);
+    }
+
+    pub fn delete_goto_char(&mut self) {
+        self.goto_input.pop();
+    }
+
+    /// Resolves the typed hash or ref and selects that commit in the log,
+    /// clearing an active search if it hides the commit
+    pub fn execute_goto_commit(&mut self) -> Result<()> {
+        self.goto_input_mode = false;
+        let rev = self.goto_input.trim().to_string();
+        if rev.is_empty() {
+            return Ok(());
         }
 
-        // Account for "Unstaged Changes:" header
-        if !unstaged.is_empty() {
-            if list_idx == current_list_idx {
-                return None; // This is the header
+        let hash = match crate::git::resolve_commit(&rev) {
+            Ok(hash) => hash,
+            Err(e) => {
+                self.set_status(format!("Error: {}", e), MessageType::Error);
+                return Ok(());
             }
-            current_list_idx += 1;
+        };
 
-            // Check if we're in the unstaged files section
-            for _ in &unstaged {
-                if list_idx == current_list_idx {
-                    return Some(file_idx);
-                }
-                current_list_idx += 1;
-                file_idx += 1;
+        let mut index = self.commits.iter().position(|c| same_hash(&c.hash, &hash));
+        if index.is_none() && self.active_filter.is_some() {
+            self.clear_search()?;
+            index = self.commits.iter().position(|c| same_hash(&c.hash, &hash));
+        }
+        // The first-parent view and the merge filter hide commits as well
+        if index.is_none() && (self.first_parent || self.merge_filter != MergeFilter::All) {
+            self.first_parent = false;
+            self.merge_filter = MergeFilter::All;
+            self.refresh_commits();
+            index = self.commits.iter().position(|c| same_hash(&c.hash, &hash));
+            if index.is_some() {
+                self.set_status(
+                    format!("Showing all commits: {} was hidden by the Log view", rev),
+                    MessageType::Info,
+                );
             }
         }
 
-        None // Out of bounds
-    }
-
-    /// Get the total number of list items (files + headers)
-    fn get_status_list_len(&self) -> usize {
-        if self.status_files.is_empty() {
-            return 1; // "No changes" message
+        match index {
+            Some(index) => {
+                self.list_state.select(Some(index));
+                self.show_diff = false;
+                self.auto_diff = false;
+                self.current_diff = None;
+                self.diff_scroll = 0;
+            }
+            None => self.set_status(
+                format!("{} ({}) is not reachable from any branch or tag", rev, &hash[..7.min(hash.len())]),
+                MessageType::Error,
+            ),
         }
+        Ok(())
+    }
 
-        let (staged, unstaged): (Vec<&StatusFile>, Vec<&StatusFile>) =
-            self.status_files.iter().partition(|f| f.staged);
+    /// Moves the Log selection to a child of the selected commit, preferring
+    /// the nearest one above it when the history forks
+    pub fn select_child_commit(&mut self) {
+        let Some(index) = self.list_state.selected() else {
+            return;
+        };
+        let Some(hash) = self.commits.get(index).map(|c| c.hash.clone()) else {
+            return;
+        };
 
-        let mut count = 0;
-        if !staged.is_empty() {
-            count += 1 + staged.len(); // Header + files
-        }
-        if !unstaged.is_empty() {
-            count += 1 + unstaged.len(); // Header + files
+        let child = self.commits[..index]
+            .iter()
+            .rposition(|c| c.parents.iter().any(|p| same_hash(p, &hash)));
+        match child {
+            Some(child) => self.list_state.select(Some(child)),
+            None => self.set_status("No child commit in the loaded log".to_string(), MessageType::Info),
         }
-        count
     }
 
-    pub fn next_status_file(&mut self) {
-        let list_len = self.get_status_list_len();
-        if list_len == 0 {
-            return;
+    /// Whether a Log commit is unpushed, unpulled, or where HEAD forked from its upstream
+    pub fn upstream_state(&self, commit: &Commit) -> Option<UpstreamState> {
+        let divergence = self.upstream_divergence.as_ref()?;
+        if divergence.ahead.contains(&commit.hash) {
+            Some(UpstreamState::Unpushed)
+        } else if divergence.behind.contains(&commit.hash) {
+            Some(UpstreamState::Unpulled)
+        } else if !divergence.ahead.is_empty()
+            && divergence.merge_base.as_ref().is_some_and(|h| same_hash(h, &commit.hash))
+        {
+            Some(UpstreamState::ForkPoint)
+        } else {
+            None
         }
-        let i = match self.status_list_state.selected() {
-            Some(i) if i >= list_len - 1 => 0,
-            Some(i) => i + 1,
-            None => 0,
-        };
-        self.status_list_state.select(Some(i));
     }
 
-    pub fn previous_status_file(&mut self) {
-        let list_len = self.get_status_list_len();
-        if list_len == 0 {
-            return;
+    fn select_head_commit(&mut self) {
+        if let Some(index) = self
+            .commits
+            .iter()
+            .position(|c| c.decorations.contains(&Decoration::Head))
+        {
+            self.list_state.select(Some(index));
         }
-        let i = match self.status_list_state.selected() {
-            Some(0) => list_len - 1,
-            Some(i) => i - 1,
-            None => 0,
+    }
+
+    // Panel navigation
+    pub fn switch_to_panel(&mut self, panel: Panel) {
+        self.current_panel = panel;
+    }
+
+    /// Cycles to the next panel in tab-bar order, wrapping around
+    pub fn next_panel(&mut self) {
+        self.current_panel = match self.current_panel {
+            Panel::Status => Panel::Log,
+            Panel::Log => Panel::Stash,
+            Panel::Stash => Panel::Branches,
+            Panel::Branches => Panel::Tags,
+            Panel::Tags => Panel::Status,
         };
-        self.status_list_state.select(Some(i));
     }
 
-    pub fn toggle_stage(&mut self) {
-        if let Some(list_idx) = self.status_list_state.selected() {
-            if let Some(file_idx) = self.list_index_to_file_index(list_idx) {
-                if let Some(file) = self.status_files.get(file_idx) {
-                let result = if file.staged {
-                    crate::git::unstage_file(&file.path)
-                } else {
-                    crate::git::stage_file(&file.path)
-                };
+    /// Cycles to the previous panel in tab-bar order, wrapping around
+    pub fn previous_panel(&mut self) {
+        self.current_panel = match self.current_panel {
+            Panel::Status => Panel::Tags,
+            Panel::Log => Panel::Status,
+            Panel::Stash => Panel::Log,
+            Panel::Branches => Panel::Stash,
+            Panel::Tags => Panel::Branches,
+        };
+    }
 
-                match result {
-                    Ok(msg) => {
-                        self.set_status(msg, MessageType::Success);
-                        self.refresh_status();
-                    }
-                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+    pub fn refresh_status(&mut self) {
+        match crate::git::get_status() {
+            Ok(files) => {
+                self.status_files = files;
+                self.in_progress = crate::git::get_in_progress();
+                self.load_untracked_dirs();
+                self.select_first_status_row();
+            }
+            Err(e) => self.set_status(format!("Failed to refresh status: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Counts the files in each collapsed untracked directory and
+    /// re-expands directories the user previously opened
+    fn load_untracked_dirs(&mut self) {
+        self.untracked_dir_counts.clear();
+
+        let dirs: Vec<String> = self
+            .status_files
+            .iter()
+            .filter(|f| is_untracked_dir(f))
+            .map(|f| f.path.clone())
+            .collect();
+
+        let dir_refs: Vec<&str> = dirs.iter().map(String::as_str).collect();
+        let mut files = crate::git::get_untracked_files(&dir_refs).unwrap_or_default();
+
+        for dir in dirs {
+            let (inside, rest): (Vec<String>, Vec<String>) =
+                files.into_iter().partition(|file| file.starts_with(dir.as_str()));
+            files = rest;
+            self.untracked_dir_counts.insert(dir.clone(), inside.len());
+
+            if self.expanded_untracked_dirs.contains(&dir) {
+                self.insert_untracked_children(&dir, inside);
+            }
+        }
+    }
+
+    /// Inserts the files of an untracked directory right after its row
+    fn insert_untracked_children(&mut self, dir: &str, files: Vec<String>) {
+        if let Some(pos) = self.status_files.iter().position(|f| is_untracked_dir(f) && f.path == dir) {
+            let children = files.into_iter().map(|path| StatusFile {
+                path,
+                status: crate::git::FileStatus::Untracked,
+                staged: false,
+                is_submodule: false,
+            });
+            self.status_files.splice(pos + 1..pos + 1, children);
+        }
+    }
+
+    pub fn is_untracked_dir_selected(&self) -> bool {
+        self.selected_status_file_index()
+            .and_then(|file_idx| self.status_files.get(file_idx))
+            .is_some_and(is_untracked_dir)
+    }
+
+    /// Expands or collapses the selected untracked directory
+    pub fn toggle_untracked_dir(&mut self) {
+        let Some(dir) = self
+            .selected_status_file_index()
+            .and_then(|file_idx| self.status_files.get(file_idx))
+            .filter(|f| is_untracked_dir(f))
+            .map(|f| f.path.clone())
+        else {
+            return;
+        };
+
+        if self.expanded_untracked_dirs.remove(&dir) {
+            self.status_files.retain(|f| {
+                f.staged
+                    || f.status != crate::git::FileStatus::Untracked
+                    || f.path == dir
+                    || !f.path.starts_with(&dir)
+            });
+        } else {
+            match crate::git::get_untracked_files(&[&dir]) {
+                Ok(files) => {
+                    self.expanded_untracked_dirs.insert(dir.clone());
+                    self.insert_untracked_children(&dir, files);
+                }
+                Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+            }
+        }
+    }
+
+    pub fn refresh_stashes(&mut self) {
+        match crate::git::get_stashes() {
+            Ok(stashes) => {
+                self.stashes = stashes;
+                let mut state = ListState::default();
+                if !self.stashes.is_empty() {
+                    state.select(Some(0));
+                }
+                self.stash_list_state = state;
+                self.update_stash_stat();
+                if self.stash_diff.is_some() {
+                    self.load_stash_diff();
                 }
+            }
+            Err(e) => self.set_status(format!("Failed to refresh stashes: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Loads the diff stat of the selected stash only
+    fn update_stash_stat(&mut self) {
+        self.stash_stat = self
+            .stash_list_state
+            .selected()
+            .and_then(|i| self.stashes.get(i))
+            .and_then(|stash| crate::git::get_stash_stat(stash.index).ok());
+    }
+
+    /// Opens or closes the per-file diff of the selected stash
+    pub fn toggle_stash_diff(&mut self) {
+        if self.stash_diff.take().is_none() {
+            self.load_stash_diff();
+        }
+    }
+
+    /// (Re)loads the open stash diff for the selected stash, keeping the
+    /// selected file when the stash has it too
+    fn load_stash_diff(&mut self) {
+        let Some(stash) = self.stash_list_state.selected().and_then(|i| self.stashes.get(i)) else {
+            self.stash_diff = None;
+            return;
+        };
+        let diff = match crate::git::get_stash_diff(stash.index, self.ignore_whitespace) {
+            Ok(diff) => diff,
+            Err(e) => {
+                self.stash_diff = None;
+                self.set_status(format!("Error: {}", e), MessageType::Error);
+                return;
+            }
+        };
+
+        let selected = self.selected_stash_file().map(|file| file.filename.clone());
+        let index = selected
+            .and_then(|name| diff.files.iter().position(|file| file.filename == name))
+            .or((!diff.files.is_empty()).then_some(0));
+        self.stash_file_state = ListState::default().with_selected(index);
+        self.stash_diff = Some(diff);
+        self.stash_diff_scroll = 0;
+    }
+
+    pub fn selected_stash_file(&self) -> Option<&FileDiff> {
+        let diff = self.stash_diff.as_ref()?;
+        self.stash_file_state.selected().and_then(|i| diff.files.get(i))
+    }
+
+    pub fn next_stash_file(&mut self) {
+        let count = self.stash_diff.as_ref().map_or(0, |diff| diff.files.len());
+        if count > 0 {
+            let i = self.stash_file_state.selected().map_or(0, |i| (i + 1) % count);
+            self.stash_file_state.select(Some(i));
+            self.stash_diff_scroll = 0;
+        }
+    }
+
+    pub fn previous_stash_file(&mut self) {
+        let count = self.stash_diff.as_ref().map_or(0, |diff| diff.files.len());
+        if count > 0 {
+            let i = self.stash_file_state.selected().map_or(0, |i| if i == 0 { count - 1 } else { i - 1 });
+            self.stash_file_state.select(Some(i));
+            self.stash_diff_scroll = 0;
+        }
+    }
+
+    pub fn scroll_stash_diff(&mut self, lines: i32) {
+        self.stash_diff_scroll = if lines < 0 {
+            self.stash_diff_scroll.saturating_sub(lines.unsigned_abs() as u16)
+        } else {
+            self.stash_diff_scroll.saturating_add(lines as u16)
+        };
+    }
+
+    // Status panel operations
+
+    /// Maps a list index (which includes headers) to the actual file index
+    /// Returns None if the index points to a header or is out of bounds
+    fn list_index_to_file_index(&self, list_idx: usize) -> Option<usize> {
+        match self.status_rows().get(list_idx) {
+            Some(StatusRow::File(i)) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Rows of the Status list: the staged files then the unstaged ones,
+    /// each section under a header, while `status_files` keeps git's path
+    /// order. A collapsed section is just its header.
+    fn status_rows(&self) -> Vec<StatusRow> {
+        let (staged, unstaged): (Vec<usize>, Vec<usize>) =
+            (0..self.status_files.len()).partition(|&i| self.status_files[i].staged);
+
+        let mut rows = Vec::new();
+        for (section, staged, collapsed) in [
+            (staged, true, self.staged_collapsed),
+            (unstaged, false, self.unstaged_collapsed),
+        ] {
+            if !section.is_empty() {
+                rows.push(StatusRow::Header { staged });
+                if !collapsed {
+                    rows.extend(section.into_iter().map(StatusRow::File));
                 }
             }
         }
+        rows
+    }
+
+    /// Get the total number of list items (files + headers)
+    fn get_status_list_len(&self) -> usize {
+        if self.status_files.is_empty() {
+            return 1; // "No changes" message
+        }
+        self.status_rows().len()
+    }
+
+    /// Files can be selected, and so can the header of a collapsed
+    /// section, so that it can be expanded again
+    fn is_selectable_status_row(&self, list_idx: usize) -> bool {
+        match self.status_rows().get(list_idx) {
+            Some(StatusRow::File(_)) => true,
+            Some(StatusRow::Header { staged }) => self.is_section_collapsed(*staged),
+            None => false,
+        }
+    }
+
+    fn is_section_collapsed(&self, staged: bool) -> bool {
+        if staged {
+            self.staged_collapsed
+        } else {
+            self.unstaged_collapsed
+        }
+    }
+
+    /// Section (true for staged) of the selected row, file or header
+    fn selected_status_section(&self) -> Option<bool> {
+        match self.status_rows().get(self.status_list_state.selected()?) {
+            Some(StatusRow::File(i)) => Some(self.status_files[*i].staged),
+            Some(StatusRow::Header { staged }) => Some(*staged),
+            None => None,
+        }
+    }
+
+    pub fn is_status_header_selected(&self) -> bool {
+        let rows = self.status_rows();
+        self.status_list_state
+            .selected()
+            .and_then(|i| rows.get(i))
+            .is_some_and(|row| matches!(row, StatusRow::Header { .. }))
+    }
+
+    /// Row to select when entering a section: its first file, or its
+    /// header while it is collapsed
+    fn section_entry_row(&self, staged: bool) -> Option<usize> {
+        let header = self
+            .status_rows()
+            .iter()
+            .position(|row| *row == StatusRow::Header { staged })?;
+        Some(if self.is_section_collapsed(staged) { header } else { header + 1 })
+    }
+
+    /// Collapses or expands the section holding the selection
+    pub fn toggle_status_section(&mut self) {
+        let Some(staged) = self.selected_status_section() else {
+            return;
+        };
+        if staged {
+            self.staged_collapsed = !self.staged_collapsed;
+        } else {
+            self.unstaged_collapsed = !self.unstaged_collapsed;
+        }
+        self.status_list_state.select(self.section_entry_row(staged));
+
+        if self.status_show_diff {
+            self.load_status_diff();
+        }
+    }
+
+    /// Selects the first selectable row, or nothing when there are no changes
+    fn select_first_status_row(&mut self) {
+        let first = (0..self.status_rows().len()).find(|&i| self.is_selectable_status_row(i));
+        self.status_list_state = ListState::default();
+        self.status_list_state.select(first);
+    }
+
+    pub fn next_status_file(&mut self) {
+        self.step_status_selection(true);
+        if self.status_show_diff {
+            self.load_status_diff();
+        }
+    }
+
+    pub fn previous_status_file(&mut self) {
+        self.step_status_selection(false);
+        if self.status_show_diff {
+            self.load_status_diff();
+        }
+    }
+
+    /// Moves the selection one row, wrapping around and skipping headers
+    fn step_status_selection(&mut self, forward: bool) {
+        let list_len = self.get_status_list_len();
+        if list_len == 0 {
+            return;
+        }
+        let mut i = self.status_list_state.selected();
+        for _ in 0..list_len {
+            let next = match i {
+                Some(i) if forward && i >= list_len - 1 => 0,
+                Some(i) if forward => i + 1,
+                Some(0) => list_len - 1,
+                Some(i) => i - 1,
+                None => 0,
+            };
+            i = Some(next);
+            if self.is_selectable_status_row(next) {
+                break;
+            }
+        }
+        self.status_list_state.select(i);
+    }
+
+    /// Jumps to the first file of the other section (staged <-> unstaged)
+    pub fn jump_status_section(&mut self) {
+        let in_staged = self.selected_status_section().unwrap_or(false);
+        let Some(target) = self.section_entry_row(!in_staged) else {
+            return;
+        };
+        self.status_list_state.select(Some(target));
+
+        if self.status_show_diff {
+            self.load_status_diff();
+        }
+    }
+
+    pub fn toggle_stage(&mut self) {
+        let Some(list_idx) = self.status_list_state.selected() else {
+            return;
+        };
+        let Some(file) = self.list_index_to_file_index(list_idx).and_then(|i| self.status_files.get(i)) else {
+            if !self.status_files.is_empty() {
+                self.set_status("Select a file to stage or unstage".to_string(), MessageType::Info);
+            }
+            return;
+        };
+
+        let result = if file.staged {
+            crate::git::unstage_file(&file.path)
+        } else {
+            crate::git::stage_file(&file.path)
+        };
+
+        match result {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.refresh_status();
+                self.restore_status_selection(list_idx);
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Puts the cursor back on the same row after the list was rebuilt,
+    /// moving to the nearest file if that row is now a header or gone
+    fn restore_status_selection(&mut self, list_idx: usize) {
+        if self.status_files.is_empty() {
+            return;
+        }
+        let list_len = self.get_status_list_len();
+        let target = list_idx.min(list_len - 1);
+        self.status_list_state.select(Some(target));
+        if !self.is_selectable_status_row(target) {
+            let forward = target + 1 < list_len;
+            self.step_status_selection(forward);
+        }
     }
 
     pub fn stage_all_files(&mut self) {
@@ -701,13 +2486,30 @@ impl App {
     }
 
     pub fn enter_commit_message_mode(&mut self) {
+        if self.request_commit_editor(false, false) {
+            return;
+        }
         self.commit_message_mode = true;
-        self.commit_message_input.clear();
+        self.commit_message_input = self.commit_message_draft.clone().unwrap_or_default();
+        self.staged_stats = crate::git::get_staged_numstat().unwrap_or_default();
+    }
+
+    /// Like `enter_commit_message_mode`, but pushes once the commit is made
+    pub fn enter_commit_and_push_mode(&mut self) {
+        if self.request_commit_editor(false, true) {
+            return;
+        }
+        self.enter_commit_message_mode();
+        self.commit_and_push = true;
     }
 
     pub fn exit_commit_message_mode(&mut self) {
         self.commit_message_mode = false;
         self.amend_mode = false;
+        self.amend_include_unstaged = false;
+        self.commit_and_push = false;
+        self.commit_allow_empty = false;
+        self.reword_target = None;
     }
 
     pub fn add_commit_char(&mut self, c: char) {
@@ -719,381 +2521,2684 @@ impl App {
     }
 
     pub fn execute_commit(&mut self) {
+        if self.reword_target.is_some() {
+            self.execute_reword();
+            return;
+        }
+
+        let push_after = self.commit_and_push;
+        self.commit_and_push = false;
+
         if self.commit_message_input.is_empty() {
             self.set_status("Commit message cannot be empty".to_string(), MessageType::Error);
             self.commit_message_mode = false;
             self.amend_mode = false;
+            self.amend_include_unstaged = false;
+            return;
+        }
+
+        // Catch an empty index up front instead of surfacing git's stderr
+        let nothing_staged = !self.amend_mode && !crate::git::has_staged_changes().unwrap_or(true);
+        if nothing_staged && !self.commit_allow_empty {
+            self.set_status(
+                "Nothing staged to commit (Tab allows an empty commit)".to_string(),
+                MessageType::Error,
+            );
+            self.commit_message_draft = Some(self.commit_message_input.clone());
+            self.commit_and_push = push_after;
             return;
         }
 
         let result = if self.amend_mode {
-            crate::git::commit_amend(&self.commit_message_input)
+            crate::git::commit_amend(&self.commit_message_input, self.amend_include_unstaged)
         } else {
-            crate::git::commit(&self.commit_message_input)
+            crate::git::commit(&self.commit_message_input, self.commit_allow_empty)
         };
 
         match result {
             Ok(msg) => {
+                let msg = if nothing_staged { "Created an empty commit".to_string() } else { msg };
                 self.set_status(msg, MessageType::Success);
                 self.commit_message_mode = false;
+                if self.amend_mode {
+                    // HEAD was replaced, so the Log is stale
+                    self.refresh_commits();
+                } else {
+                    self.commit_message_draft = None;
+                }
                 self.amend_mode = false;
+                self.amend_include_unstaged = false;
+                self.commit_allow_empty = false;
+                self.commit_message_input.clear();
                 self.refresh_status();
+                self.refresh_head();
+                if push_after {
+                    self.push_after_commit();
+                }
             }
             Err(e) => {
+                // Keep the input open with the message so it can be retried
+                // after fixing the cause (e.g. a hook or nothing staged)
                 self.set_status(format!("Error: {}", e), MessageType::Error);
-                self.commit_message_mode = false;
-                self.amend_mode = false;
-            }
-        }
-    }
-
-    pub fn enter_amend_mode(&mut self) {
-        match crate::git::get_last_commit_message() {
-            Ok(msg) => {
-                self.amend_mode = true;
-                self.commit_message_mode = true;
-                self.commit_message_input = msg;
-            }
-            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
-        }
-    }
-
-    pub fn discard_selected_file(&mut self) {
-        if let Some(list_idx) = self.status_list_state.selected() {
-            if let Some(file_idx) = self.list_index_to_file_index(list_idx) {
-                if let Some(file) = self.status_files.get(file_idx) {
-                if file.staged {
-                    self.set_status("Cannot discard staged file. Unstage it first.".to_string(), MessageType::Error);
-                    return;
-                }
-
-                match crate::git::discard_file(&file.path) {
-                    Ok(msg) => {
-                        self.set_status(msg, MessageType::Success);
-                        self.refresh_status();
-                    }
-                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
-                }
+                if !self.amend_mode {
+                    self.commit_message_draft = Some(self.commit_message_input.clone());
                 }
+                self.commit_and_push = push_after;
             }
         }
     }
 
-    pub fn toggle_status_diff(&mut self) {
-        self.status_show_diff = !self.status_show_diff;
-
-        if self.status_show_diff {
-            // Load diff for selected file
-            if let Some(list_idx) = self.status_list_state.selected() {
-                if let Some(file_idx) = self.list_index_to_file_index(list_idx) {
-                    if let Some(file) = self.status_files.get(file_idx) {
-                        match crate::git::get_file_diff(&file.path, file.staged) {
-                            Ok(diff) => self.status_diff_content = Some(diff),
-                            Err(e) => {
-                                self.set_status(format!("Failed to load diff: {}", e), MessageType::Error);
-                                self.status_show_diff = false;
-                            }
-                        }
-                    }
-                }
-            }
-        } else {
-            self.status_diff_content = None;
-            self.status_diff_scroll = 0;
-        }
+    /// Prompts for a one-line message on the status line
+    pub fn enter_quick_commit_mode(&mut self) {
+        self.quick_commit_input = Some(String::new());
+        self.quick_commit_all = false;
     }
 
-    pub fn scroll_status_diff_up(&mut self) {
-        if self.status_diff_scroll > 0 {
-            self.status_diff_scroll -= 1;
-        }
+    /// Like the quick commit, but stages every change first
+    pub fn enter_commit_all_mode(&mut self) {
+        self.quick_commit_input = Some(String::new());
+        self.quick_commit_all = true;
     }
 
-    pub fn scroll_status_diff_down(&mut self) {
-        self.status_diff_scroll += 1;
+    pub fn exit_quick_commit_mode(&mut self) {
+        self.quick_commit_input = None;
     }
 
-    pub fn scroll_status_diff_page_up(&mut self) {
-        self.status_diff_scroll = self.status_diff_scroll.saturating_sub(10);
+    pub fn add_quick_commit_char(&mut self, c: char) {
+        if let Some(ref mut input) = self.quick_commit_input {
+            input.push(c);
+        }
     }
 
-    pub fn scroll_status_diff_page_down(&mut self) {
-        self.status_diff_scroll = self.status_diff_scroll.saturating_add(10);
+    pub fn delete_quick_commit_char(&mut self) {
+        if let Some(ref mut input) = self.quick_commit_input {
+            input.pop();
+        }
     }
 
-    // Stash panel operations
-    pub fn next_stash(&mut self) {
-        if self.stashes.is_empty() {
+    /// Commits what is staged (or everything, for commit-all) with the typed
+    /// message, no questions asked. A failed commit keeps the message as the
+    /// commit draft.
+    pub fn execute_quick_commit(&mut self) {
+        let Some(message) = self.quick_commit_input.take().map(|m| m.trim().to_string()) else {
             return;
-        }
-        let i = match self.stash_list_state.selected() {
-            Some(i) if i >= self.stashes.len() - 1 => 0,
-            Some(i) => i + 1,
-            None => 0,
         };
-        self.stash_list_state.select(Some(i));
-    }
-
-    pub fn previous_stash(&mut self) {
-        if self.stashes.is_empty() {
+        if message.is_empty() {
+            self.set_status("Commit message cannot be empty".to_string(), MessageType::Error);
             return;
         }
-        let i = match self.stash_list_state.selected() {
-            Some(0) => self.stashes.len() - 1,
-            Some(i) => i - 1,
-            None => 0,
-        };
-        self.stash_list_state.select(Some(i));
-    }
 
-    pub fn apply_selected_stash(&mut self) {
-        if let Some(index) = self.stash_list_state.selected() {
-            if let Some(stash) = self.stashes.get(index) {
-                match crate::git::apply_stash(stash.index) {
-                    Ok(msg) => {
-                        self.set_status(msg, MessageType::Success);
-                        self.refresh_status();
-                    }
-                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
-                }
+        if self.quick_commit_all {
+            if let Err(e) = crate::git::stage_all() {
+                self.set_status(format!("Error: {}", e), MessageType::Error);
+                self.commit_message_draft = Some(message);
+                self.refresh_status();
+                return;
             }
+            self.refresh_status();
         }
-    }
 
-    pub fn pop_selected_stash(&mut self) {
-        if let Some(index) = self.stash_list_state.selected() {
-            if let Some(stash) = self.stashes.get(index) {
-                match crate::git::pop_stash(stash.index) {
-                    Ok(msg) => {
-                        self.set_status(msg, MessageType::Success);
-                        self.refresh_status();
-                        self.refresh_stashes();
-                    }
-                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
-                }
-            }
+        if !crate::git::has_staged_changes().unwrap_or(true) {
+            let msg = if self.quick_commit_all { "Nothing to commit" } else { "Nothing staged to commit" };
+            self.set_status(msg.to_string(), MessageType::Error);
+            self.commit_message_draft = Some(message);
+            return;
         }
-    }
 
-    pub fn drop_selected_stash(&mut self) {
-        if let Some(index) = self.stash_list_state.selected() {
-            if let Some(stash) = self.stashes.get(index) {
-                match crate::git::drop_stash(stash.index) {
-                    Ok(msg) => {
-                        self.set_status(msg, MessageType::Success);
-                        self.refresh_stashes();
-                    }
-                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
-                }
+        match crate::git::commit(&message, false) {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.refresh_status();
+                self.refresh_head();
+            }
+            Err(e) => {
+                self.set_status(format!("Error: {}", e), MessageType::Error);
+                self.commit_message_draft = Some(message);
             }
         }
     }
 
-    // Stash creation methods
-    pub fn enter_stash_input_mode(&mut self) {
-        self.stash_input_mode = true;
-        self.stash_message_input.clear();
-    }
-
-    pub fn exit_stash_input_mode(&mut self) {
-        self.stash_input_mode = false;
+    fn push_after_commit(&mut self) {
+        match crate::git::push_current_branch() {
+            Ok(msg) => {
+                self.set_status(format!("Committed successfully. {}", msg), MessageType::Success);
+                self.refresh_branches();
+            }
+            Err(e) => self.set_status(
+                format!("Committed successfully, but push failed: {}", e),
+                MessageType::Error,
+            ),
+        }
     }
 
-    pub fn add_stash_char(&mut self, c: char) {
-        self.stash_message_input.push(c);
+    /// Edits the selected Log commit's message: HEAD goes through the
+    /// amend flow, older commits through reword
+    pub fn amend_selected_commit(&mut self) {
+        let Some(hash) = self.list_state.selected().and_then(|i| self.commits.get(i)).map(|c| c.hash.clone()) else {
+            return;
+        };
+        match crate::git::resolve_commit("HEAD") {
+            Ok(head) if same_hash(&head, &hash) => self.enter_amend_mode(),
+            Ok(_) => self.enter_reword_mode(),
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
     }
 
-    pub fn delete_stash_char(&mut self) {
-        self.stash_message_input.pop();
+    /// Opens the commit message input to reword the selected Log commit
+    pub fn enter_reword_mode(&mut self) {
+        if let Some(commit) = self.list_state.selected().and_then(|i| self.commits.get(i)) {
+            self.reword_target = Some(commit.hash.clone());
+            self.commit_message_input = commit.message.clone();
+            self.commit_message_mode = true;
+        }
     }
 
-    pub fn execute_create_stash(&mut self) {
-        let message = if self.stash_message_input.is_empty() {
-            None
-        } else {
-            Some(self.stash_message_input.as_str())
+    fn execute_reword(&mut self) {
+        let Some(hash) = self.reword_target.take() else {
+            return;
         };
+        self.commit_message_mode = false;
 
-        match crate::git::create_stash(message, false) {
+        if self.commit_message_input.is_empty() {
+            self.set_status("Commit message cannot be empty".to_string(), MessageType::Error);
+            return;
+        }
+
+        match crate::git::reword_commit(&hash, &self.commit_message_input) {
             Ok(msg) => {
+                self.refresh_all();
                 self.set_status(msg, MessageType::Success);
-                self.stash_input_mode = false;
-                self.refresh_status();
-                self.refresh_stashes();
             }
             Err(e) => {
+                self.refresh_all();
                 self.set_status(format!("Error: {}", e), MessageType::Error);
-                self.stash_input_mode = false;
             }
         }
     }
 
-    // Branches panel operations
-    pub fn refresh_branches(&mut self) {
-        match crate::git::get_branches() {
-            Ok(branches) => {
-                self.branches = branches;
-                let mut state = ListState::default();
-                if !self.branches.is_empty() {
-                    state.select(Some(0));
-                }
-                self.branch_list_state = state;
+    /// Opens the author prompt for amending HEAD's author and date
+    pub fn enter_amend_metadata_mode(&mut self) {
+        match crate::git::get_head_author_and_date() {
+            Ok((author, date)) => {
+                self.amend_metadata = Some(AmendMetadata {
+                    author,
+                    date,
+                    editing_date: false,
+                })
             }
-            Err(e) => self.set_status(format!("Failed to refresh branches: {}", e), MessageType::Error),
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
         }
     }
 
-    pub fn next_branch(&mut self) {
-        if self.branches.is_empty() {
-            return;
+    pub fn exit_amend_metadata_mode(&mut self) {
+        self.amend_metadata = None;
+    }
+
+    fn amend_metadata_field(&mut self) -> Option<&mut String> {
+        let metadata = self.amend_metadata.as_mut()?;
+        Some(if metadata.editing_date { &mut metadata.date } else { &mut metadata.author })
+    }
+
+    pub fn add_amend_metadata_char(&mut self, c: char) {
+        if let Some(field) = self.amend_metadata_field() {
+            field.push(c);
         }
-        let i = match self.branch_list_state.selected() {
-            Some(i) if i >= self.branches.len() - 1 => 0,
-            Some(i) => i + 1,
-            None => 0,
-        };
-        self.branch_list_state.select(Some(i));
     }
 
-    pub fn previous_branch(&mut self) {
-        if self.branches.is_empty() {
-            return;
+    pub fn delete_amend_metadata_char(&mut self) {
+        if let Some(field) = self.amend_metadata_field() {
+            field.pop();
         }
-        let i = match self.branch_list_state.selected() {
-            Some(0) => self.branches.len() - 1,
-            Some(i) => i - 1,
-            None => 0,
-        };
-        self.branch_list_state.select(Some(i));
     }
 
-    pub fn switch_to_selected_branch(&mut self) {
-        if let Some(index) = self.branch_list_state.selected() {
-            if let Some(branch) = self.branches.get(index) {
-                if branch.is_current {
-                    self.set_status("Already on this branch".to_string(), MessageType::Info);
-                    return;
-                }
+    /// Switches between the author and date prompts
+    pub fn toggle_amend_metadata_field(&mut self) {
+        if let Some(metadata) = self.amend_metadata.as_mut() {
+            metadata.editing_date = !metadata.editing_date;
+        }
+    }
 
-                match crate::git::switch_branch(&branch.name) {
-                    Ok(msg) => {
-                        self.set_status(msg, MessageType::Success);
-                        self.refresh_branches();
-                    }
-                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
-                }
+    /// Enter on the author moves on to the date once the author is valid;
+    /// Enter on the date amends HEAD
+    pub fn confirm_amend_metadata(&mut self) {
+        let Some(metadata) = self.amend_metadata.as_mut() else {
+            return;
+        };
+        if !crate::git::is_valid_author(&metadata.author) {
+            metadata.editing_date = false;
+            self.set_status("Author must look like: Name <email>".to_string(), MessageType::Error);
+            return;
+        }
+        if !metadata.editing_date {
+            metadata.editing_date = true;
+            return;
+        }
+        if metadata.date.trim().is_empty() {
+            self.set_status("Date cannot be empty".to_string(), MessageType::Error);
+            return;
+        }
+
+        match crate::git::amend_author_and_date(&metadata.author, &metadata.date) {
+            Ok(msg) => {
+                self.amend_metadata = None;
+                self.refresh_all();
+                self.set_status(msg, MessageType::Success);
             }
+            // Keep the prompt open so the value can be corrected
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
         }
     }
 
-    pub fn delete_selected_branch(&mut self) {
-        if let Some(index) = self.branch_list_state.selected() {
-            if let Some(branch) = self.branches.get(index) {
-                if branch.is_current {
-                    self.set_status("Cannot delete current branch".to_string(), MessageType::Error);
-                    return;
-                }
+    /// Toggles whether a new commit may be made with nothing staged
+    pub fn toggle_commit_allow_empty(&mut self) {
+        if !self.amend_mode && self.reword_target.is_none() {
+            self.commit_allow_empty = !self.commit_allow_empty;
+        }
+    }
 
-                if branch.is_remote {
-                    self.set_status("Cannot delete remote branches from this view".to_string(), MessageType::Error);
-                    return;
-                }
+    /// Toggles whether the amend also picks up unstaged tracked changes
+    pub fn toggle_amend_include_unstaged(&mut self) {
+        if self.amend_mode {
+            self.amend_include_unstaged = !self.amend_include_unstaged;
+        }
+    }
 
-                match crate::git::delete_branch(&branch.name, false) {
-                    Ok(msg) => {
-                        self.set_status(msg, MessageType::Success);
-                        self.refresh_branches();
-                    }
-                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
-                }
+    pub fn enter_amend_mode(&mut self) {
+        if self.request_commit_editor(true, false) {
+            return;
+        }
+        match crate::git::get_last_commit_message() {
+            Ok(msg) => {
+                self.amend_mode = true;
+                self.commit_message_mode = true;
+                self.commit_message_input = msg;
+                self.staged_stats = crate::git::get_staged_numstat().unwrap_or_default();
             }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
         }
     }
 
-    pub fn merge_selected_branch(&mut self) {
-        if let Some(index) = self.branch_list_state.selected() {
-            if let Some(branch) = self.branches.get(index) {
-                if branch.is_current {
-                    self.set_status("Cannot merge a branch into itself".to_string(), MessageType::Error);
+    pub fn discard_selected_file(&mut self) {
+        if let Some(list_idx) = self.status_list_state.selected() {
+            if let Some(file_idx) = self.list_index_to_file_index(list_idx) {
+                if let Some(file) = self.status_files.get(file_idx) {
+                if file.staged {
+                    self.set_status("Cannot discard staged file. Unstage it first.".to_string(), MessageType::Error);
                     return;
                 }
 
-                match crate::git::merge_branch(&branch.name) {
+                match crate::git::discard_file(&file.path) {
                     Ok(msg) => {
                         self.set_status(msg, MessageType::Success);
-                        self.refresh_branches();
                         self.refresh_status();
                     }
                     Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
                 }
+                }
             }
         }
     }
 
-    pub fn enter_new_branch_mode(&mut self) {
-        self.new_branch_input_mode = true;
-        self.new_branch_name_input.clear();
+    /// Opens the "discard all changes" popup, which needs "yes" typed out
+    pub fn open_discard_all(&mut self) {
+        if self.status_files.is_empty() {
+            self.set_status("Nothing to discard".to_string(), MessageType::Info);
+            return;
+        }
+        self.discard_all_input = Some(String::new());
+        self.discard_all_untracked = false;
     }
 
-    pub fn exit_new_branch_mode(&mut self) {
-        self.new_branch_input_mode = false;
+    pub fn close_discard_all(&mut self) {
+        self.discard_all_input = None;
     }
 
-    pub fn add_new_branch_char(&mut self, c: char) {
-        self.new_branch_name_input.push(c);
+    pub fn add_discard_all_char(&mut self, c: char) {
+        if let Some(ref mut input) = self.discard_all_input {
+            input.push(c);
+        }
     }
 
-    pub fn delete_new_branch_char(&mut self) {
-        self.new_branch_name_input.pop();
+    pub fn delete_discard_all_char(&mut self) {
+        if let Some(ref mut input) = self.discard_all_input {
+            input.pop();
+        }
     }
 
-    pub fn execute_create_new_branch(&mut self) {
-        if self.new_branch_name_input.is_empty() {
-            self.set_status("Branch name cannot be empty".to_string(), MessageType::Error);
-            self.new_branch_input_mode = false;
+    pub fn toggle_discard_all_untracked(&mut self) {
+        self.discard_all_untracked = !self.discard_all_untracked;
+    }
+
+    pub fn execute_discard_all(&mut self) {
+        let Some(input) = self.discard_all_input.as_deref() else {
+            return;
+        };
+        if input.trim() != "yes" {
+            self.set_status("Type \"yes\" to discard all changes".to_string(), MessageType::Error);
             return;
         }
 
-        match crate::git::create_new_branch(&self.new_branch_name_input) {
-            Ok(msg) => {
-                self.set_status(msg, MessageType::Success);
-                self.new_branch_input_mode = false;
-                self.refresh_branches();
-            }
-            Err(e) => {
-                self.set_status(format!("Error: {}", e), MessageType::Error);
-                self.new_branch_input_mode = false;
-            }
+        self.discard_all_input = None;
+        match crate::git::discard_all_changes(self.discard_all_untracked) {
+            Ok(msg) => self.set_status(msg, MessageType::Success),
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
         }
+        self.status_show_diff = false;
+        self.refresh_status();
     }
 
-    // Remote operations
-    pub fn fetch_from_remote(&mut self) {
-        match crate::git::fetch() {
-            Ok(msg) => {
-                self.set_status(msg, MessageType::Success);
-                self.refresh_branches();
+    /// Opens the interactive staging popup for the selected file
+    pub fn start_interactive_staging(&mut self) {
+        let Some(file) = self
+            .selected_status_file_index()
+            .and_then(|file_idx| self.status_files.get(file_idx))
+        else {
+            return;
+        };
+
+        let path = file.path.clone();
+        let unstage = file.staged;
+
+        match crate::git::get_file_patch(&path, unstage) {
+            Ok(diff) => {
+                let patch = FilePatch::parse(&diff);
+                if patch.hunks.is_empty() {
+                    self.set_status(format!("No hunks to stage in {}", path), MessageType::Info);
+                    return;
+                }
+                self.interactive_staging = Some(InteractiveStaging {
+                    path,
+                    unstage,
+                    stash: false,
+                    picker: HunkPicker::new(patch),
+                });
             }
             Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
         }
     }
 
-    pub fn push_to_remote(&mut self) {
-        match crate::git::push(false) {
-            Ok(msg) => self.set_status(msg, MessageType::Success),
-            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+    /// Opens the hunk picker for the selected file's unstaged changes; the
+    /// chosen hunks are stashed rather than staged
+    pub fn start_interactive_stash(&mut self) {
+        let Some(file) = self
+            .selected_status_file_index()
+            .and_then(|file_idx| self.status_files.get(file_idx))
+        else {
+            return;
+        };
+        if file.staged {
+            self.set_status("Only unstaged changes can be stashed by hunk".to_string(), MessageType::Info);
+            return;
         }
-    }
 
-    pub fn pull_from_remote(&mut self) {
-        match crate::git::pull(false) {
-            Ok(msg) => {
-                self.set_status(msg, MessageType::Success);
-                self.refresh_status();
-                self.refresh_branches();
+        let path = file.path.clone();
+        match crate::git::get_file_patch(&path, false) {
+            Ok(diff) => {
+                let patch = FilePatch::parse(&diff);
+                if patch.hunks.is_empty() {
+                    self.set_status(format!("No hunks to stash in {}", path), MessageType::Info);
+                    return;
+                }
+                self.interactive_staging = Some(InteractiveStaging {
+                    path,
+                    unstage: false,
+                    stash: true,
+                    picker: HunkPicker::new(patch),
+                });
             }
             Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
         }
     }
-}
 
+    /// Records a stage/skip decision for the current hunk
+    pub fn interactive_decide(&mut self, stage: bool) {
+        if let Some(ref mut staging) = self.interactive_staging {
+            staging.picker.decide(stage);
+            if staging.picker.is_done() {
+                self.finish_interactive_staging();
+            }
+        }
+    }
+
+    pub fn interactive_split(&mut self) {
+        if let Some(ref mut staging) = self.interactive_staging {
+            if !staging.picker.split() {
+                self.set_status("This hunk cannot be split further".to_string(), MessageType::Info);
+            }
+        }
+    }
+
+    pub fn cancel_interactive_staging(&mut self) {
+        self.interactive_staging = None;
+    }
+
+    /// Applies the chosen hunks to the index and closes the popup
+    pub fn finish_interactive_staging(&mut self) {
+        let Some(staging) = self.interactive_staging.take() else {
+            return;
+        };
+
+        let verb = if staging.unstage { "Unstaged" } else { "Staged" };
+        let count = staging.picker.chosen_count();
+
+        let Some(patch) = staging.picker.patch.build(&staging.picker.selected, staging.unstage) else {
+            self.set_status(format!("No hunks selected in {}", staging.path), MessageType::Info);
+            return;
+        };
+
+        if staging.stash {
+            let message = format!("{} hunk(s) of {}", count, staging.path);
+            match crate::git::stash_patch(&patch, &message) {
+                Ok(msg) => self.set_status(msg, MessageType::Success),
+                Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+            }
+            self.refresh_status();
+            self.refresh_stashes();
+            return;
+        }
+
+        match crate::git::apply_patch_to_index(&patch, staging.unstage) {
+            Ok(()) => {
+                self.set_status(
+                    format!("{} {} hunk(s) in {}", verb, count, staging.path),
+                    MessageType::Success,
+                );
+                self.refresh_status();
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Opens the line staging popup for the selected file
+    pub fn start_line_staging(&mut self) {
+        let Some(file) = self
+            .selected_status_file_index()
+            .and_then(|file_idx| self.status_files.get(file_idx))
+        else {
+            return;
+        };
+
+        let path = file.path.clone();
+        let unstage = file.staged;
+
+        match crate::git::get_file_patch(&path, unstage) {
+            Ok(diff) => {
+                let patch = FilePatch::parse(&diff);
+                if patch.hunks.is_empty() {
+                    self.set_status(format!("No lines to stage in {}", path), MessageType::Info);
+                    return;
+                }
+                self.line_staging = Some(LineStaging {
+                    path,
+                    unstage,
+                    picker: LinePicker::new(patch),
+                });
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Moves the line cursor; `extend` grows the selection instead of moving it
+    pub fn line_staging_move(&mut self, down: bool, extend: bool) {
+        if let Some(ref mut staging) = self.line_staging {
+            staging.picker.move_cursor(down, extend);
+        }
+    }
+
+    pub fn cancel_line_staging(&mut self) {
+        self.line_staging = None;
+    }
+
+    /// Applies the selected lines to the index, then reloads the file's
+    /// remaining changes so more lines can be picked
+    pub fn apply_line_staging(&mut self) {
+        let Some(ref staging) = self.line_staging else {
+            return;
+        };
+
+        let verb = if staging.unstage { "Unstaged" } else { "Staged" };
+        let count = staging.picker.selected_changes();
+        let Some(patch) = staging.picker.patch.build(&staging.picker.selection(), staging.unstage) else {
+            self.set_status("No changed lines selected".to_string(), MessageType::Info);
+            return;
+        };
+
+        let (path, unstage) = (staging.path.clone(), staging.unstage);
+        if let Err(e) = crate::git::apply_patch_to_index(&patch, unstage) {
+            self.set_status(format!("Error: {}", e), MessageType::Error);
+            return;
+        }
+
+        self.set_status(format!("{} {} line(s) in {}", verb, count, path), MessageType::Success);
+        self.refresh_status();
+
+        let remaining = crate::git::get_file_patch(&path, unstage).map(|diff| FilePatch::parse(&diff));
+        match remaining {
+            Ok(patch) if !patch.hunks.is_empty() => {
+                self.line_staging = Some(LineStaging {
+                    path,
+                    unstage,
+                    picker: LinePicker::new(patch),
+                });
+            }
+            _ => self.line_staging = None,
+        }
+    }
+
+    /// Updates the selected submodule, or all submodules if the
+    /// selection is not one
+    pub fn update_submodules(&mut self) {
+        let path = self
+            .selected_status_file_index()
+            .and_then(|file_idx| self.status_files.get(file_idx))
+            .filter(|file| file.is_submodule)
+            .map(|file| file.path.clone());
+
+        match crate::git::update_submodule(path.as_deref()) {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.refresh_status();
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    pub fn reveal_selected_file(&mut self) {
+        if let Some(file) = self
+            .selected_status_file_index()
+            .and_then(|file_idx| self.status_files.get(file_idx))
+        {
+            match crate::platform::reveal_in_file_manager(&file.path) {
+                Ok(msg) => self.set_status(msg, MessageType::Success),
+                Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+            }
+        }
+    }
+
+    pub fn toggle_status_diff(&mut self) {
+        self.status_show_diff = !self.status_show_diff;
+
+        if self.status_show_diff {
+            self.load_status_diff();
+        } else {
+            self.status_diff_content = None;
+            self.status_diff_path = None;
+            self.status_diff_scroll = 0;
+            self.status_conflicts = None;
+            self.status_conflict_lines.clear();
+        }
+    }
+
+    /// Loads the diff for the currently selected status file.
+    /// Clears the diff pane when the selection is on a header row.
+    fn load_status_diff(&mut self) {
+        self.status_diff_scroll = 0;
+        self.status_diff_content = None;
+        self.status_diff_path = None;
+        self.status_conflicts = None;
+        self.status_conflict_lines.clear();
+
+        let Some(file) = self
+            .selected_status_file_index()
+            .and_then(|file_idx| self.status_files.get(file_idx))
+        else {
+            return;
+        };
+
+        // A conflicted file is shown as it is on disk, markers and all,
+        // starting at its first conflict (a file deleted on one side or a
+        // binary one falls back to the diff)
+        if file.status == crate::git::FileStatus::Conflicted {
+            if let Ok(content) = std::fs::read_to_string(&file.path) {
+                let conflicts = crate::git::find_conflicts(&content);
+                let mut lines = self.highlight_browse_file(&content, &file.path);
+                if !self.no_color {
+                    crate::syntax::mark_conflicts(&mut lines, &content);
+                }
+                self.status_diff_scroll = conflicts.first().map_or(0, |&line| clamp_scroll(line));
+                self.status_diff_path = Some(file.path.clone());
+                self.status_conflict_lines = lines;
+                self.status_diff_content = Some(content);
+                self.status_conflicts = Some(conflicts);
+                return;
+            }
+        }
+
+        match crate::git::get_file_diff(&file.path, file.staged, self.ignore_whitespace) {
+            Ok(diff) => {
+                self.status_diff_path = Some(file.path.clone());
+                self.status_diff_content = Some(diff);
+            }
+            Err(e) => {
+                self.set_status(format!("Failed to load diff: {}", e), MessageType::Error);
+                self.status_show_diff = false;
+            }
+        }
+    }
+
+    /// Moves to the next file and shows its diff
+    pub fn next_status_diff_file(&mut self) {
+        self.step_status_selection(true);
+        self.load_status_diff();
+    }
+
+    /// Moves to the previous file and shows its diff
+    pub fn previous_status_diff_file(&mut self) {
+        self.step_status_selection(false);
+        self.load_status_diff();
+    }
+
+    fn selected_status_file_index(&self) -> Option<usize> {
+        self.status_list_state
+            .selected()
+            .and_then(|list_idx| self.list_index_to_file_index(list_idx))
+    }
+
+    /// Scrolls the conflicted file to its next conflict, wrapping around
+    pub fn next_conflict(&mut self) {
+        let Some(conflicts) = &self.status_conflicts else {
+            return;
+        };
+        let scroll = self.status_diff_scroll as usize;
+        let next = conflicts.iter().position(|&line| line > scroll).unwrap_or(0);
+        self.goto_conflict(next);
+    }
+
+    /// Scrolls the conflicted file to its previous conflict, wrapping around
+    pub fn previous_conflict(&mut self) {
+        let Some(conflicts) = &self.status_conflicts else {
+            return;
+        };
+        let scroll = self.status_diff_scroll as usize;
+        let previous = conflicts
+            .iter()
+            .rposition(|&line| line < scroll)
+            .unwrap_or(conflicts.len().saturating_sub(1));
+        self.goto_conflict(previous);
+    }
+
+    fn goto_conflict(&mut self, index: usize) {
+        let Some(&line) = self.status_conflicts.as_ref().and_then(|conflicts| conflicts.get(index)) else {
+            self.set_status("No conflict markers left in this file".to_string(), MessageType::Info);
+            return;
+        };
+        let total = self.status_conflicts.as_ref().map_or(0, |conflicts| conflicts.len());
+        self.status_diff_scroll = clamp_scroll(line);
+        self.set_status(format!("Conflict {}/{}", index + 1, total), MessageType::Info);
+    }
+
+    pub fn scroll_status_diff_up(&mut self) {
+        if self.status_diff_scroll > 0 {
+            self.status_diff_scroll -= 1;
+        }
+    }
+
+    pub fn scroll_status_diff_down(&mut self) {
+        self.status_diff_scroll += 1;
+    }
+
+    pub fn scroll_status_diff_page_up(&mut self) {
+        self.status_diff_scroll = self.status_diff_scroll.saturating_sub(10);
+    }
+
+    pub fn scroll_status_diff_page_down(&mut self) {
+        self.status_diff_scroll = self.status_diff_scroll.saturating_add(10);
+    }
+
+    // Stash panel operations
+    pub fn next_stash(&mut self) {
+        if self.stashes.is_empty() {
+            return;
+        }
+        let i = match self.stash_list_state.selected() {
+            Some(i) if i >= self.stashes.len() - 1 => 0,
+            Some(i) => i + 1,
+            None => 0,
+        };
+        self.stash_list_state.select(Some(i));
+        self.update_stash_stat();
+        if self.stash_diff.is_some() {
+            self.load_stash_diff();
+        }
+    }
+
+    pub fn previous_stash(&mut self) {
+        if self.stashes.is_empty() {
+            return;
+        }
+        let i = match self.stash_list_state.selected() {
+            Some(0) => self.stashes.len() - 1,
+            Some(i) => i - 1,
+            None => 0,
+        };
+        self.stash_list_state.select(Some(i));
+        self.update_stash_stat();
+        if self.stash_diff.is_some() {
+            self.load_stash_diff();
+        }
+    }
+
+    /// Whether apply and pop stage again what was staged when stashing
+    pub fn toggle_stash_restore_index(&mut self) {
+        self.stash_restore_index = !self.stash_restore_index;
+        let msg = if self.stash_restore_index {
+            "Apply/pop restore the staged changes (--index)"
+        } else {
+            "Apply/pop bring every change back unstaged"
+        };
+        self.set_status(msg.to_string(), MessageType::Info);
+    }
+
+    pub fn apply_selected_stash(&mut self) {
+        if let Some(index) = self.stash_list_state.selected() {
+            if let Some(stash) = self.stashes.get(index) {
+                match crate::git::apply_stash(stash.index, self.stash_restore_index) {
+                    Ok((msg, conflicts)) => {
+                        self.set_status(msg, stash_message_type(conflicts));
+                        self.refresh_status();
+                    }
+                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+                }
+            }
+        }
+    }
+
+    pub fn pop_selected_stash(&mut self) {
+        if let Some(index) = self.stash_list_state.selected() {
+            if let Some(stash) = self.stashes.get(index) {
+                match crate::git::pop_stash(stash.index, self.stash_restore_index) {
+                    Ok((msg, conflicts)) => {
+                        self.set_status(msg, stash_message_type(conflicts));
+                        self.refresh_status();
+                        self.refresh_stashes();
+                    }
+                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+                }
+            }
+        }
+    }
+
+    pub fn drop_selected_stash(&mut self) {
+        if let Some(index) = self.stash_list_state.selected() {
+            if let Some(stash) = self.stashes.get(index) {
+                match crate::git::drop_stash(stash.index) {
+                    Ok(msg) => {
+                        self.set_status(msg, MessageType::Success);
+                        self.refresh_stashes();
+                    }
+                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+                }
+            }
+        }
+    }
+
+    // Stash target picker
+    /// Opens a list of local branches to switch to before applying the selected stash
+    pub fn open_stash_target_picker(&mut self) {
+        if self.stash_list_state.selected().is_none() || self.stashes.is_empty() {
+            return;
+        }
+
+        match crate::git::get_branches() {
+            Ok(branches) => {
+                let names: Vec<String> = branches
+                    .into_iter()
+                    .filter(|b| !b.is_remote && !b.is_current)
+                    .map(|b| b.name)
+                    .collect();
+                if names.is_empty() {
+                    self.set_status("No other local branches".to_string(), MessageType::Info);
+                    return;
+                }
+
+                self.stash_target_branches = names;
+                self.stash_target_list_state = ListState::default();
+                self.stash_target_list_state.select(Some(0));
+                self.stash_target_visible = true;
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    pub fn close_stash_target_picker(&mut self) {
+        self.stash_target_visible = false;
+    }
+
+    pub fn next_stash_target(&mut self) {
+        if self.stash_target_branches.is_empty() {
+            return;
+        }
+        let i = match self.stash_target_list_state.selected() {
+            Some(i) if i >= self.stash_target_branches.len() - 1 => 0,
+            Some(i) => i + 1,
+            None => 0,
+        };
+        self.stash_target_list_state.select(Some(i));
+    }
+
+    pub fn previous_stash_target(&mut self) {
+        if self.stash_target_branches.is_empty() {
+            return;
+        }
+        let i = match self.stash_target_list_state.selected() {
+            Some(0) => self.stash_target_branches.len() - 1,
+            Some(i) => i - 1,
+            None => 0,
+        };
+        self.stash_target_list_state.select(Some(i));
+    }
+
+    /// Switches to the picked branch, then applies (or pops) the selected stash there
+    pub fn apply_stash_on_target_branch(&mut self, pop: bool) {
+        self.stash_target_visible = false;
+
+        let Some(branch) = self
+            .stash_target_list_state
+            .selected()
+            .and_then(|i| self.stash_target_branches.get(i))
+            .cloned()
+        else {
+            return;
+        };
+        let Some(stash_index) = self
+            .stash_list_state
+            .selected()
+            .and_then(|i| self.stashes.get(i))
+            .map(|stash| stash.index)
+        else {
+            return;
+        };
+
+        if let Err(e) = crate::git::switch_branch(&branch) {
+            self.set_status(format!("Error: {}", e), MessageType::Error);
+            return;
+        }
+
+        let result = if pop {
+            crate::git::pop_stash(stash_index, self.stash_restore_index)
+        } else {
+            crate::git::apply_stash(stash_index, self.stash_restore_index)
+        };
+        self.refresh_all();
+
+        match result {
+            Ok((msg, conflicts)) => {
+                self.set_status(format!("Switched to '{}'. {}", branch, msg), stash_message_type(conflicts));
+            }
+            Err(e) => self.set_status(
+                format!("Switched to '{}', but the stash did not apply: {}", branch, e),
+                MessageType::Error,
+            ),
+        }
+    }
+
+    // Stash creation methods
+    pub fn enter_stash_input_mode(&mut self) {
+        self.stash_input_mode = true;
+        self.stash_message_input.clear();
+    }
+
+    pub fn exit_stash_input_mode(&mut self) {
+        self.stash_input_mode = false;
+        self.restash_index = None;
+    }
+
+    /// Opens the stash prompt with the selected stash's message to give it
+    /// a new one
+    pub fn enter_restash_mode(&mut self) {
+        let Some(stash) = self.stash_list_state.selected().and_then(|i| self.stashes.get(i)) else {
+            return;
+        };
+        self.stash_message_input = stash.message.clone();
+        self.restash_index = Some(stash.index);
+        self.stash_input_mode = true;
+    }
+
+    /// Asks before re-stashing, since the stash moves to stash@{0}
+    fn confirm_restash(&mut self, index: usize) {
+        let message = self.stash_message_input.trim().to_string();
+        self.exit_stash_input_mode();
+        if message.is_empty() {
+            self.set_status("Stash message cannot be empty".to_string(), MessageType::Error);
+            return;
+        }
+        let Some(stash) = self.stashes.iter().find(|s| s.index == index) else {
+            return;
+        };
+
+        let mut details = vec![format!("stash@{{{}}}: {}", index, stash.message)];
+        if index != 0 {
+            details.push(format!(
+                "It becomes stash@{{0}}; stash@{{0}}..stash@{{{}}} each move down by one",
+                index - 1
+            ));
+        }
+        self.confirm = Some(ConfirmPrompt {
+            message: format!("Re-stash stash@{{{}}} as '{}'?", index, message),
+            details,
+            action: ConfirmAction::Restash {
+                index,
+                branch: stash.branch.clone(),
+                message,
+            },
+        });
+    }
+
+    pub fn add_stash_char(&mut self, c: char) {
+        self.stash_message_input.push(c);
+    }
+
+    pub fn delete_stash_char(&mut self) {
+        self.stash_message_input.pop();
+    }
+
+    pub fn execute_create_stash(&mut self) {
+        if let Some(index) = self.restash_index {
+            self.confirm_restash(index);
+            return;
+        }
+
+        let message = if self.stash_message_input.is_empty() {
+            None
+        } else {
+            Some(self.stash_message_input.as_str())
+        };
+
+        match crate::git::create_stash(message, false) {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.stash_input_mode = false;
+                self.refresh_status();
+                self.refresh_stashes();
+            }
+            Err(e) => {
+                self.set_status(format!("Error: {}", e), MessageType::Error);
+                self.stash_input_mode = false;
+            }
+        }
+    }
+
+    // Branches panel operations
+    pub fn refresh_branches(&mut self) {
+        match crate::git::get_branches() {
+            Ok(branches) => {
+                self.branches = branches;
+                let mut state = ListState::default();
+                if !self.branches.is_empty() {
+                    state.select(Some(0));
+                }
+                self.branch_list_state = state;
+                self.update_branch_merge_base();
+                self.refresh_head();
+                // Upstream refs may have moved (fetch, push, pull)
+                self.upstream_divergence = crate::git::get_upstream_divergence().unwrap_or_default();
+            }
+            Err(e) => self.set_status(format!("Failed to refresh branches: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Re-reads HEAD after something may have moved it
+    pub fn refresh_head(&mut self) {
+        self.head = crate::git::get_head().ok();
+        self.head_branch = crate::git::get_head_branch();
+    }
+
+    // Tags panel operations
+    pub fn refresh_tags(&mut self) {
+        match crate::git::get_tags() {
+            Ok(tags) => {
+                self.tags = tags;
+                let mut state = ListState::default();
+                if !self.tags.is_empty() {
+                    state.select(Some(0));
+                }
+                self.tag_list_state = state;
+                self.verify_selected_tag();
+            }
+            Err(e) => self.set_status(format!("Failed to refresh tags: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Checks the signature of the selected tag the first time it is selected
+    fn verify_selected_tag(&mut self) {
+        let Some(tag) = self.tag_list_state.selected().and_then(|i| self.tags.get_mut(i)) else {
+            return;
+        };
+        if tag.signature == TagSignature::Unchecked {
+            match crate::git::verify_tag(&tag.name) {
+                Ok(signature) => tag.signature = signature,
+                Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+            }
+        }
+    }
+
+    pub fn next_tag(&mut self) {
+        if self.tags.is_empty() {
+            return;
+        }
+        let i = match self.tag_list_state.selected() {
+            Some(i) if i >= self.tags.len() - 1 => 0,
+            Some(i) => i + 1,
+            None => 0,
+        };
+        self.tag_list_state.select(Some(i));
+        self.verify_selected_tag();
+    }
+
+    pub fn previous_tag(&mut self) {
+        if self.tags.is_empty() {
+            return;
+        }
+        let i = match self.tag_list_state.selected() {
+            Some(0) => self.tags.len() - 1,
+            Some(i) => i - 1,
+            None => 0,
+        };
+        self.tag_list_state.select(Some(i));
+        self.verify_selected_tag();
+    }
+
+    fn selected_tag_name(&self) -> Option<String> {
+        self.tag_list_state
+            .selected()
+            .and_then(|i| self.tags.get(i))
+            .map(|tag| tag.name.clone())
+    }
+
+    pub fn checkout_selected_tag(&mut self) {
+        let Some(name) = self.selected_tag_name() else {
+            return;
+        };
+        match crate::git::checkout_tag(&name) {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.refresh_all();
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Asks before deleting, since a deleted tag can't be recovered from the reflog
+    pub fn delete_selected_tag(&mut self) {
+        let Some(name) = self.selected_tag_name() else {
+            return;
+        };
+        self.confirm = Some(ConfirmPrompt {
+            message: format!("Delete tag '{}'?", name),
+            details: Vec::new(),
+            action: ConfirmAction::DeleteTag(name),
+        });
+    }
+
+    pub fn push_selected_tag(&mut self) {
+        let Some(name) = self.selected_tag_name() else {
+            return;
+        };
+        match crate::git::push_tag(&name) {
+            Ok(msg) => self.set_status(msg, MessageType::Success),
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    pub fn push_all_tags(&mut self) {
+        match crate::git::push_tags() {
+            Ok(msg) => self.set_status(msg, MessageType::Success),
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    pub fn enter_tag_input_mode(&mut self) {
+        self.tag_input_mode = true;
+        self.tag_input.clear();
+    }
+
+    pub fn exit_tag_input_mode(&mut self) {
+        self.tag_input_mode = false;
+        self.tag_sign = false;
+    }
+
+    pub fn toggle_tag_sign(&mut self) {
+        self.tag_sign = !self.tag_sign;
+    }
+
+    pub fn add_tag_char(&mut self, c: char) {
+        self.tag_input.push(c);
+    }
+
+    pub fn delete_tag_char(&mut self) {
+        self.tag_input.pop();
+    }
+
+    /// Tags HEAD, then offers to push the new tag
+    pub fn execute_create_tag(&mut self) {
+        self.tag_input_mode = false;
+        let sign = std::mem::take(&mut self.tag_sign);
+
+        let input = self.tag_input.trim().to_string();
+        let (name, message) = match input.split_once(' ') {
+            Some((name, message)) => (name, Some(message.trim()).filter(|m| !m.is_empty())),
+            None => (input.as_str(), None),
+        };
+        if name.is_empty() {
+            self.set_status("Tag name cannot be empty".to_string(), MessageType::Error);
+            return;
+        }
+
+        match crate::git::create_tag(name, message, sign) {
+            Ok(msg) => {
+                self.refresh_commits();
+                self.refresh_tags();
+                if let Some(index) = self.tags.iter().position(|tag| tag.name == name) {
+                    self.tag_list_state.select(Some(index));
+                    self.verify_selected_tag();
+                }
+                self.set_status(msg, MessageType::Success);
+                self.confirm = Some(ConfirmPrompt {
+                    message: format!("Push tag '{}' to the remote?", name),
+                    details: Vec::new(),
+                    action: ConfirmAction::PushTag(name.to_string()),
+                });
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Looks up the common ancestor of HEAD and the selected branch
+    fn update_branch_merge_base(&mut self) {
+        self.branch_merge_base = self
+            .branch_list_state
+            .selected()
+            .and_then(|index| self.branches.get(index))
+            .filter(|branch| !branch.is_current)
+            .and_then(|branch| merge_base_summary("HEAD", &branch.name));
+    }
+
+    pub fn next_branch(&mut self) {
+        if self.branches.is_empty() {
+            return;
+        }
+        let i = match self.branch_list_state.selected() {
+            Some(i) if i >= self.branches.len() - 1 => 0,
+            Some(i) => i + 1,
+            None => 0,
+        };
+        self.branch_list_state.select(Some(i));
+        self.update_branch_merge_base();
+    }
+
+    pub fn previous_branch(&mut self) {
+        if self.branches.is_empty() {
+            return;
+        }
+        let i = match self.branch_list_state.selected() {
+            Some(0) => self.branches.len() - 1,
+            Some(i) => i - 1,
+            None => 0,
+        };
+        self.branch_list_state.select(Some(i));
+        self.update_branch_merge_base();
+    }
+
+    pub fn switch_to_selected_branch(&mut self) {
+        if let Some(index) = self.branch_list_state.selected() {
+            if let Some(branch) = self.branches.get(index) {
+                if branch.is_current {
+                    self.set_status("Already on this branch".to_string(), MessageType::Info);
+                    return;
+                }
+
+                match crate::git::switch_branch(&branch.name) {
+                    Ok(msg) => {
+                        self.set_status(msg, MessageType::Success);
+                        self.refresh_branches();
+                    }
+                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+                }
+            }
+        }
+    }
+
+    /// Toggles back to the branch checked out before this one
+    pub fn switch_to_previous_branch(&mut self) {
+        match crate::git::switch_previous_branch() {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.refresh_branches();
+                self.refresh_status();
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    pub fn delete_selected_branch(&mut self) {
+        if let Some(index) = self.branch_list_state.selected() {
+            if let Some(branch) = self.branches.get(index) {
+                if branch.is_current {
+                    self.set_status("Cannot delete current branch".to_string(), MessageType::Error);
+                    return;
+                }
+
+                if branch.is_remote {
+                    self.set_status("Cannot delete remote branches from this view".to_string(), MessageType::Error);
+                    return;
+                }
+
+                match crate::git::delete_branch(&branch.name, false) {
+                    Ok(msg) => {
+                        self.set_status(msg, MessageType::Success);
+                        self.refresh_branches();
+                    }
+                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+                }
+            }
+        }
+    }
+
+    /// Opens the reflog of the selected local branch
+    pub fn open_branch_reflog(&mut self) {
+        let Some(branch) = self.branch_list_state.selected().and_then(|i| self.branches.get(i)) else {
+            return;
+        };
+        if branch.is_remote {
+            self.set_status("Reflog is only shown for local branches".to_string(), MessageType::Error);
+            return;
+        }
+
+        let name = branch.name.clone();
+        match crate::git::get_branch_reflog(&name) {
+            Ok(entries) if entries.is_empty() => {
+                self.set_status(format!("No reflog entries for '{}'", name), MessageType::Info);
+            }
+            Ok(entries) => {
+                self.reflog = entries;
+                self.reflog_list_state = ListState::default().with_selected(Some(0));
+                self.reflog_branch = Some(name);
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Compares the selected branch with another copy of it: the upstream
+    /// of a local branch, or the same name on another remote
+    pub fn compare_selected_branch(&mut self) {
+        let Some(branch) = self.branch_list_state.selected().and_then(|i| self.branches.get(i)) else {
+            return;
+        };
+        let name = branch.name.clone();
+        match self.branch_variants(&name).first() {
+            Some(other) => self.show_branch_compare(name, other.clone()),
+            None => self.set_status(format!("No other copy of '{}' to compare with", name), MessageType::Info),
+        }
+    }
+
+    /// Compares the popup's left branch with its next copy
+    pub fn next_branch_variant(&mut self) {
+        let Some((left, right)) = self.branch_compare.clone() else {
+            return;
+        };
+        let variants = self.branch_variants(&left);
+        let next = variants
+            .iter()
+            .position(|name| *name == right)
+            .map_or(0, |i| (i + 1) % variants.len());
+        if let Some(other) = variants.get(next) {
+            self.show_branch_compare(left, other.clone());
+        }
+    }
+
+    /// Other branches sharing a branch's name: its upstream first, then the
+    /// local branch and the other remotes' copies in list order
+    fn branch_variants(&self, name: &str) -> Vec<String> {
+        let Some(branch) = self.branches.iter().find(|b| b.name == name) else {
+            return Vec::new();
+        };
+        let short = crate::git::branch_short_name(branch);
+        let mut variants: Vec<String> = branch.upstream.iter().cloned().collect();
+        for other in &self.branches {
+            if other.name != name
+                && crate::git::branch_short_name(other) == short
+                && !variants.contains(&other.name)
+            {
+                variants.push(other.name.clone());
+            }
+        }
+        variants
+    }
+
+    fn show_branch_compare(&mut self, left: String, right: String) {
+        match crate::git::compare_branches(&left, &right) {
+            Ok((only_left, only_right)) => {
+                self.branch_compare_commits = only_left
+                    .into_iter()
+                    .map(|commit| (false, commit))
+                    .chain(only_right.into_iter().map(|commit| (true, commit)))
+                    .collect();
+                let selected = (!self.branch_compare_commits.is_empty()).then_some(0);
+                self.branch_compare_list_state = ListState::default().with_selected(selected);
+                self.branch_compare_merge_base = merge_base_summary(&left, &right);
+                self.branch_compare = Some((left, right));
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    pub fn close_branch_compare(&mut self) {
+        self.branch_compare = None;
+        self.branch_compare_commits.clear();
+        self.branch_compare_merge_base = None;
+    }
+
+    pub fn next_branch_compare_commit(&mut self) {
+        if !self.branch_compare_commits.is_empty() {
+            let i = self
+                .branch_compare_list_state
+                .selected()
+                .map_or(0, |i| (i + 1) % self.branch_compare_commits.len());
+            self.branch_compare_list_state.select(Some(i));
+        }
+    }
+
+    pub fn previous_branch_compare_commit(&mut self) {
+        if !self.branch_compare_commits.is_empty() {
+            let i = self
+                .branch_compare_list_state
+                .selected()
+                .map_or(0, |i| if i == 0 { self.branch_compare_commits.len() - 1 } else { i - 1 });
+            self.branch_compare_list_state.select(Some(i));
+        }
+    }
+
+    /// Closes the popup and selects the chosen commit in the Log
+    pub fn goto_branch_compare_commit(&mut self) {
+        let Some(hash) = self
+            .branch_compare_list_state
+            .selected()
+            .and_then(|i| self.branch_compare_commits.get(i))
+            .map(|(_, c)| c.hash.clone())
+        else {
+            return;
+        };
+        self.close_branch_compare();
+        match self.commits.iter().position(|c| same_hash(&c.hash, &hash)) {
+            Some(index) => {
+                self.current_panel = Panel::Log;
+                self.list_state.select(Some(index));
+            }
+            None => self.set_status(format!("{} is not in the loaded log", hash), MessageType::Info),
+        }
+    }
+
+    /// Lists the commits a push would send: HEAD's commits missing from
+    /// its upstream
+    pub fn open_outgoing_commits(&mut self) {
+        self.show_sync_commits(false);
+    }
+
+    /// Fetches, then lists what pulling would bring: the upstream's commits
+    /// missing from HEAD
+    pub fn fetch_and_show_incoming(&mut self) {
+        if let Err(e) = crate::git::fetch() {
+            self.set_status(format!("Error: {}", e), MessageType::Error);
+            return;
+        }
+        // The fetched commits show up in the log and its ↑/↓ marks
+        self.refresh_branches();
+        self.refresh_commits();
+        self.show_sync_commits(true);
+    }
+
+    fn show_sync_commits(&mut self, incoming: bool) {
+        let branch = self.head_branch.clone().unwrap_or_else(|| "HEAD".to_string());
+        match crate::git::get_upstream_commits(incoming) {
+            Ok(None) if incoming => self.set_status(
+                format!("Fetched; '{}' has no upstream to compare with", branch),
+                MessageType::Info,
+            ),
+            Ok(None) => self.set_status(
+                format!("'{}' has no upstream; {} pushes and sets one", branch, self.keymap.hint("log.push")),
+                MessageType::Info,
+            ),
+            Ok(Some((upstream, commits))) if commits.is_empty() => {
+                let msg = if incoming {
+                    format!("Fetched; nothing new on {}", upstream)
+                } else {
+                    format!("Nothing to push: '{}' is up to date with {}", branch, upstream)
+                };
+                self.set_status(msg, MessageType::Info)
+            }
+            Ok(Some((upstream, commits))) => {
+                self.sync_commits = commits;
+                self.sync_list_state = ListState::default().with_selected(Some(0));
+                self.sync_upstream = Some(upstream);
+                self.sync_incoming = incoming;
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    pub fn close_sync_commits(&mut self) {
+        self.sync_upstream = None;
+        self.sync_commits.clear();
+    }
+
+    pub fn next_sync_commit(&mut self) {
+        if !self.sync_commits.is_empty() {
+            let i = self.sync_list_state.selected().map_or(0, |i| (i + 1) % self.sync_commits.len());
+            self.sync_list_state.select(Some(i));
+        }
+    }
+
+    pub fn previous_sync_commit(&mut self) {
+        if !self.sync_commits.is_empty() {
+            let i = self
+                .sync_list_state
+                .selected()
+                .map_or(0, |i| if i == 0 { self.sync_commits.len() - 1 } else { i - 1 });
+            self.sync_list_state.select(Some(i));
+        }
+    }
+
+    /// Closes the popup and selects the chosen commit in the Log
+    pub fn goto_sync_commit(&mut self) {
+        let Some(hash) = self
+            .sync_list_state
+            .selected()
+            .and_then(|i| self.sync_commits.get(i))
+            .map(|c| c.hash.clone())
+        else {
+            return;
+        };
+        self.close_sync_commits();
+        match self.commits.iter().position(|c| same_hash(&c.hash, &hash)) {
+            Some(index) => self.list_state.select(Some(index)),
+            None => self.set_status(format!("{} is not in the loaded log", hash), MessageType::Info),
+        }
+    }
+
+    /// Pushes the outgoing commits, or pulls the incoming ones
+    pub fn sync_from_popup(&mut self) {
+        let incoming = self.sync_incoming;
+        self.close_sync_commits();
+        if incoming {
+            self.pull_from_remote();
+        } else {
+            self.push_to_upstream();
+        }
+    }
+
+    pub fn close_branch_reflog(&mut self) {
+        self.reflog_branch = None;
+        self.reflog.clear();
+    }
+
+    pub fn next_reflog_entry(&mut self) {
+        if !self.reflog.is_empty() {
+            let i = self.reflog_list_state.selected().map_or(0, |i| (i + 1) % self.reflog.len());
+            self.reflog_list_state.select(Some(i));
+        }
+    }
+
+    pub fn previous_reflog_entry(&mut self) {
+        if !self.reflog.is_empty() {
+            let i = self
+                .reflog_list_state
+                .selected()
+                .map_or(0, |i| if i == 0 { self.reflog.len() - 1 } else { i - 1 });
+            self.reflog_list_state.select(Some(i));
+        }
+    }
+
+    /// Asks before moving the branch back to the selected reflog entry
+    pub fn reset_branch_to_reflog_entry(&mut self) {
+        let Some(ref branch) = self.reflog_branch else {
+            return;
+        };
+        let Some(entry) = self.reflog_list_state.selected().and_then(|i| self.reflog.get(i)) else {
+            return;
+        };
+
+        let is_current = self.branches.iter().any(|b| b.is_current && &b.name == branch);
+        let mut details = vec![format!("{}  {}", entry.selector, entry.subject)];
+        if is_current {
+            details.push("Local changes are kept; the reset stops if they would be lost".to_string());
+        }
+        self.confirm = Some(ConfirmPrompt {
+            message: format!("Reset '{}' to {}?", branch, entry.hash),
+            details,
+            action: ConfirmAction::ResetBranch {
+                branch: branch.clone(),
+                target: entry.hash.clone(),
+                is_current,
+            },
+        });
+    }
+
+    pub fn merge_selected_branch(&mut self) {
+        if let Some(index) = self.branch_list_state.selected() {
+            if let Some(branch) = self.branches.get(index) {
+                if branch.is_current {
+                    self.set_status("Cannot merge a branch into itself".to_string(), MessageType::Error);
+                    return;
+                }
+
+                let name = branch.name.clone();
+                let range = format!("HEAD..{}", name);
+                let preview = crate::git::get_range_commits(&range).and_then(|commits| {
+                    Ok((commits, crate::git::get_range_diff_stat(&format!("HEAD...{}", name))?))
+                });
+
+                match preview {
+                    Ok((commits, _)) if commits.is_empty() => {
+                        self.set_status(format!("Already up to date with '{}'", name), MessageType::Info);
+                    }
+                    Ok((commits, stat)) => {
+                        let mut details = merge_base_details(&name);
+                        details.extend(commits.iter().cloned());
+                        details.push(String::new());
+                        details.extend(stat);
+                        self.confirm = Some(ConfirmPrompt {
+                            message: format!("Merge {} commit(s) from '{}' into the current branch?", commits.len(), name),
+                            details,
+                            action: ConfirmAction::Merge(name),
+                        });
+                    }
+                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+                }
+            }
+        }
+    }
+
+    /// Previews the commits that would be replayed, then asks before rebasing
+    pub fn rebase_onto_selected_branch(&mut self) {
+        let Some(branch) = self.branch_list_state.selected().and_then(|i| self.branches.get(i)) else {
+            return;
+        };
+        if branch.is_current {
+            self.set_status("Cannot rebase a branch onto itself".to_string(), MessageType::Error);
+            return;
+        }
+        let name = branch.name.clone();
+
+        match crate::git::get_range_commits(&format!("{}..HEAD", name)) {
+            Ok(commits) => {
+                let message = if commits.is_empty() {
+                    format!("No local commits to replay; the current branch will fast-forward to '{}'. Continue?", name)
+                } else {
+                    format!("Replay {} commit(s) onto '{}'?", commits.len(), name)
+                };
+                let mut details = merge_base_details(&name);
+                details.extend(commits);
+                self.confirm = Some(ConfirmPrompt {
+                    message,
+                    details,
+                    action: ConfirmAction::Rebase(name),
+                });
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    pub fn enter_new_branch_mode(&mut self) {
+        self.new_branch_input_mode = true;
+        self.new_branch_name_input.clear();
+    }
+
+    pub fn exit_new_branch_mode(&mut self) {
+        self.new_branch_input_mode = false;
+    }
+
+    pub fn add_new_branch_char(&mut self, c: char) {
+        self.new_branch_name_input.push(c);
+    }
+
+    pub fn delete_new_branch_char(&mut self) {
+        self.new_branch_name_input.pop();
+    }
+
+    pub fn execute_create_new_branch(&mut self) {
+        if self.new_branch_name_input.is_empty() {
+            self.set_status("Branch name cannot be empty".to_string(), MessageType::Error);
+            self.new_branch_input_mode = false;
+            return;
+        }
+
+        match crate::git::create_new_branch(&self.new_branch_name_input) {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.new_branch_input_mode = false;
+                self.refresh_branches();
+            }
+            Err(e) => {
+                self.set_status(format!("Error: {}", e), MessageType::Error);
+                self.new_branch_input_mode = false;
+            }
+        }
+    }
+
+    // Worktree operations
+    pub fn open_worktrees(&mut self) {
+        match crate::git::get_worktrees() {
+            Ok(worktrees) => {
+                let root = crate::git::get_repo_root()
+                    .ok()
+                    .and_then(|root| std::fs::canonicalize(root).ok());
+                self.current_worktree = worktrees
+                    .iter()
+                    .find(|wt| std::fs::canonicalize(&wt.path).ok() == root)
+                    .map(|wt| wt.path.clone());
+
+                let mut state = ListState::default();
+                let current_idx = worktrees
+                    .iter()
+                    .position(|wt| Some(&wt.path) == self.current_worktree.as_ref());
+                if !worktrees.is_empty() {
+                    state.select(Some(current_idx.unwrap_or(0)));
+                }
+
+                self.worktrees = worktrees;
+                self.worktree_list_state = state;
+                self.worktrees_visible = true;
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    pub fn close_worktrees(&mut self) {
+        self.worktrees_visible = false;
+    }
+
+    pub fn next_worktree(&mut self) {
+        if self.worktrees.is_empty() {
+            return;
+        }
+        let i = match self.worktree_list_state.selected() {
+            Some(i) if i >= self.worktrees.len() - 1 => 0,
+            Some(i) => i + 1,
+            None => 0,
+        };
+        self.worktree_list_state.select(Some(i));
+    }
+
+    pub fn previous_worktree(&mut self) {
+        if self.worktrees.is_empty() {
+            return;
+        }
+        let i = match self.worktree_list_state.selected() {
+            Some(0) => self.worktrees.len() - 1,
+            Some(i) => i - 1,
+            None => 0,
+        };
+        self.worktree_list_state.select(Some(i));
+    }
+
+    /// Moves gitu into the selected worktree and reloads every panel
+    pub fn switch_to_selected_worktree(&mut self) {
+        let Some(worktree) = self
+            .worktree_list_state
+            .selected()
+            .and_then(|index| self.worktrees.get(index))
+            .cloned()
+        else {
+            return;
+        };
+
+        if worktree.is_bare {
+            self.set_status("Cannot switch to a bare worktree".to_string(), MessageType::Error);
+            return;
+        }
+
+        if Some(&worktree.path) == self.current_worktree.as_ref() {
+            self.set_status("Already in this worktree".to_string(), MessageType::Info);
+            return;
+        }
+
+        match std::env::set_current_dir(&worktree.path) {
+            Ok(()) => {
+                self.worktrees_visible = false;
+                self.refresh_all();
+                self.set_status(format!("Switched to worktree {}", worktree.path), MessageType::Success);
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    pub fn enter_worktree_input_mode(&mut self) {
+        self.worktree_input_mode = true;
+        self.worktree_input.clear();
+    }
+
+    pub fn exit_worktree_input_mode(&mut self) {
+        self.worktree_input_mode = false;
+    }
+
+    pub fn add_worktree_char(&mut self, c: char) {
+        self.worktree_input.push(c);
+    }
+
+    pub fn delete_worktree_char(&mut self) {
+        self.worktree_input.pop();
+    }
+
+    /// Adds a worktree from input of the form "<path> [branch]"
+    pub fn execute_add_worktree(&mut self) {
+        self.worktree_input_mode = false;
+
+        let input = self.worktree_input.clone();
+        let (path, branch) = split_worktree_input(&input, |rev| crate::git::resolve_commit(rev).is_ok());
+        if path.is_empty() {
+            self.set_status("Worktree path cannot be empty".to_string(), MessageType::Error);
+            return;
+        }
+
+        match crate::git::add_worktree(path, branch) {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.open_worktrees();
+                self.refresh_branches();
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    pub fn refresh_commits(&mut self) {
+        match get_commits(self.active_filter.as_ref(), self.first_parent, self.merge_filter) {
+            Ok(commits) => {
+                self.commits = commits;
+                self.upstream_divergence = crate::git::get_upstream_divergence().unwrap_or_default();
+                let mut state = ListState::default();
+                if !self.commits.is_empty() {
+                    state.select(Some(0));
+                }
+                self.list_state = state;
+                self.show_diff = false;
+                self.auto_diff = false;
+                self.diff_fullscreen = false;
+                self.worktree_diff_base = None;
+                self.file_filter = None;
+                self.current_diff = None;
+                self.tree_view_mode = false;
+                self.diff_scroll = 0;
+            }
+            Err(e) => self.set_status(format!("Failed to refresh log: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Reloads every panel, e.g. after the repository changed underneath us
+    pub fn refresh_all(&mut self) {
+        self.refresh_commits();
+        self.refresh_status();
+        self.refresh_stashes();
+        self.refresh_branches();
+        self.refresh_tags();
+    }
+
+    // Remote operations
+    pub fn fetch_from_remote(&mut self) {
+        match crate::git::fetch() {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.refresh_branches();
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Fetches with --prune, asking which remote to use when there are several
+    pub fn fetch_prune(&mut self) {
+        match crate::git::get_remotes() {
+            Ok(remotes) if remotes.len() > 1 => {
+                self.remotes_push = false;
+                self.show_remotes(remotes, 0)
+            }
+            Ok(remotes) => self.run_fetch_prune(remotes.first().map(|r| r.as_str())),
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Opens the remotes popup, even with a single remote, to manage URLs
+    pub fn open_remotes(&mut self) {
+        match crate::git::get_remotes() {
+            Ok(remotes) if remotes.is_empty() => {
+                self.set_status("No remotes configured".to_string(), MessageType::Info)
+            }
+            Ok(remotes) => {
+                self.remotes_push = false;
+                self.show_remotes(remotes, 0)
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    fn show_remotes(&mut self, remotes: Vec<String>, selected: usize) {
+        self.remote_urls = crate::git::get_remote_urls().unwrap_or_default();
+        self.remote_list_state = ListState::default();
+        self.remote_list_state.select(Some(selected.min(remotes.len().saturating_sub(1))));
+        self.remotes = remotes;
+        self.remotes_visible = true;
+    }
+
+    /// Opens the URL input for the selected remote, filled with its URL
+    pub fn enter_remote_url_input_mode(&mut self) {
+        let Some(remote) = self.remote_list_state.selected().and_then(|i| self.remotes.get(i)) else {
+            return;
+        };
+        self.remote_url_input = self.remote_urls.get(remote).cloned().unwrap_or_default();
+        self.remote_url_input_mode = true;
+    }
+
+    pub fn exit_remote_url_input_mode(&mut self) {
+        self.remote_url_input_mode = false;
+    }
+
+    pub fn add_remote_url_char(&mut self, c: char) {
+        self.remote_url_input.push(c);
+    }
+
+    pub fn delete_remote_url_char(&mut self) {
+        self.remote_url_input.pop();
+    }
+
+    /// Sets the selected remote's URL and relists the remotes
+    pub fn execute_set_remote_url(&mut self) {
+        let url = self.remote_url_input.trim().to_string();
+        if url.is_empty() {
+            self.set_status("Remote URL cannot be empty".to_string(), MessageType::Error);
+            return;
+        }
+        let selected = self.remote_list_state.selected().unwrap_or(0);
+        let Some(remote) = self.remotes.get(selected).cloned() else {
+            self.remote_url_input_mode = false;
+            return;
+        };
+        self.remote_url_input_mode = false;
+
+        match crate::git::set_remote_url(&remote, &url) {
+            Ok(msg) => self.set_status(msg, MessageType::Success),
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+        match crate::git::get_remotes() {
+            Ok(remotes) => self.show_remotes(remotes, selected),
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    pub fn close_remotes(&mut self) {
+        self.remotes_visible = false;
+    }
+
+    pub fn next_remote(&mut self) {
+        if self.remotes.is_empty() {
+            return;
+        }
+        let i = match self.remote_list_state.selected() {
+            Some(i) if i >= self.remotes.len() - 1 => 0,
+            Some(i) => i + 1,
+            None => 0,
+        };
+        self.remote_list_state.select(Some(i));
+    }
+
+    pub fn previous_remote(&mut self) {
+        if self.remotes.is_empty() {
+            return;
+        }
+        let i = match self.remote_list_state.selected() {
+            Some(0) => self.remotes.len() - 1,
+            Some(i) => i - 1,
+            None => 0,
+        };
+        self.remote_list_state.select(Some(i));
+    }
+
+    /// Prune-fetches the remote selected in the picker, or pushes to it
+    /// when the picker was opened for a push
+    pub fn fetch_selected_remote(&mut self) {
+        let remote = self
+            .remote_list_state
+            .selected()
+            .and_then(|i| self.remotes.get(i))
+            .cloned();
+        self.remotes_visible = false;
+        match remote {
+            Some(remote) if self.remotes_push => self.push_to_selected_remote(&remote),
+            Some(remote) => self.run_fetch_prune(Some(&remote)),
+            None => {}
+        }
+    }
+
+    /// Prune-fetches every remote from the picker
+    pub fn fetch_all_remotes(&mut self) {
+        if self.remotes_push {
+            return;
+        }
+        self.remotes_visible = false;
+        self.run_fetch_prune(None);
+    }
+
+    fn run_fetch_prune(&mut self, remote: Option<&str>) {
+        match crate::git::fetch_prune(remote) {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.refresh_branches();
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Pushes HEAD's branch, asking which remote to push to when there are
+    /// several; the picker starts on the branch's upstream remote
+    pub fn push_to_remote(&mut self) {
+        let remotes = match crate::git::get_remotes() {
+            Ok(remotes) => remotes,
+            Err(e) => {
+                self.set_status(format!("Error: {}", e), MessageType::Error);
+                return;
+            }
+        };
+        if remotes.len() < 2 {
+            self.push_to_upstream();
+            return;
+        }
+        let Some(branch) = self.head_branch.clone() else {
+            self.set_status("HEAD is detached; no branch to push".to_string(), MessageType::Info);
+            return;
+        };
+
+        let upstream = crate::git::get_upstream_remote(&branch);
+        let selected = upstream
+            .and_then(|upstream| remotes.iter().position(|remote| *remote == upstream))
+            .unwrap_or(0);
+        self.remotes_push = true;
+        self.show_remotes(remotes, selected);
+    }
+
+    /// Pushes HEAD's branch to the remote picked in the remotes popup
+    fn push_to_selected_remote(&mut self, remote: &str) {
+        let Some(branch) = self.head_branch.clone() else {
+            return;
+        };
+        if crate::git::get_upstream_remote(&branch).as_deref() == Some(remote) {
+            self.push_to_upstream();
+            return;
+        }
+
+        match crate::git::push_branch_to(remote, &branch) {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.refresh_branches();
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Pushes to the upstream, offering to pull first when it is ahead
+    fn push_to_upstream(&mut self) {
+        // A push is rejected when the upstream has commits we lack, so offer to pull first
+        if let Ok(Some((_, behind))) = crate::git::get_ahead_behind() {
+            if behind > 0 {
+                self.confirm = Some(ConfirmPrompt {
+                    message: format!(
+                        "The remote is {} commit{} ahead and would reject the push. Pull first, then push?",
+                        behind,
+                        if behind == 1 { "" } else { "s" }
+                    ),
+                    details: Vec::new(),
+                    action: ConfirmAction::PullThenPush,
+                });
+                return;
+            }
+        }
+
+        match crate::git::push(false) {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.refresh_branches();
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+
+    /// Runs the action of the confirm popup
+    pub fn accept_confirm(&mut self) {
+        let Some(prompt) = self.confirm.take() else {
+            return;
+        };
+
+        match prompt.action {
+            ConfirmAction::PullThenPush => {
+                let result = crate::git::pull(false).and_then(|_| crate::git::push(false));
+                match result {
+                    Ok(_) => self.set_status("Pulled and pushed to remote".to_string(), MessageType::Success),
+                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+                }
+                self.refresh_all();
+            }
+            ConfirmAction::Merge(name) => {
+                match crate::git::merge_branch(&name) {
+                    Ok(msg) => self.set_status(msg, MessageType::Success),
+                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+                }
+                self.refresh_all();
+            }
+            ConfirmAction::Rebase(name) => {
+                match crate::git::rebase_onto(&name) {
+                    Ok(msg) => self.set_status(msg, MessageType::Success),
+                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+                }
+                self.refresh_all();
+            }
+            ConfirmAction::DeleteTag(name) => {
+                match crate::git::delete_tag(&name) {
+                    Ok(msg) => self.set_status(msg, MessageType::Success),
+                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+                }
+                // Decorations in the log change too
+                self.refresh_commits();
+                self.refresh_tags();
+            }
+            ConfirmAction::PushTag(name) => match crate::git::push_tag(&name) {
+                Ok(msg) => self.set_status(msg, MessageType::Success),
+                Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+            },
+            ConfirmAction::ResetBranch { branch, target, is_current } => {
+                match crate::git::reset_branch(&branch, &target, is_current) {
+                    Ok(msg) => {
+                        self.set_status(msg, MessageType::Success);
+                        self.close_branch_reflog();
+                    }
+                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+                }
+                self.refresh_all();
+            }
+            ConfirmAction::Restash { index, branch, message } => {
+                match crate::git::restash(index, &branch, &message) {
+                    Ok(msg) => self.set_status(msg, MessageType::Success),
+                    Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+                }
+                self.refresh_stashes();
+                self.stash_list_state.select((!self.stashes.is_empty()).then_some(0));
+            }
+            ConfirmAction::Quit => self.should_quit = true,
+        }
+    }
+
+    pub fn cancel_confirm(&mut self) {
+        self.confirm = None;
+    }
+
+    pub fn pull_from_remote(&mut self) {
+        match crate::git::pull(false) {
+            Ok(msg) => {
+                self.set_status(msg, MessageType::Success);
+                self.refresh_status();
+                self.refresh_branches();
+            }
+            Err(e) => self.set_status(format!("Error: {}", e), MessageType::Error),
+        }
+    }
+}
+
+/// A stash that applied with conflicts still needs the user's attention
+fn stash_message_type(conflicts: bool) -> MessageType {
+    if conflicts {
+        MessageType::Error
+    } else {
+        MessageType::Success
+    }
+}
+
+/// Short hash and subject of the common ancestor of two revisions
+fn merge_base_summary(a: &str, b: &str) -> Option<(String, String)> {
+    crate::git::merge_base(a, b)
+        .and_then(|hash| crate::git::get_commit_summary(&hash))
+        .ok()
+}
+
+/// Opening lines of a merge or rebase preview naming where HEAD and
+/// `branch` diverged
+fn merge_base_details(branch: &str) -> Vec<String> {
+    merge_base_summary("HEAD", branch)
+        .map(|(hash, subject)| vec![format!("Merge base: {} {}", hash, subject), String::new()])
+        .unwrap_or_default()
+}
+
+/// A line number as a scroll offset, which stops at the last one a u16 holds
+fn clamp_scroll(line: usize) -> u16 {
+    u16::try_from(line).unwrap_or(u16::MAX)
+}
+
+/// Compares two possibly abbreviated commit hashes
+fn same_hash(a: &str, b: &str) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+/// Writes `text` to `path`, lets the user edit it in their editor and
+/// returns what they saved without `comment` lines
+fn edit_in_editor(path: &std::path::Path, text: &str, comment: &str) -> Result<String> {
+    std::fs::write(path, text).context("Failed to write the commit message file")?;
+    let editor = crate::git::get_editor()?;
+    crate::platform::run_editor(&editor, path)?;
+    let saved = std::fs::read_to_string(path).context("Failed to read the commit message file")?;
+    Ok(crate::git::strip_message_comments(&saved, comment))
+}
+
+/// Deepest directory (ending in '/', or "") containing all the paths
+pub fn common_dir<'a>(mut paths: impl Iterator<Item = &'a str>) -> String {
+    let Some(first) = paths.next() else {
+        return String::new();
+    };
+    let mut common = first.strip_suffix('/').unwrap_or(first).rfind('/').map_or("", |i| &first[..=i]);
+    for path in paths {
+        while !path.starts_with(common) {
+            common = common[..common.len() - 1].rfind('/').map_or("", |i| &common[..=i]);
+        }
+    }
+    common.to_string()
+}
+
+/// Entries directly inside `dir` ("" or ending in '/') of a recursive file
+/// listing: (name, is_dir), directories first, each group sorted
+fn dir_entries(files: &[String], dir: &str) -> Vec<(String, bool)> {
+    let mut dirs = std::collections::BTreeSet::new();
+    let mut names = std::collections::BTreeSet::new();
+    for rest in files.iter().filter_map(|path| path.strip_prefix(dir)) {
+        match rest.split_once('/') {
+            Some((sub, _)) => dirs.insert(sub.to_string()),
+            None => names.insert(rest.to_string()),
+        };
+    }
+    dirs.into_iter()
+        .map(|name| (name, true))
+        .chain(names.into_iter().map(|name| (name, false)))
+        .collect()
+}
+
+/// Whether a changed file matches the file filter: a glob (`*`, `?`) when
+/// the filter has wildcards, otherwise a case-insensitive substring
+fn matches_file_filter(filter: &str, path: &str) -> bool {
+    if filter.contains(['*', '?']) {
+        let pattern: Vec<char> = filter.chars().collect();
+        let text: Vec<char> = path.chars().collect();
+        glob_match(&pattern, &text)
+    } else {
+        path.to_lowercase().contains(&filter.to_lowercase())
+    }
+}
+
+/// Matches `*` (any run of characters, `/` included) and `?` (one
+/// character), backtracking to the last `*` on a mismatch
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Splits the new-worktree input into a path and an optional branch. Only
+/// the last word can be the branch, and only when it names a commit, so
+/// paths with spaces stay whole
+fn split_worktree_input(input: &str, is_commit: impl Fn(&str) -> bool) -> (&str, Option<&str>) {
+    let input = input.trim();
+    match input.rsplit_once(char::is_whitespace) {
+        Some((path, branch)) if is_commit(branch) => (path.trim_end(), Some(branch)),
+        _ => (input, None),
+    }
+}
+
+/// Untracked directories are reported collapsed with a trailing slash
+pub fn is_untracked_dir(file: &StatusFile) -> bool {
+    file.status == crate::git::FileStatus::Untracked && file.path.ends_with('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::FileStatus;
+
+    fn app_with_files(paths: &[&str]) -> App {
+        let mut app = App::new(Vec::new());
+        app.status_files = paths
+            .iter()
+            .map(|path| StatusFile {
+                path: path.to_string(),
+                status: FileStatus::Untracked,
+                staged: false,
+                is_submodule: false,
+            })
+            .collect();
+        // Row 0 is the "Unstaged Changes:" header
+        app.status_list_state.select(Some(1));
+        app
+    }
+
+    #[test]
+    fn test_status_diff_follows_selection() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_status_diff_follows_selection", || {
+            let mut app = app_with_files(&["first.txt", "second.txt"]);
+            app.toggle_status_diff();
+            assert_eq!(app.status_diff_path.as_deref(), Some("first.txt"));
+
+            app.status_diff_scroll = 5;
+            app.next_status_file();
+            assert_eq!(app.status_diff_path.as_deref(), Some("second.txt"));
+            assert!(app.status_diff_content.is_some());
+            assert_eq!(app.status_diff_scroll, 0);
+
+            app.previous_status_diff_file();
+            assert_eq!(app.status_diff_path.as_deref(), Some("first.txt"));
+        });
+    }
+
+    #[test]
+    fn test_status_navigation_skips_headers() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_status_navigation_skips_headers", || {
+            let mut app = app_with_files(&["a.txt", "b.txt", "c.txt"]);
+            app.status_files[0].staged = true;
+            // Rows: 0 "Staged" header, 1 a.txt, 2 "Unstaged" header, 3 b.txt, 4 c.txt
+
+            app.next_status_file();
+            assert_eq!(app.status_list_state.selected(), Some(3));
+            app.previous_status_file();
+            assert_eq!(app.status_list_state.selected(), Some(1));
+            app.previous_status_file();
+            assert_eq!(app.status_list_state.selected(), Some(4));
+            app.next_status_file();
+            assert_eq!(app.status_list_state.selected(), Some(1));
+
+            app.jump_status_section();
+            assert_eq!(app.status_list_state.selected(), Some(3));
+            app.next_status_file();
+            app.jump_status_section();
+            assert_eq!(app.status_list_state.selected(), Some(1));
+        });
+    }
+
+    #[test]
+    fn test_common_dir_and_display_path() {
+        let paths = ["src/ui/app.rs", "src/ui/render.rs", "src/ui/widgets/"];
+        assert_eq!(common_dir(paths.into_iter()), "src/ui/");
+        assert_eq!(common_dir(["src/ui/app.rs", "src/git.rs"].into_iter()), "src/");
+        assert_eq!(common_dir(["src/git.rs", "README.md"].into_iter()), "");
+        assert_eq!(common_dir(std::iter::empty()), "");
+
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_common_dir_and_display_path", || {
+            let mut app = App::new(Vec::new());
+            assert_eq!(app.display_path("src/ui/app.rs", "src/ui/"), "src/ui/app.rs");
+            app.path_display = PathDisplay::Relative;
+            assert_eq!(app.display_path("src/ui/app.rs", "src/ui/"), "app.rs");
+            app.path_display = PathDisplay::Name;
+            assert_eq!(app.display_path("src/ui/widgets/", ""), "widgets/");
+            assert_eq!(app.display_path("README.md", ""), "README.md");
+        });
+    }
+
+    #[test]
+    fn test_dir_entries() {
+        let files: Vec<String> = ["README.md", "src/main.rs", "src/ui/app.rs", "src/ui/mod.rs", "Cargo.toml"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let names = |entries: Vec<(String, bool)>| -> Vec<String> {
+            entries
+                .into_iter()
+                .map(|(name, is_dir)| if is_dir { format!("{}/", name) } else { name })
+                .collect()
+        };
+        assert_eq!(names(dir_entries(&files, "")), vec!["src/", "Cargo.toml", "README.md"]);
+        assert_eq!(names(dir_entries(&files, "src/")), vec!["ui/", "main.rs"]);
+        assert_eq!(names(dir_entries(&files, "src/ui/")), vec!["app.rs", "mod.rs"]);
+    }
+
+    #[test]
+    fn test_file_filter_matches() {
+        assert!(matches_file_filter("*.rs", "src/ui/app.rs"));
+        assert!(!matches_file_filter("*.rs", "README.md"));
+        assert!(matches_file_filter("src/*/app.?s", "src/ui/app.rs"));
+        assert!(matches_file_filter("readme", "docs/README.md"));
+        assert!(!matches_file_filter("src/*.toml", "Cargo.toml"));
+    }
+
+    #[test]
+    fn test_file_filter_narrows_file_list() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_file_filter_narrows_file_list", || {
+            let mut app = App::new(Vec::new());
+            let file = |name: &str| FileDiff {
+                filename: name.to_string(),
+                diff_content: String::new(),
+                note: None,
+                mode_change: None,
+            };
+            app.current_diff = Some(CommitDiff {
+                files: vec![file("README.md"), file("src/main.rs"), file("src/git.rs")],
+            });
+            app.file_list_state.select(Some(0));
+
+            for c in "*.rs".chars() {
+                app.add_file_filter_char(c);
+            }
+            assert_eq!(app.visible_diff_files().len(), 2);
+            app.next_file();
+            app.next_file();
+            assert_eq!(app.selected_diff_file().map(|f| f.filename.as_str()), Some("src/main.rs"));
+
+            app.clear_file_filter();
+            assert_eq!(app.visible_diff_files().len(), 3);
+            assert_eq!(app.selected_diff_file().map(|f| f.filename.as_str()), Some("src/main.rs"));
+        });
+    }
+
+    #[test]
+    fn test_collapsed_section_hides_its_files() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_collapsed_section_hides_its_files", || {
+            let mut app = app_with_files(&["a.txt", "b.txt", "c.txt"]);
+            app.status_files[0].staged = true;
+            // Rows: 0 "Staged" header, 1 a.txt, 2 "Unstaged" header, 3 b.txt, 4 c.txt
+            app.status_list_state.select(Some(3));
+
+            app.toggle_status_section();
+            assert!(app.unstaged_collapsed);
+            // Rows: 0 "Staged" header, 1 a.txt, 2 "Unstaged" header (collapsed)
+            assert_eq!(app.get_status_list_len(), 3);
+            assert_eq!(app.status_list_state.selected(), Some(2));
+            assert!(app.is_status_header_selected());
+
+            // The collapsed header stays reachable, the open one is skipped
+            app.next_status_file();
+            assert_eq!(app.status_list_state.selected(), Some(1));
+            app.previous_status_file();
+            assert_eq!(app.status_list_state.selected(), Some(2));
+
+            app.toggle_status_section();
+            assert!(!app.unstaged_collapsed);
+            assert_eq!(app.selected_status_file_index(), Some(1));
+        });
+    }
+
+    #[test]
+    fn test_status_rows_map_to_their_files() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_status_rows_map_to_their_files", || {
+            // git lists files by path, so staged and unstaged entries interleave
+            let mut app = app_with_files(&["a.txt", "b.txt", "c.txt", "d.txt"]);
+            app.status_files[1].staged = true;
+            app.status_files[3].staged = true;
+            // Rows: 0 "Staged" header, 1 b.txt, 2 d.txt, 3 "Unstaged" header, 4 a.txt, 5 c.txt
+
+            let rows: Vec<Option<&str>> = (0..7)
+                .map(|row| app.list_index_to_file_index(row).map(|i| app.status_files[i].path.as_str()))
+                .collect();
+            assert_eq!(
+                rows,
+                vec![None, Some("b.txt"), Some("d.txt"), None, Some("a.txt"), Some("c.txt"), None]
+            );
+        });
+    }
+
+    #[test]
+    fn test_status_selection_restored_after_rebuild() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_status_selection_restored_after_rebuild", || {
+            let mut app = app_with_files(&["a.txt", "b.txt", "c.txt"]);
+            app.status_files[0].staged = true;
+            // Rows: 0 "Staged" header, 1 a.txt, 2 "Unstaged" header, 3 b.txt, 4 c.txt
+
+            // Unstaging the only staged file drops the staged section
+            app.status_files[0].staged = false;
+            app.restore_status_selection(1);
+            assert_eq!(app.status_list_state.selected(), Some(1));
+
+            // A row that became a header moves on to the next file
+            app.status_files[2].staged = true;
+            // Rows: 0 "Staged" header, 1 c.txt, 2 "Unstaged" header, 3 a.txt, 4 b.txt
+            app.restore_status_selection(2);
+            assert_eq!(app.status_list_state.selected(), Some(3));
+
+            app.restore_status_selection(9);
+            assert_eq!(app.status_list_state.selected(), Some(4));
+        });
+    }
+
+    #[test]
+    fn test_status_selection_never_rests_on_header() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_status_selection_never_rests_on_header", || {
+            let mut app = app_with_files(&["a.txt"]);
+            app.status_list_state.select(None);
+
+            app.next_status_file();
+            assert_eq!(app.selected_status_file_index(), Some(0));
+            app.next_status_file();
+            assert_eq!(app.selected_status_file_index(), Some(0));
+            app.previous_status_file();
+            assert_eq!(app.selected_status_file_index(), Some(0));
+        });
+    }
+
+    #[test]
+    fn test_failed_commit_message_is_restored() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_failed_commit_message_is_restored", || {
+            let mut app = App::new(Vec::new());
+            app.enter_commit_message_mode();
+            "Fix the parser".chars().for_each(|c| app.add_commit_char(c));
+
+            // Nothing is staged and empty commits aren't allowed
+            app.execute_commit();
+            assert_eq!(app.status_message_type, MessageType::Error);
+            assert_eq!(app.commit_message_input, "Fix the parser");
+
+            app.exit_commit_message_mode();
+            app.commit_message_input.clear();
+            app.enter_commit_message_mode();
+            assert_eq!(app.commit_message_input, "Fix the parser");
+            assert_eq!(crate::test_repo::git(&["rev-list", "--count", "HEAD"]), "1");
+        });
+    }
+
+    #[test]
+    fn test_split_worktree_input() {
+        let is_commit = |rev: &str| rev == "main";
+        assert_eq!(split_worktree_input("../wt", is_commit), ("../wt", None));
+        assert_eq!(split_worktree_input("../wt main", is_commit), ("../wt", Some("main")));
+        assert_eq!(split_worktree_input(" ../my wt ", is_commit), ("../my wt", None));
+        assert_eq!(split_worktree_input("../my wt  main", is_commit), ("../my wt", Some("main")));
+        assert_eq!(split_worktree_input("   ", is_commit), ("", None));
+    }
+
+    #[test]
+    fn test_quit_confirms_only_while_busy() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_quit_confirms_only_while_busy", || {
+            let mut app = App::new(Vec::new());
+            app.quit();
+            assert!(app.should_quit);
+            assert!(app.confirm.is_none());
+
+            let mut app = App::new(Vec::new());
+            app.operation_in_flight = true;
+            app.quit();
+            assert!(!app.should_quit);
+            assert!(matches!(app.confirm, Some(ConfirmPrompt { action: ConfirmAction::Quit, .. })));
+            app.cancel_confirm();
+            assert!(!app.should_quit);
+
+            app.quit();
+            app.accept_confirm();
+            assert!(app.should_quit);
+        });
+    }
+
+    #[test]
+    fn test_untracked_dirs_collapse_with_a_count() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_untracked_dirs_collapse_with_a_count", || {
+            for path in ["new/a.txt", "new/sub/b.txt", "new/skip.log", "other/c.txt"] {
+                std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap()).unwrap();
+                std::fs::write(path, "x\n").unwrap();
+            }
+            std::fs::write(".gitignore", "*.log\n").unwrap();
+
+            let mut app = App::new(Vec::new());
+            let paths = |app: &App| app.status_files.iter().map(|f| f.path.clone()).collect::<Vec<_>>();
+            assert_eq!(paths(&app), vec![".gitignore", "new/", "other/"]);
+            assert_eq!(app.untracked_dir_counts["new/"], 2);
+            assert_eq!(app.untracked_dir_counts["other/"], 1);
+
+            // Rows: 0 "Unstaged" header, 1 .gitignore, 2 new/, 3 other/
+            app.status_list_state.select(Some(2));
+            app.toggle_untracked_dir();
+            assert_eq!(paths(&app), vec![".gitignore", "new/", "new/a.txt", "new/sub/b.txt", "other/"]);
+
+            // Stays open across a refresh
+            app.refresh_status();
+            assert_eq!(paths(&app), vec![".gitignore", "new/", "new/a.txt", "new/sub/b.txt", "other/"]);
+
+            app.status_list_state.select(Some(2));
+            app.toggle_untracked_dir();
+            assert_eq!(paths(&app), vec![".gitignore", "new/", "other/"]);
+        });
+    }
+
+    #[test]
+    fn test_upstream_state_marks_log_rows() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_upstream_state_marks_log_rows", || {
+            use crate::test_repo::git;
+            git(&["commit", "-q", "--allow-empty", "-m", "Shared"]);
+            git(&["branch", "-q", "up"]);
+            git(&["commit", "-q", "--allow-empty", "-m", "Local"]);
+            git(&["switch", "-q", "up"]);
+            git(&["commit", "-q", "--allow-empty", "-m", "Remote"]);
+            git(&["switch", "-q", "main"]);
+            git(&["branch", "-q", "--set-upstream-to=up"]);
+
+            let app = App::new(get_commits(None, false, MergeFilter::All).unwrap());
+            let state = |subject: &str| {
+                let commit = app.commits.iter().find(|c| c.message.ends_with(subject)).unwrap();
+                app.upstream_state(commit)
+            };
+            assert_eq!(state("Local"), Some(UpstreamState::Unpushed));
+            assert_eq!(state("Shared"), Some(UpstreamState::ForkPoint));
+            assert_eq!(state("Initial"), None);
+        });
+    }
+
+    #[test]
+    fn test_in_progress_guidance_keeps_config_error() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_in_progress_guidance_keeps_config_error", || {
+            use crate::test_repo::git;
+            let commit = |text: &str| {
+                std::fs::write("a.txt", text).unwrap();
+                git(&["commit", "-q", "-am", text]);
+            };
+            std::fs::write("a.txt", "base\n").unwrap();
+            git(&["add", "a.txt"]);
+            git(&["commit", "-q", "-m", "Base"]);
+            git(&["switch", "-q", "-c", "side"]);
+            commit("side\n");
+            git(&["switch", "-q", "main"]);
+            commit("main\n");
+            let merge = std::process::Command::new("git").args(["merge", "-q", "side"]).output().unwrap();
+            assert!(!merge.status.success());
+            std::fs::write(".gitu.toml", "default_panel = \"nowhere\"\n").unwrap();
+
+            let app = App::new(Vec::new());
+            let message = app.status_message.unwrap();
+            assert!(message.starts_with("Config error: unknown default_panel 'nowhere'. "), "{}", message);
+            assert!(message.contains("MERGING in progress: 1 conflicted file(s)"), "{}", message);
+            assert_eq!(app.status_message_type, MessageType::Error);
+        });
+    }
+
+    #[test]
+    fn test_worktree_diff_on_clean_tree() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_worktree_diff_on_clean_tree", || {
+            let mut app = App::new(crate::git::get_commits(None, false, Default::default()).unwrap());
+            app.list_state.select(Some(0));
+            app.toggle_worktree_diff().unwrap();
+
+            assert!(!app.show_diff);
+            assert!(app.status_message.unwrap().starts_with("Working tree matches"));
+        });
+    }
+
+    #[test]
+    fn test_panel_cycling_wraps() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_panel_cycling_wraps", || {
+            let mut app = App::new(Vec::new());
+            app.switch_to_panel(Panel::Tags);
+            app.next_panel();
+            assert_eq!(app.current_panel, Panel::Status);
+            app.previous_panel();
+            assert_eq!(app.current_panel, Panel::Tags);
+            app.previous_panel();
+            assert_eq!(app.current_panel, Panel::Branches);
+        });
+    }
+
+    #[test]
+    fn test_parent_and_child_navigation() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_parent_and_child_navigation", || {
+            let commit = |hash: &str, parents: &[&str]| Commit {
+                graph: String::new(),
+                hash: hash.to_string(),
+                parents: parents.iter().map(|p| p.to_string()).collect(),
+                message: String::new(),
+                decorations: Vec::new(),
+                date: String::new(),
+            };
+            let mut app = App::new(Vec::new());
+            app.commits = vec![
+                commit("aaa1111", &["bbb2222", "ccc3333"]),
+                commit("ccc3333", &["ddd4444"]),
+                commit("bbb2222", &["ddd4444"]),
+                commit("ddd4444", &[]),
+            ];
+            app.list_state.select(Some(0));
+
+            app.select_parent_commit();
+            assert_eq!(app.list_state.selected(), Some(2));
+            app.select_parent_commit();
+            assert_eq!(app.list_state.selected(), Some(3));
+
+            // Both bbb and ccc are children; the nearest one above wins
+            app.select_child_commit();
+            assert_eq!(app.list_state.selected(), Some(2));
+            app.select_child_commit();
+            assert_eq!(app.list_state.selected(), Some(0));
+        });
+    }
+
+    #[test]
+    fn test_marked_range_follows_selection() {
+        crate::test_repo::in_scratch_repo("ui::app::tests::test_marked_range_follows_selection", || {
+            let commit = |hash: &str| Commit {
+                graph: String::new(),
+                hash: hash.to_string(),
+                parents: Vec::new(),
+                message: String::new(),
+                decorations: Vec::new(),
+                date: String::new(),
+            };
+            let mut app = App::new(Vec::new());
+            app.commits = vec![commit("aaa1111"), commit("bbb2222"), commit("ccc3333")];
+            app.list_state.select(Some(2));
+
+            app.toggle_range_mark();
+            assert_eq!(app.marked_range(), Some((2, 2)));
+            app.list_state.select(Some(0));
+            assert_eq!(app.marked_range(), Some((0, 2)));
+
+            app.toggle_range_mark();
+            assert_eq!(app.marked_range(), None);
+        });
+    }
+}
@@ -1,5 +1,5 @@
 mod app;
 mod render;
 
-pub use app::{App, MessageType, Panel};
+pub use app::{common_dir, App, MessageType, Panel, PathDisplay, UpstreamState};
 pub use render::ui;
@@ -1,18 +1,24 @@
-use super::{App, MessageType, Panel};
-use crate::git::{Branch, Decoration, SearchFilter, StatusFile};
+use super::{common_dir, App, MessageType, Panel, PathDisplay, UpstreamState};
+use crate::git::{Branch, Commit, Decoration, MergeFilter, SearchFilter, StatusFile, TagSignature};
 use crate::syntax;
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    buffer::Buffer,
+    widgets::{
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState,
+        Wrap,
+    },
     Frame,
 };
+use std::borrow::Cow;
 
 pub fn ui(f: &mut Frame, app: &mut App) {
     // Calculate constraints based on what needs to be shown
-    let has_status_msg = app.status_message.is_some();
-    let has_input = app.search_mode || app.branch_input_mode || app.commit_message_mode || app.stash_input_mode || app.new_branch_input_mode;
+    // The quick commit prompt takes over the status line
+    let has_status_msg = app.status_message.is_some() || app.quick_commit_input.is_some();
+    let has_input = app.search_mode || app.branch_input_mode || app.commit_message_mode || app.stash_input_mode || app.new_branch_input_mode || app.tag_input_mode || app.goto_input_mode || app.worktree_input_mode || app.amend_metadata.is_some() || app.remote_url_input_mode || app.rename_source.is_some() || app.file_filter_input_mode;
 
     let mut constraints = vec![];
     if has_status_msg {
@@ -21,7 +27,12 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     constraints.push(Constraint::Length(1)); // Tab bar
     constraints.push(Constraint::Min(3));    // Main content
     if has_input {
-        constraints.push(Constraint::Length(3)); // Input prompt
+        let summary_height = if app.commit_message_mode && app.reword_target.is_none() {
+            staged_summary_height(app)
+        } else {
+            0
+        };
+        constraints.push(Constraint::Length(3 + summary_height)); // Input prompt
     }
 
     let root_chunks = Layout::default()
@@ -63,6 +74,7 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         Panel::Log => render_log_panel(f, app, main_area),
         Panel::Stash => render_stash_panel(f, app, main_area),
         Panel::Branches => render_branches_panel(f, app, main_area),
+        Panel::Tags => render_tags_panel(f, app, main_area),
     }
 
     // Render input prompts
@@ -77,13 +89,141 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             render_stash_input(f, app, input_rect);
         } else if app.new_branch_input_mode {
             render_new_branch_input(f, app, input_rect);
+        } else if app.tag_input_mode {
+            render_tag_input(f, app, input_rect);
+        } else if app.goto_input_mode {
+            render_goto_input(f, app, input_rect);
+        } else if app.worktree_input_mode {
+            render_worktree_input(f, app, input_rect);
+        } else if app.amend_metadata.is_some() {
+            render_amend_metadata_input(f, app, input_rect);
+        } else if app.remote_url_input_mode {
+            render_remote_url_input(f, app, input_rect);
+        } else if app.rename_source.is_some() {
+            render_rename_input(f, app, input_rect);
+        } else if app.file_filter_input_mode {
+            render_file_filter_input(f, app, input_rect);
         }
     }
 
+    if app.interactive_staging.is_some() {
+        render_interactive_staging(f, app);
+    }
+
+    if app.line_staging.is_some() {
+        render_line_staging(f, app);
+    }
+
+    if app.reflog_branch.is_some() {
+        render_reflog_popup(f, app);
+    }
+
+    if app.sync_upstream.is_some() {
+        render_sync_popup(f, app);
+    }
+
+    if app.branch_compare.is_some() {
+        render_branch_compare_popup(f, app);
+    }
+
+    if app.worktrees_visible {
+        render_worktrees_popup(f, app);
+    }
+
+    if app.remotes_visible {
+        render_remotes_popup(f, app);
+    }
+
+    if app.stash_target_visible {
+        render_stash_target_popup(f, app);
+    }
+
+    if app.confirm.is_some() {
+        render_confirm_popup(f, app);
+    }
+
+    if app.discard_all_input.is_some() {
+        render_discard_all_popup(f, app);
+    }
+
     // Render help popup overlay (on top of everything)
     if app.help_visible {
-        render_help_popup(f);
+        render_help_popup(f, app);
+    }
+
+    if app.no_color {
+        strip_colors(f.buffer_mut());
+    }
+}
+
+/// Removes all colors from the rendered buffer for monochrome output.
+/// Cells that relied on a background color (selection, pills, banners)
+/// are shown in reverse video instead so they stay distinguishable.
+fn strip_colors(buffer: &mut Buffer) {
+    for cell in buffer.content.iter_mut() {
+        if cell.bg != Color::Reset {
+            cell.modifier |= Modifier::REVERSED;
+        }
+        cell.set_fg(Color::Reset);
+        cell.set_bg(Color::Reset);
+    }
+}
+
+/// Builds the display lines for a diff, honoring the color setting
+fn diff_lines(app: &App, diff_content: &str, filename: &str) -> Vec<Line<'static>> {
+    let limit = app.config.max_diff_line_length();
+    let (diff_content, cut) = if limit > 0 && !app.expand_long_lines {
+        syntax::truncate_long_lines(diff_content, limit)
+    } else {
+        (diff_content.to_string(), Vec::new())
+    };
+    let diff_content = syntax::expand_diff_tabs(&diff_content, app.tab_width);
+
+    let mut lines = if app.no_color {
+        syntax::plain_diff(&diff_content)
+    } else if app.simple_diff_colors {
+        syntax::simple_diff(&diff_content)
+    } else {
+        syntax::highlight_diff(&diff_content, filename, &app.theme)
+    };
+
+    for (i, hidden) in cut {
+        if let Some(line) = lines.get_mut(i) {
+            line.spans.push(Span::styled(
+                format!(" … +{} chars (E: expand)", hidden),
+                Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC | Modifier::REVERSED),
+            ));
+        }
+    }
+    lines
+}
+
+/// Draws a thin scrollbar over the right border of a bordered `area` when
+/// its `total` rows don't fit; `position` is the first visible row
+fn render_scrollbar(f: &mut Frame, area: Rect, total: usize, position: usize) {
+    let visible = area.height.saturating_sub(2) as usize;
+    if total <= visible {
+        return;
     }
+
+    let mut state = ScrollbarState::new(total - visible + 1)
+        .position(position)
+        .viewport_content_length(visible);
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .track_symbol(None)
+        .thumb_symbol("┃")
+        .thumb_style(Style::default().fg(Color::DarkGray));
+    f.render_stateful_widget(scrollbar, area.inner(Margin { vertical: 1, horizontal: 0 }), &mut state);
+}
+
+/// Explains why a file diff has no hunks (mode change, binary file, ...)
+fn diff_note_line(note: &str) -> Line<'static> {
+    Line::from(Span::styled(
+        note.to_string(),
+        Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+    ))
 }
 
 fn render_tab_bar(f: &mut Frame, app: &App, area: Rect) {
@@ -92,6 +232,7 @@ fn render_tab_bar(f: &mut Frame, app: &App, area: Rect) {
         ("[2] Log", Panel::Log),
         ("[3] Stash", Panel::Stash),
         ("[4] Branches", Panel::Branches),
+        ("[5] Tags", Panel::Tags),
     ];
 
     let mut spans = Vec::new();
@@ -112,10 +253,44 @@ fn render_tab_bar(f: &mut Frame, app: &App, area: Rect) {
         spans.push(Span::styled(label.to_string(), style));
     }
 
+    // What new work builds on, flagged when HEAD is detached
+    if let Some((ref hash, ref subject)) = app.head {
+        spans.push(Span::styled("   HEAD ", Style::default().fg(Color::DarkGray)));
+        match app.head_branch {
+            Some(ref branch) => spans.push(Span::styled(format!("{} ", branch), Style::default().fg(Color::Green))),
+            None => spans.push(Span::styled(
+                "(detached) ",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )),
+        }
+        spans.push(Span::styled(format!("{} ", hash), Style::default().fg(Color::Yellow)));
+        spans.push(Span::styled(subject.clone(), Style::default().fg(Color::Gray)));
+    }
+
+    if let Some(op) = app.in_progress {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(
+            format!(" {} ", op.label()),
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
     let line = Line::from(spans);
     f.render_widget(Paragraph::new(line), area);
 }
 
+/// Status section header; the count covers files hidden by collapsing
+fn section_header(title: &str, count: usize, collapsed: bool) -> String {
+    if collapsed {
+        format!("▸ {} ({}, collapsed)", title, count)
+    } else {
+        format!("{} ({}):", title, count)
+    }
+}
+
 fn render_status_panel(f: &mut Frame, app: &mut App, area: Rect) {
     // Split area if showing diff
     let chunks = if app.status_show_diff {
@@ -133,84 +308,125 @@ fn render_status_panel(f: &mut Frame, app: &mut App, area: Rect) {
     let (staged, unstaged): (Vec<&StatusFile>, Vec<&StatusFile>) =
         app.status_files.iter().partition(|f| f.staged);
 
-    // Build a mapping from list index to file index (accounting for headers)
-    let mut index_to_file: Vec<usize> = Vec::new();
-
+    let common = common_dir(app.status_files.iter().map(|f| f.path.as_str()));
     let items: Vec<ListItem> = {
         let mut items = Vec::new();
-        let mut file_idx = 0;
 
         if !staged.is_empty() {
             items.push(ListItem::new(Line::from(Span::styled(
-                "Staged Changes:",
+                section_header("Staged Changes", staged.len(), app.staged_collapsed),
                 Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
             ))));
-            index_to_file.push(usize::MAX); // Header, no file mapping
 
-            for file in &staged {
-                index_to_file.push(file_idx);
-                file_idx += 1;
+            for file in staged.iter().filter(|_| !app.staged_collapsed) {
                 let status_char = match file.status {
                     crate::git::FileStatus::Modified => "M",
                     crate::git::FileStatus::Added => "A",
                     crate::git::FileStatus::Deleted => "D",
                     crate::git::FileStatus::Renamed => "R",
                     crate::git::FileStatus::Untracked => "?",
+                    crate::git::FileStatus::Conflicted => "U",
                 };
 
-                items.push(ListItem::new(Line::from(vec![
+                let mut spans = vec![
                     Span::styled(
                         format!("[{}] ", status_char),
                         Style::default().fg(Color::Green),
                     ),
-                    Span::raw(&file.path),
-                ])));
+                    Span::raw(app.display_path(&file.path, &common)),
+                ];
+                if file.is_submodule {
+                    spans.push(submodule_marker());
+                }
+
+                items.push(ListItem::new(Line::from(spans)));
             }
         }
 
         if !unstaged.is_empty() {
             items.push(ListItem::new(Line::from(Span::styled(
-                "Unstaged Changes:",
+                section_header("Unstaged Changes", unstaged.len(), app.unstaged_collapsed),
                 Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
             ))));
-            index_to_file.push(usize::MAX); // Header, no file mapping
 
-            for file in &unstaged {
-                index_to_file.push(file_idx);
-                file_idx += 1;
+            for file in unstaged.iter().filter(|_| !app.unstaged_collapsed) {
                 let status_char = match file.status {
                     crate::git::FileStatus::Modified => "M",
                     crate::git::FileStatus::Added => "A",
                     crate::git::FileStatus::Deleted => "D",
                     crate::git::FileStatus::Renamed => "R",
                     crate::git::FileStatus::Untracked => "?",
+                    crate::git::FileStatus::Conflicted => "U",
                 };
 
-                items.push(ListItem::new(Line::from(vec![
-                    Span::styled(
-                        format!("[{}] ", status_char),
-                        Style::default().fg(Color::Red),
-                    ),
-                    Span::raw(&file.path),
-                ])));
+                let style = if file.status == crate::git::FileStatus::Conflicted {
+                    Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Red)
+                };
+                let mut spans = vec![Span::styled(format!("[{}] ", status_char), style)];
+
+                if let Some(count) = app.untracked_dir_counts.get(&file.path) {
+                    let marker = if app.expanded_untracked_dirs.contains(&file.path) { "▾ " } else { "▸ " };
+                    spans.push(Span::raw(marker));
+                    spans.push(Span::raw(app.display_path(&file.path, &common)));
+                    spans.push(Span::styled(
+                        format!(" ({} files)", count),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                } else if file.status == crate::git::FileStatus::Untracked
+                    && app.expanded_untracked_dirs.iter().any(|dir| file.path.starts_with(dir.as_str()))
+                {
+                    spans.push(Span::raw("  "));
+                    spans.push(Span::raw(app.display_path(&file.path, &common)));
+                } else {
+                    spans.push(Span::raw(app.display_path(&file.path, &common)));
+                }
+                if file.is_submodule {
+                    spans.push(submodule_marker());
+                }
+
+                items.push(ListItem::new(Line::from(spans)));
             }
         }
 
         if items.is_empty() {
             items.push(ListItem::new("No changes"));
-            index_to_file.push(usize::MAX); // No file
         }
 
         items
     };
 
-    let title = format!(" Status ({} files) ", app.status_files.len());
+    let untracked = unstaged
+        .iter()
+        .filter(|f| f.status == crate::git::FileStatus::Untracked)
+        .count();
+    let title = format!(
+        " Status ({} staged, {} unstaged, {} untracked) {}",
+        staged.len(),
+        unstaged.len() - untracked,
+        untracked,
+        path_display_tag(app, &common)
+    );
+    let keys = &app.keymap;
     let help = if app.status_show_diff {
-        " j/k: Scroll | PgUp/PgDn: Page | Enter: Hide diff | Space: Stage/Unstage "
+        format!(
+            " j/k: Scroll | h/l: Switch file | PgUp/PgDn: Page | Enter: Hide diff | {}: Stage/Unstage ",
+            keys.hint("status.stage")
+        )
     } else {
-        " Space: Stage/Unstage | a/u: Stage/Unstage all | c: Commit | A: Amend | x: Discard | ?: Help "
+        format!(
+            " {}: Stage/Unstage | {}/{}: Stage/Unstage all | {}: Commit | {}: Amend | {}: Discard | ?: Help ",
+            keys.hint("status.stage"),
+            keys.hint("status.stage_all"),
+            keys.hint("status.unstage_all"),
+            keys.hint("status.commit"),
+            keys.hint("status.amend"),
+            keys.hint("status.discard")
+        )
     };
 
+    let total = items.len();
     let list = List::new(items)
         .block(
             Block::default()
@@ -226,52 +442,78 @@ fn render_status_panel(f: &mut Frame, app: &mut App, area: Rect) {
         .highlight_symbol(">> ");
 
     f.render_stateful_widget(list, chunks[0], &mut app.status_list_state);
+    render_scrollbar(f, chunks[0], total, app.status_list_state.offset());
 
     // Render diff if showing
     if app.status_show_diff && chunks.len() > 1 {
         if let Some(ref diff_content) = app.status_diff_content {
-            // Map list index to file index using the mapping we built
-            let selected_file = app.status_list_state.selected()
-                .and_then(|list_idx| index_to_file.get(list_idx))
-                .and_then(|&file_idx| {
-                    if file_idx == usize::MAX {
-                        None // This was a header, not a file
-                    } else {
-                        app.status_files.get(file_idx)
-                    }
-                });
-
-            let filename = selected_file
-                .map(|f| f.path.as_str())
-                .unwrap_or("unknown");
+            let filename = app.status_diff_path.as_deref().unwrap_or("unknown");
 
-            let lines = crate::syntax::highlight_diff(diff_content, filename);
+            // Conflicted files were highlighted when loaded
+            let (lines, title) = match app.status_conflicts {
+                Some(ref conflicts) => (
+                    Cow::Borrowed(app.status_conflict_lines.as_slice()),
+                    format!(" Conflicts: {} ({}) ", filename, conflicts.len()),
+                ),
+                None => (
+                    Cow::Owned(diff_lines(app, diff_content, filename)),
+                    format!(" Diff: {} {}", filename, whitespace_tag(app)),
+                ),
+            };
+            let total = lines.len();
 
             let visible_lines: Vec<Line> = lines
-                .into_iter()
+                .iter()
                 .skip(app.status_diff_scroll as usize)
+                .cloned()
                 .collect();
 
             let paragraph = Paragraph::new(visible_lines)
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
-                        .title(format!(" Diff: {} ", filename)),
+                        .title(title),
                 )
                 .wrap(ratatui::widgets::Wrap { trim: false });
 
             f.render_widget(paragraph, chunks[1]);
+            render_scrollbar(f, chunks[1], total, app.status_diff_scroll as usize);
         }
     }
 }
 
+/// "[in src/ui/] " while paths are shown below their shared directory
+fn path_display_tag(app: &App, common: &str) -> String {
+    if app.path_display == PathDisplay::Relative && !common.is_empty() {
+        format!("[in {}] ", common)
+    } else {
+        String::new()
+    }
+}
+
+/// Diff title tag while whitespace changes are hidden
+fn whitespace_tag(app: &App) -> &'static str {
+    if app.ignore_whitespace {
+        "[-w] "
+    } else {
+        ""
+    }
+}
+
+fn submodule_marker() -> Span<'static> {
+    Span::styled(" (submodule)", Style::default().fg(Color::Magenta).add_modifier(Modifier::ITALIC))
+}
+
 fn render_log_panel(f: &mut Frame, app: &mut App, area: Rect) {
     // Split based on view mode
-    let chunks = if app.tree_view_mode {
+    let chunks = if app.tree_view_mode || app.browse_hash.is_some() {
         Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
             .split(area)
+    } else if app.show_diff && app.diff_fullscreen {
+        render_diff(f, app, area);
+        return;
     } else if app.show_diff {
         Layout::default()
             .direction(Direction::Horizontal)
@@ -290,19 +532,52 @@ fn render_log_panel(f: &mut Frame, app: &mut App, area: Rect) {
 
     render_commit_list(f, app, chunks[0]);
 
-    if app.tree_view_mode && chunks.len() >= 2 {
+    if app.browse_hash.is_some() && chunks.len() >= 2 {
+        if app.browse_file.is_some() {
+            render_browse_file(f, app, chunks[1]);
+        } else {
+            render_browse_entries(f, app, chunks[1]);
+        }
+    } else if app.tree_view_mode && chunks.len() >= 2 {
         if app.tree_file_selected {
             render_tree_file_diff(f, app, chunks[1]);
         } else {
             render_tree_file_list(f, app, chunks[1]);
         }
     } else if app.show_diff && chunks.len() >= 3 {
-        render_file_list(f, app, chunks[1]);
+        if let Some(ref notes) = app.commit_notes {
+            // Notes sit under the file list, capped so the list stays usable
+            let height = (notes.lines().count() as u16 + 2).min(chunks[1].height / 2);
+            let split = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(height)])
+                .split(chunks[1]);
+            render_file_list(f, app, split[0]);
+            render_commit_notes(f, app, split[1]);
+        } else {
+            render_file_list(f, app, chunks[1]);
+        }
         render_diff(f, app, chunks[2]);
     }
 }
 
 fn render_stash_panel(f: &mut Frame, app: &mut App, area: Rect) {
+    let area = if app.stash_diff.is_some() {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(30),
+                Constraint::Percentage(25),
+                Constraint::Percentage(45),
+            ])
+            .split(area);
+        render_stash_files(f, app, chunks[1]);
+        render_stash_diff(f, app, chunks[2]);
+        chunks[0]
+    } else {
+        area
+    };
+
     let items: Vec<ListItem> = app
         .stashes
         .iter()
@@ -327,9 +602,164 @@ fn render_stash_panel(f: &mut Frame, app: &mut App, area: Rect) {
         items
     };
 
-    let title = format!(" Stashes ({}) ", app.stashes.len());
-    let help = " a: Apply | p: Pop | d: Drop | q: Quit ";
+    let title = format!(
+        " Stashes ({}) {}",
+        app.stashes.len(),
+        if app.stash_restore_index { "[--index] " } else { "" }
+    );
+    let keys = &app.keymap;
+    let help = format!(
+        " Enter: Diff | {}: Apply | {}: Pop | {}: Drop | {}: Apply on branch | {}: Rename | q: Quit ",
+        keys.hint("stash.apply"),
+        keys.hint("stash.pop"),
+        keys.hint("stash.drop"),
+        keys.hint("stash.apply_on_branch"),
+        keys.hint("stash.rename")
+    );
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(help);
+
+    // Quick size of the selected stash without opening its diff
+    if let Some(ref stat) = app.stash_stat {
+        if !stat.is_empty() {
+            block = block.title_bottom(
+                Line::from(Span::styled(format!(" {} ", stat), Style::default().fg(Color::Gray))).right_aligned(),
+            );
+        }
+    }
+
+    let total = items.len();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    f.render_stateful_widget(list, area, &mut app.stash_list_state);
+    render_scrollbar(f, area, total, app.stash_list_state.offset());
+}
+
+fn render_stash_files(f: &mut Frame, app: &mut App, area: Rect) {
+    let Some(ref diff) = app.stash_diff else {
+        return;
+    };
+
+    let common = common_dir(diff.files.iter().map(|file| file.filename.as_str()));
+    let items: Vec<ListItem> = diff
+        .files
+        .iter()
+        .map(|file| ListItem::new(Line::from(Span::raw(app.display_path(&file.filename, &common).to_string()))))
+        .collect();
+    let title = format!(" Files ({}) {}", items.len(), path_display_tag(app, &common));
+
+    let total = items.len();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(" ←/→: Switch File "),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    f.render_stateful_widget(list, area, &mut app.stash_file_state);
+    render_scrollbar(f, area, total, app.stash_file_state.offset());
+}
+
+fn render_stash_diff(f: &mut Frame, app: &App, area: Rect) {
+    let file_diff = app.selected_stash_file();
+    let diff_content = file_diff.map(|f| f.diff_content.as_str()).unwrap_or("");
+    let filename = file_diff.map(|f| f.filename.as_str()).unwrap_or("");
+
+    let mut lines = diff_lines(app, diff_content, filename);
+    if let Some(note) = file_diff.and_then(|f| f.note.as_deref()) {
+        lines.insert(0, diff_note_line(note));
+    } else if let Some((old, new)) = file_diff.and_then(|f| f.mode_change.as_ref()) {
+        lines.insert(0, diff_note_line(&format!("mode: {} → {}", old, new)));
+    }
+
+    let total = lines.len();
+    let visible: Vec<Line> = lines.into_iter().skip(app.stash_diff_scroll as usize).collect();
+    let paragraph = Paragraph::new(visible)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {} {}", filename, whitespace_tag(app)))
+                .title_bottom(" ↑/↓: Scroll | Enter/ESC: Close "),
+        )
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(paragraph, area);
+    render_scrollbar(f, area, total, app.stash_diff_scroll as usize);
+}
+
+fn render_tags_panel(f: &mut Frame, app: &mut App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .tags
+        .iter()
+        .map(|tag| {
+            let mut spans = vec![
+                Span::styled(&tag.name, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                Span::raw(" "),
+                Span::styled(&tag.target, Style::default().fg(Color::Magenta)),
+            ];
+            match tag.signature {
+                TagSignature::Unsigned => {}
+                TagSignature::Unchecked => {
+                    spans.push(Span::styled(" [signed]", Style::default().fg(Color::DarkGray)));
+                }
+                TagSignature::Good(ref signer) => {
+                    let label = if signer.is_empty() {
+                        " [✓ signed]".to_string()
+                    } else {
+                        format!(" [✓ {}]", signer)
+                    };
+                    spans.push(Span::styled(label, Style::default().fg(Color::Green)));
+                }
+                TagSignature::Bad => {
+                    spans.push(Span::styled(" [✗ bad signature]", Style::default().fg(Color::Red)));
+                }
+                TagSignature::Unverifiable(ref reason) => {
+                    spans.push(Span::styled(format!(" [? {}]", reason), Style::default().fg(Color::Yellow)));
+                }
+            }
+            if !tag.subject.is_empty() {
+                spans.push(Span::raw(" "));
+                spans.push(Span::styled(&tag.subject, Style::default().fg(Color::Gray)));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let items = if items.is_empty() {
+        vec![ListItem::new("No tags")]
+    } else {
+        items
+    };
 
+    let title = format!(" Tags ({}) ", app.tags.len());
+    let keys = &app.keymap;
+    let help = format!(
+        " Enter/{}: Checkout | {}: New | {}: Delete | {}: Push | {}: Push all | ?: Help ",
+        keys.hint("tags.checkout"),
+        keys.hint("tags.new"),
+        keys.hint("tags.delete"),
+        keys.hint("tags.push"),
+        keys.hint("tags.push_all")
+    );
+
+    let total = items.len();
     let list = List::new(items)
         .block(
             Block::default()
@@ -344,7 +774,8 @@ fn render_stash_panel(f: &mut Frame, app: &mut App, area: Rect) {
         )
         .highlight_symbol(">> ");
 
-    f.render_stateful_widget(list, area, &mut app.stash_list_state);
+    f.render_stateful_widget(list, area, &mut app.tag_list_state);
+    render_scrollbar(f, area, total, app.tag_list_state.offset());
 }
 
 fn render_branches_panel(f: &mut Frame, app: &mut App, area: Rect) {
@@ -426,15 +857,38 @@ fn render_branches_panel(f: &mut Frame, app: &mut App, area: Rect) {
     };
 
     let title = format!(" Branches ({}) ", app.branches.len());
-    let help = " Enter: Switch | d: Delete | n: New | m: Merge | ?: Help ";
+    let keys = &app.keymap;
+    let help = format!(
+        " Enter: Switch | {}: Delete | {}: New | {}: Merge | {}: Rebase | {}: Reflog | {}: Compare | {}: Previous | ?: Help ",
+        keys.hint("branches.delete"),
+        keys.hint("branches.new"),
+        keys.hint("branches.merge"),
+        keys.hint("branches.rebase"),
+        keys.hint("branches.reflog"),
+        keys.hint("branches.compare"),
+        keys.hint("branches.switch_previous")
+    );
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(help);
+
+    // Show where the selected branch diverged from HEAD
+    if let Some((ref hash, ref subject)) = app.branch_merge_base {
+        block = block.title(
+            Line::from(vec![
+                Span::raw(" merge-base: "),
+                Span::styled(hash.clone(), Style::default().fg(Color::Yellow)),
+                Span::raw(format!(" {} ", subject)),
+            ])
+            .right_aligned(),
+        );
+    }
 
+    let total = items.len();
     let list = List::new(items)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(title)
-                .title_bottom(help),
-        )
+        .block(block)
         .highlight_style(
             Style::default()
                 .bg(Color::DarkGray)
@@ -443,51 +897,105 @@ fn render_branches_panel(f: &mut Frame, app: &mut App, area: Rect) {
         .highlight_symbol(">> ");
 
     f.render_stateful_widget(list, area, &mut app.branch_list_state);
+    render_scrollbar(f, area, total, app.branch_list_state.offset());
 }
 
 fn render_commit_list(f: &mut Frame, app: &mut App, area: Rect) {
-    let items: Vec<ListItem> = app
-        .commits
-        .iter()
-        .map(|commit| {
-            let mut spans = vec![
-                Span::styled(&commit.graph, Style::default().fg(Color::Cyan)),
-                Span::styled(&commit.hash, Style::default().fg(Color::Yellow)),
-                Span::raw(" "),
-            ];
+    // Only build rows for the commits that fit on screen; long logs have
+    // thousands of entries and styling each one every frame adds up
+    let height = area.height.saturating_sub(2) as usize;
+    let selected = app.list_state.selected();
+    let mut offset = app.list_state.offset().min(app.commits.len().saturating_sub(1));
+    if let Some(selected) = selected {
+        if selected < offset {
+            offset = selected;
+        } else if height > 0 && selected >= offset + height {
+            offset = selected + 1 - height;
+        }
+    }
+    // Day headers take rows too, so scroll on until the selection fits
+    let mut rows = log_window_rows(&app.commits, offset, height, app.group_by_day);
+    if let Some(selected) = selected {
+        while offset < selected && !rows.contains(&LogRow::Commit(selected)) {
+            offset += 1;
+            rows = log_window_rows(&app.commits, offset, height, app.group_by_day);
+        }
+    }
+    *app.list_state.offset_mut() = offset;
 
-            // Add decoration pills
-            for decoration in &commit.decorations {
-                spans.extend(render_decoration(decoration));
-                spans.push(Span::raw(" "));
+    let range = app.marked_range();
+    let items: Vec<ListItem> = rows
+        .iter()
+        .map(|row| match *row {
+            LogRow::Commit(i) => {
+                let in_range = range.map(|(first, last)| (first..=last).contains(&i));
+                commit_row(app, &app.commits[i], in_range)
             }
-
-            // Add commit message
-            spans.push(Span::raw(&commit.message));
-
-            let line = Line::from(spans);
-            ListItem::new(line)
+            LogRow::Day(i) => ListItem::new(Line::from(Span::styled(
+                format!("{}:", app.commits[i].date),
+                Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+            ))),
         })
         .collect();
 
-    let title = if let Some(ref filter) = app.active_filter {
+    // Sync state with the upstream, left out when the branch has none
+    let sync = match app.upstream_divergence {
+        Some(ref divergence) => format!(", ↑{} ↓{}", divergence.ahead.len(), divergence.behind.len()),
+        None => String::new(),
+    };
+    let mut title = if let Some(ref filter) = app.active_filter {
         let filter_str = match filter {
             SearchFilter::Message(q) => format!("grep: {}", q),
             SearchFilter::Author(q) => format!("author: {}", q),
+            SearchFilter::Pickaxe { pattern, regex: false } => format!("added/removed: {}", pattern),
+            SearchFilter::Pickaxe { pattern, regex: true } => format!("diff matches: {}", pattern),
         };
-        format!(" Git Log ({} commits) [{}] ", app.commits.len(), filter_str)
+        format!(" Git Log ({} commits{}) [{}] ", app.commits.len(), sync, filter_str)
     } else {
-        format!(" Git Log ({} commits) ", app.commits.len())
+        format!(" Git Log ({} commits{}) ", app.commits.len(), sync)
     };
+    if app.first_parent {
+        title.push_str("[first-parent] ");
+    }
+    match app.merge_filter {
+        MergeFilter::All => {}
+        MergeFilter::MergesOnly => title.push_str("[merges only] "),
+        MergeFilter::NoMerges => title.push_str("[no merges] "),
+    }
+    if app.bisect_active {
+        title.push_str("[bisecting] ");
+    }
+    if let Some((first, last)) = range {
+        title.push_str(&format!("[range: {}] ", last - first + 1));
+    }
 
+    let keys = &app.keymap;
     let help = if app.show_diff {
-        " Enter: Close | q: Quit "
+        " Enter: Close | q: Quit ".to_string()
+    } else if app.browse_hash.is_some() {
+        " Esc: Close file browser | q: Quit ".to_string()
     } else if app.tree_view_mode {
-        " t: Exit tree view | q: Quit "
+        " y: Copy path | t: Exit tree view | q: Quit ".to_string()
+    } else if range.is_some() {
+        format!(
+            " ↑/↓: Extend range | {}: Copy hashes | {}/Esc: Clear range | q: Quit ",
+            keys.hint("log.copy_hash"),
+            keys.hint("log.mark_range")
+        )
+    } else if app.bisect_active {
+        format!(" g: Good | b: Bad | s: Skip | {}: Reset bisect | q: Quit ", keys.hint("log.bisect"))
     } else if app.active_filter.is_some() {
-        " ↑/↓: Nav | Enter: View | t: Tree | /: Search | Esc: Clear | q: Quit "
+        format!(
+            " ↑/↓: Nav | Enter: View | {}: Tree | {}: Search | Esc: Clear | q: Quit ",
+            keys.hint("log.tree_view"),
+            keys.hint("log.search")
+        )
     } else {
-        " ↑/↓: Nav | Enter: View | t: Tree view | /: Search | q: Quit "
+        format!(
+            " ↑/↓: Nav | Enter: View | {}: Tree view | {}: Search | q: Quit ",
+            keys.hint("log.tree_view"),
+            keys.hint("log.search")
+        )
     };
 
     let list = List::new(items)
@@ -504,7 +1012,95 @@ fn render_commit_list(f: &mut Frame, app: &mut App, area: Rect) {
         )
         .highlight_symbol(">> ");
 
-    f.render_stateful_widget(list, area, &mut app.list_state);
+    // The window starts at `offset`, so select relative to it
+    let selected_row = selected.and_then(|i| rows.iter().position(|row| *row == LogRow::Commit(i)));
+    let mut window_state = ListState::default().with_selected(selected_row);
+    f.render_stateful_widget(list, area, &mut window_state);
+    render_scrollbar(f, area, app.commits.len(), offset);
+}
+
+/// A row of the Log window; both variants hold an index into `app.commits`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LogRow {
+    /// Header naming the day of the commit below it
+    Day(usize),
+    Commit(usize),
+}
+
+/// Rows for the commits from `offset` that fit in `height` lines. When
+/// grouping by day, a header goes at the top of the window and before
+/// every commit made on a different day than the one above it.
+fn log_window_rows(commits: &[Commit], offset: usize, height: usize, group_by_day: bool) -> Vec<LogRow> {
+    let mut rows = Vec::new();
+    for i in offset..commits.len() {
+        if rows.len() >= height {
+            break;
+        }
+        if group_by_day && (i == offset || commits[i].date != commits[i - 1].date) {
+            rows.push(LogRow::Day(i));
+            if rows.len() >= height {
+                break;
+            }
+        }
+        rows.push(LogRow::Commit(i));
+    }
+    rows
+}
+
+/// `in_range` is None when no range is marked, so rows keep their width
+fn commit_row<'a>(app: &App, commit: &'a Commit, in_range: Option<bool>) -> ListItem<'a> {
+    ListItem::new(commit_line(app, commit, in_range))
+}
+
+fn commit_line<'a>(app: &App, commit: &'a Commit, in_range: Option<bool>) -> Line<'a> {
+    let upstream_state = app.upstream_state(commit);
+    let hash_color = match upstream_state {
+        Some(UpstreamState::Unpushed) => Color::Magenta,
+        Some(UpstreamState::Unpulled) => Color::Blue,
+        _ => Color::Yellow,
+    };
+    let mut spans = Vec::new();
+    if let Some(in_range) = in_range {
+        spans.push(Span::styled(if in_range { "▌" } else { " " }, Style::default().fg(Color::Green)));
+    }
+    spans.extend([
+        Span::styled(&commit.graph, Style::default().fg(Color::Cyan)),
+        Span::styled(&commit.hash, Style::default().fg(hash_color)),
+        Span::raw(" "),
+    ]);
+
+    match upstream_state {
+        Some(UpstreamState::Unpushed) => spans.push(Span::styled("↑ ", Style::default().fg(Color::Magenta))),
+        Some(UpstreamState::Unpulled) => spans.push(Span::styled("↓ ", Style::default().fg(Color::Blue))),
+        _ => {}
+    }
+
+    // Merges are hard to spot in a busy graph; octopus merges show their parent count
+    if commit.is_merge() {
+        let marker = match commit.parents.len() {
+            2 => "⑂ ".to_string(),
+            n => format!("⑂{} ", n),
+        };
+        spans.push(Span::styled(marker, Style::default().fg(Color::LightYellow)));
+    }
+
+    // Add decoration pills
+    for decoration in &commit.decorations {
+        spans.extend(render_decoration(decoration));
+        spans.push(Span::raw(" "));
+    }
+
+    // Add commit message
+    spans.push(Span::raw(&commit.message));
+
+    if upstream_state == Some(UpstreamState::ForkPoint) {
+        spans.push(Span::styled(
+            "  ◂ upstream fork point",
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        ));
+    }
+
+    Line::from(spans)
 }
 
 /// Renders a decoration as styled spans (pills)
@@ -573,18 +1169,32 @@ fn render_decoration(decoration: &Decoration) -> Vec<Span<'static>> {
 
 fn render_file_list(f: &mut Frame, app: &mut App, area: Rect) {
     if let Some(ref diff) = app.current_diff {
-        let items: Vec<ListItem> = diff
-            .files
+        let visible = app.visible_diff_files();
+        let common = common_dir(visible.iter().map(|file| file.filename.as_str()));
+        let items: Vec<ListItem> = visible
             .iter()
             .map(|file| {
-                let line = Line::from(Span::raw(&file.filename));
+                let line = Line::from(Span::raw(app.display_path(&file.filename, &common).to_string()));
                 ListItem::new(line)
             })
             .collect();
 
-        let title = format!(" Files ({}) ", diff.files.len());
-        let help = " ←/→: Switch File ";
+        let count = file_count(app, visible.len(), diff.files.len());
+        let filter_hint = format!("{}: Filter", app.keymap.hint("log.search"));
+        let (mut title, help) = match (&app.worktree_diff_base, app.merge_parent_label()) {
+            (Some(base), _) => (
+                format!(" Files ({}) [working tree vs {}] ", count, base),
+                format!(" ←/→: Switch File | {} ", filter_hint),
+            ),
+            (None, Some(label)) => (
+                format!(" Files ({}) [{}] ", count, label),
+                format!(" ←/→: Switch File | {}: Next parent | {} ", app.keymap.hint("log.next_parent"), filter_hint),
+            ),
+            (None, None) => (format!(" Files ({}) ", count), format!(" ←/→: Switch File | {} ", filter_hint)),
+        };
+        title.push_str(&path_display_tag(app, &common));
 
+        let total = items.len();
         let list = List::new(items)
             .block(
                 Block::default()
@@ -600,28 +1210,75 @@ fn render_file_list(f: &mut Frame, app: &mut App, area: Rect) {
             .highlight_symbol(">> ");
 
         f.render_stateful_widget(list, area, &mut app.file_list_state);
+        render_scrollbar(f, area, total, app.file_list_state.offset());
+    }
+}
+
+/// "3", or "1 of 3 [filter: *.rs]" while the file filter hides some
+fn file_count(app: &App, shown: usize, total: usize) -> String {
+    match app.file_filter {
+        Some(ref filter) => format!("{} of {} [filter: {}]", shown, total, filter),
+        None => total.to_string(),
     }
 }
 
+fn render_commit_notes(f: &mut Frame, app: &App, area: Rect) {
+    let Some(ref notes) = app.commit_notes else {
+        return;
+    };
+
+    let paragraph = Paragraph::new(notes.clone())
+        .style(Style::default().fg(Color::Gray))
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title(" Notes "));
+
+    f.render_widget(paragraph, area);
+}
+
 fn render_diff(f: &mut Frame, app: &App, area: Rect) {
-    if let Some(ref commit_diff) = app.current_diff {
+    if app.current_diff.is_some() {
         let selected_file_index = app.file_list_state.selected().unwrap_or(0);
 
-        let file_diff = commit_diff.files.get(selected_file_index);
+        let file_diff = app.selected_diff_file();
         let diff_content = file_diff.map(|f| f.diff_content.as_str()).unwrap_or("");
         let filename = file_diff.map(|f| f.filename.as_str()).unwrap_or("");
 
         // Apply syntax highlighting to the diff
-        let all_highlighted_lines = syntax::highlight_diff(diff_content, filename);
+        let mut all_highlighted_lines = diff_lines(app, diff_content, filename);
+        if let Some(note) = file_diff.and_then(|f| f.note.as_deref()) {
+            all_highlighted_lines.insert(0, diff_note_line(note));
+        } else if let Some((old, new)) = file_diff.and_then(|f| f.mode_change.as_ref()) {
+            all_highlighted_lines.insert(0, diff_note_line(&format!("mode: {} → {}", old, new)));
+        }
 
         // Apply scroll offset
+        let total = all_highlighted_lines.len();
         let diff_lines: Vec<Line> = all_highlighted_lines
             .into_iter()
             .skip(app.diff_scroll as usize)
             .collect();
 
-        let title = format!(" {} ", filename);
-        let help = " ↑/↓: Scroll | ESC: Close ";
+        // The file list is hidden in full screen, so show the position here
+        let mut title = if app.diff_fullscreen {
+            format!(" {} ({}/{}) ", filename, selected_file_index + 1, app.visible_diff_files().len())
+        } else {
+            format!(" {} ", filename)
+        };
+        if let Some(ref base) = app.worktree_diff_base {
+            title.push_str(&format!("[working tree vs {}] ", base));
+        }
+        title.push_str(whitespace_tag(app));
+        if app.auto_diff {
+            title.push_str("[pinned] ");
+        }
+        let help = if app.auto_diff {
+            format!(
+                " ↑/↓: Next/previous commit | PgUp/PgDn: Scroll | {}: Unpin | ESC: Close ",
+                app.keymap.hint("log.pin_diff")
+            )
+        } else {
+            format!(" ↑/↓: Scroll | {}: Full screen | ESC: Close ", app.keymap.hint("log.fullscreen"))
+        };
 
         let paragraph = Paragraph::new(diff_lines)
             .block(
@@ -633,17 +1290,22 @@ fn render_diff(f: &mut Frame, app: &App, area: Rect) {
             .wrap(Wrap { trim: false });
 
         f.render_widget(paragraph, area);
+        render_scrollbar(f, area, total, app.diff_scroll as usize);
     }
 }
 
 fn render_search_input(f: &mut Frame, app: &App, area: Rect) {
     let search_type = if app.search_query.starts_with('@') {
         "Author Search"
+    } else if app.search_query.starts_with("S:") {
+        "Pickaxe Search (added/removed)"
+    } else if app.search_query.starts_with("G:") {
+        "Pickaxe Search (diff regex)"
     } else {
         "Message Search"
     };
 
-    let help = " Type to search | @ prefix for author | Enter: Apply | Esc: Cancel ";
+    let help = " Type to search | @ author | S: added/removed text | G: diff regex | ↑/↓: History | Enter: Apply | Esc: Cancel ";
 
     let input_text = if app.search_query.is_empty() {
         "Type to search commits...".to_string()
@@ -670,26 +1332,131 @@ fn render_search_input(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, area);
 }
 
-fn render_commit_message_input(f: &mut Frame, app: &App, area: Rect) {
-    let (title, help) = if app.amend_mode {
-        (" Amend Commit Message ", " Edit message | Enter: Amend | Esc: Cancel ")
-    } else {
-        (" Commit Message ", " Type commit message | Enter: Commit | Esc: Cancel ")
-    };
+/// Maximum number of staged files listed above the commit input
+const STAGED_SUMMARY_MAX_FILES: usize = 6;
 
-    let input_text = if app.commit_message_input.is_empty() {
-        "Enter commit message...".to_string()
-    } else {
-        app.commit_message_input.clone()
+/// Height of the staged files box shown above the commit input
+fn staged_summary_height(app: &App) -> u16 {
+    let staged = app.status_files.iter().filter(|f| f.staged).count();
+    let rows = match staged {
+        0 => 1,
+        n if n > STAGED_SUMMARY_MAX_FILES => STAGED_SUMMARY_MAX_FILES + 1,
+        n => n,
     };
+    rows as u16 + 2
+}
 
-    let input_style = if app.commit_message_input.is_empty() {
-        Style::default().fg(Color::DarkGray)
+/// Lists the staged files (with +/- line counts) that the commit will include
+fn render_staged_summary(f: &mut Frame, app: &App, area: Rect) {
+    let staged: Vec<&StatusFile> = app.status_files.iter().filter(|f| f.staged).collect();
+
+    let mut lines: Vec<Line> = staged
+        .iter()
+        .take(STAGED_SUMMARY_MAX_FILES)
+        .map(|file| {
+            let status_char = match file.status {
+                crate::git::FileStatus::Modified => "M",
+                crate::git::FileStatus::Added => "A",
+                crate::git::FileStatus::Deleted => "D",
+                crate::git::FileStatus::Renamed => "R",
+                crate::git::FileStatus::Untracked => "?",
+                crate::git::FileStatus::Conflicted => "U",
+            };
+
+            let mut spans = vec![
+                Span::styled(format!("[{}] ", status_char), Style::default().fg(Color::Green)),
+                Span::raw(file.path.clone()),
+            ];
+            match app.staged_stats.get(&file.path) {
+                Some(Some((added, removed))) => {
+                    spans.push(Span::styled(format!("  +{}", added), Style::default().fg(Color::Green)));
+                    spans.push(Span::styled(format!(" -{}", removed), Style::default().fg(Color::Red)));
+                }
+                Some(None) => spans.push(Span::styled("  binary", Style::default().fg(Color::DarkGray))),
+                None => {}
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    if staged.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Nothing staged",
+            Style::default().fg(Color::DarkGray),
+        )));
+    } else if staged.len() > STAGED_SUMMARY_MAX_FILES {
+        lines.push(Line::from(Span::styled(
+            format!("... and {} more", staged.len() - STAGED_SUMMARY_MAX_FILES),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Staged ({} files) ", staged.len()))
+            .border_style(Style::default().fg(Color::DarkGray)),
+    );
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_commit_message_input(f: &mut Frame, app: &App, area: Rect) {
+    let area = if app.reword_target.is_none() {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(3)])
+            .split(area);
+        render_staged_summary(f, app, chunks[0]);
+        chunks[1]
+    } else {
+        area
+    };
+
+    let (title, help) = if app.reword_target.is_some() {
+        (" Reword Commit Message ".to_string(), " Edit subject | Enter: Reword | Esc: Cancel ")
+    } else if app.amend_mode && app.amend_include_unstaged {
+        (
+            " Amend Commit Message (+ unstaged changes of tracked files) ".to_string(),
+            " Edit message | Enter: Amend | Tab: Staged only | Esc: Cancel ",
+        )
+    } else if app.amend_mode {
+        (
+            " Amend Commit Message ".to_string(),
+            " Edit message | Enter: Amend | Tab: Include unstaged | Esc: Cancel ",
+        )
+    } else {
+        let title = format!(
+            " Commit Message{}{} ",
+            if app.commit_and_push { " (then push)" } else { "" },
+            if app.commit_allow_empty { " [allow empty]" } else { "" }
+        );
+        let help = match (app.commit_and_push, app.commit_allow_empty) {
+            (true, false) => " Type commit message | Enter: Commit & Push | Tab: Allow empty | Esc: Cancel ",
+            (true, true) => " Type commit message | Enter: Commit & Push | Tab: Require changes | Esc: Cancel ",
+            (false, false) => " Type commit message | Enter: Commit | Tab: Allow empty | Esc: Cancel ",
+            (false, true) => " Type commit message | Enter: Commit | Tab: Require changes | Esc: Cancel ",
+        };
+        (title, help)
+    };
+
+    let input_text = if app.commit_message_input.is_empty() {
+        "Enter commit message...".to_string()
+    } else {
+        app.commit_message_input.clone()
+    };
+
+    let input_style = if app.commit_message_input.is_empty() {
+        Style::default().fg(Color::DarkGray)
     } else {
         Style::default().fg(Color::White)
     };
 
-    let border_color = if app.amend_mode { Color::Yellow } else { Color::Green };
+    let border_color = if app.amend_mode || app.reword_target.is_some() {
+        Color::Yellow
+    } else {
+        Color::Green
+    };
 
     let paragraph = Paragraph::new(input_text)
         .style(input_style)
@@ -705,7 +1472,16 @@ fn render_commit_message_input(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_stash_input(f: &mut Frame, app: &App, area: Rect) {
-    let help = " Type stash message (optional) | Enter: Create stash | Esc: Cancel ";
+    let (title, help) = match app.restash_index {
+        Some(index) => (
+            format!(" New Message for stash@{{{}}} ", index),
+            " Type new message | Enter: Re-stash (moves it to stash@{0}) | Esc: Cancel ",
+        ),
+        None => (
+            " Stash Message ".to_string(),
+            " Type stash message (optional) | Enter: Create stash | Esc: Cancel ",
+        ),
+    };
 
     let input_text = if app.stash_message_input.is_empty() {
         "Enter stash message (optional)...".to_string()
@@ -724,7 +1500,7 @@ fn render_stash_input(f: &mut Frame, app: &App, area: Rect) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(" Stash Message ")
+                .title(title)
                 .title_bottom(help)
                 .border_style(Style::default().fg(Color::Magenta)),
         );
@@ -760,10 +1536,639 @@ fn render_new_branch_input(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, area);
 }
 
+fn render_goto_input(f: &mut Frame, app: &App, area: Rect) {
+    let help = " Hash, branch, tag or HEAD~n | Enter: Jump | Esc: Cancel ";
+
+    let input_text = if app.goto_input.is_empty() {
+        "Enter a commit hash or ref...".to_string()
+    } else {
+        app.goto_input.clone()
+    };
+
+    let input_style = if app.goto_input.is_empty() {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default().fg(Color::White)
+    };
+
+    let paragraph = Paragraph::new(input_text)
+        .style(input_style)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Go to Commit ")
+                .title_bottom(help)
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_amend_metadata_input(f: &mut Frame, app: &App, area: Rect) {
+    let Some(ref metadata) = app.amend_metadata else {
+        return;
+    };
+    let (title, input_text, help) = if metadata.editing_date {
+        (
+            " Amend HEAD Date ",
+            metadata.date.clone(),
+            " e.g. 2024-03-01T12:00:00+01:00 or \"2 days ago\" | Enter: Amend | Tab: Author | Esc: Cancel ",
+        )
+    } else {
+        (
+            " Amend HEAD Author ",
+            metadata.author.clone(),
+            " Name <email> | Enter: Next | Tab: Date | Esc: Cancel ",
+        )
+    };
+
+    let paragraph = Paragraph::new(input_text)
+        .style(Style::default().fg(Color::White))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(help)
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_file_filter_input(f: &mut Frame, app: &App, area: Rect) {
+    let help = " *.rs, src/*/mod.rs or any text | Enter: Keep | Esc: Clear ";
+
+    let filter = app.file_filter.as_deref().unwrap_or_default();
+    let (input_text, input_style) = if filter.is_empty() {
+        ("Type a glob or part of a path...".to_string(), Style::default().fg(Color::DarkGray))
+    } else {
+        (filter.to_string(), Style::default().fg(Color::White))
+    };
+
+    let paragraph = Paragraph::new(input_text)
+        .style(input_style)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Filter Files ")
+                .title_bottom(help)
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_rename_input(f: &mut Frame, app: &App, area: Rect) {
+    let source = app.rename_source.as_deref().unwrap_or_default();
+
+    let paragraph = Paragraph::new(app.rename_input.clone())
+        .style(Style::default().fg(Color::White))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Rename {} ", source))
+                .title_bottom(" New path | Enter: git mv | Esc: Cancel ")
+                .border_style(Style::default().fg(Color::Green)),
+        );
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_remote_url_input(f: &mut Frame, app: &App, area: Rect) {
+    let remote = app
+        .remote_list_state
+        .selected()
+        .and_then(|i| app.remotes.get(i))
+        .map(String::as_str)
+        .unwrap_or("remote");
+
+    let paragraph = Paragraph::new(app.remote_url_input.clone())
+        .style(Style::default().fg(Color::White))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" URL of {} ", remote))
+                .title_bottom(" Enter: Set URL | Esc: Cancel ")
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_tag_input(f: &mut Frame, app: &App, area: Rect) {
+    let (title, help) = if app.tag_sign {
+        (" New Signed Tag ", " <name> [message] | Enter: Tag HEAD | Tab: Don't sign | Esc: Cancel ")
+    } else {
+        (" New Tag ", " <name> [message] | Enter: Tag HEAD | Tab: Sign | Esc: Cancel ")
+    };
+
+    let input_text = if app.tag_input.is_empty() {
+        "Enter tag name, plus a message for an annotated tag...".to_string()
+    } else {
+        app.tag_input.clone()
+    };
+
+    let input_style = if app.tag_input.is_empty() {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default().fg(Color::White)
+    };
+
+    let paragraph = Paragraph::new(input_text)
+        .style(input_style)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(help)
+                .border_style(Style::default().fg(Color::Green)),
+        );
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_worktree_input(f: &mut Frame, app: &App, area: Rect) {
+    let help = " <path> [branch] | Enter: Add worktree | Esc: Cancel ";
+
+    let input_text = if app.worktree_input.is_empty() {
+        "Enter worktree path and optional branch...".to_string()
+    } else {
+        app.worktree_input.clone()
+    };
+
+    let input_style = if app.worktree_input.is_empty() {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default().fg(Color::White)
+    };
+
+    let paragraph = Paragraph::new(input_text)
+        .style(input_style)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" New Worktree ")
+                .title_bottom(help)
+                .border_style(Style::default().fg(Color::Green)),
+        );
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_stash_target_popup(f: &mut Frame, app: &mut App) {
+    let area = centered_rect(50, 50, f.area());
+    f.render_widget(Clear, area);
+
+    let items: Vec<ListItem> = app
+        .stash_target_branches
+        .iter()
+        .map(|branch| ListItem::new(Span::styled(branch.clone(), Style::default().fg(Color::Green))))
+        .collect();
+
+    let title = match app.stash_list_state.selected().and_then(|i| app.stashes.get(i)) {
+        Some(stash) => format!(" Apply stash@{{{}}} on branch ", stash.index),
+        None => " Apply stash on branch ".to_string(),
+    };
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(" Enter: Switch & apply | p: Switch & pop | Esc: Close ")
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    f.render_stateful_widget(list, area, &mut app.stash_target_list_state);
+}
+
+fn render_remotes_popup(f: &mut Frame, app: &mut App) {
+    let area = centered_rect(60, 30, f.area());
+    f.render_widget(Clear, area);
+
+    let items: Vec<ListItem> = app
+        .remotes
+        .iter()
+        .map(|remote| {
+            let mut spans = vec![Span::styled(remote.clone(), Style::default().fg(Color::White))];
+            if let Some(url) = app.remote_urls.get(remote) {
+                spans.push(Span::styled(format!("  {}", url), Style::default().fg(Color::DarkGray)));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(if app.remotes_push { " Push to remote " } else { " Remotes " })
+                .title_bottom(if app.remotes_push {
+                    " Enter: Push | e: Edit URL | Esc: Cancel "
+                } else {
+                    " Enter: Fetch --prune | a: Fetch all | e: Edit URL | Esc: Close "
+                })
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    f.render_stateful_widget(list, area, &mut app.remote_list_state);
+}
+
+fn render_confirm_popup(f: &mut Frame, app: &App) {
+    let Some(ref prompt) = app.confirm else {
+        return;
+    };
+
+    let area = if prompt.details.is_empty() {
+        centered_rect(60, 20, f.area())
+    } else {
+        centered_rect(70, 60, f.area())
+    };
+    f.render_widget(Clear, area);
+
+    let mut lines = vec![Line::from(prompt.message.clone())];
+    if !prompt.details.is_empty() {
+        lines.push(Line::from(""));
+        lines.extend(
+            prompt
+                .details
+                .iter()
+                .map(|detail| Line::from(Span::styled(detail.clone(), Style::default().fg(Color::Gray)))),
+        );
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White))
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Confirm ")
+                .title_bottom(" y: Yes | n/Esc: No ")
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_discard_all_popup(f: &mut Frame, app: &App) {
+    let Some(ref input) = app.discard_all_input else {
+        return;
+    };
+
+    let area = centered_rect(60, 30, f.area());
+    f.render_widget(Clear, area);
+
+    let checkbox = if app.discard_all_untracked { "[x]" } else { "[ ]" };
+    let lines = vec![
+        Line::from(Span::styled(
+            "Discard ALL staged and unstaged changes to tracked files?",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        Line::from("This cannot be undone."),
+        Line::from(""),
+        Line::from(format!("{} Also delete untracked files (Tab)", checkbox)),
+        Line::from(""),
+        Line::from(vec![
+            Span::raw("Type yes to confirm: "),
+            Span::styled(input.clone(), Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
+        ]),
+    ];
+
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Discard All Changes ")
+                .title_bottom(" Enter: Discard | Tab: Untracked too | Esc: Cancel ")
+                .border_style(Style::default().fg(Color::Red)),
+        );
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_reflog_popup(f: &mut Frame, app: &mut App) {
+    let Some(ref branch) = app.reflog_branch else {
+        return;
+    };
+
+    let area = centered_rect(80, 60, f.area());
+    f.render_widget(Clear, area);
+
+    let items: Vec<ListItem> = app
+        .reflog
+        .iter()
+        .map(|entry| {
+            ListItem::new(Line::from(vec![
+                Span::styled(entry.hash.clone(), Style::default().fg(Color::Yellow)),
+                Span::raw(" "),
+                Span::styled(entry.selector.clone(), Style::default().fg(Color::Cyan)),
+                Span::raw(" "),
+                Span::raw(entry.subject.clone()),
+            ]))
+        })
+        .collect();
+
+    let title = format!(" Reflog: {} ({}) ", branch, app.reflog.len());
+    let help = " Enter/r: Reset branch here | Esc: Close ";
+
+    let total = items.len();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(help)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    f.render_stateful_widget(list, area, &mut app.reflog_list_state);
+    render_scrollbar(f, area, total, app.reflog_list_state.offset());
+}
+
+fn render_sync_popup(f: &mut Frame, app: &mut App) {
+    let Some(ref upstream) = app.sync_upstream else {
+        return;
+    };
+
+    let area = centered_rect(80, 60, f.area());
+    f.render_widget(Clear, area);
+
+    let items: Vec<ListItem> = app
+        .sync_commits
+        .iter()
+        .map(|commit| commit_row(app, commit, None))
+        .collect();
+
+    let branch = app.head_branch.as_deref().unwrap_or("HEAD");
+    let (title, help) = if app.sync_incoming {
+        (
+            format!(" Incoming: {} → {} ({}) ", upstream, branch, app.sync_commits.len()),
+            " Enter: Show in log | U: Pull | Esc: Close ",
+        )
+    } else {
+        (
+            format!(" To push: {} → {} ({}) ", branch, upstream, app.sync_commits.len()),
+            " Enter: Show in log | P: Push | Esc: Close ",
+        )
+    };
+
+    let total = items.len();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(help)
+                .border_style(Style::default().fg(Color::Magenta)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    f.render_stateful_widget(list, area, &mut app.sync_list_state);
+    render_scrollbar(f, area, total, app.sync_list_state.offset());
+}
+
+fn render_branch_compare_popup(f: &mut Frame, app: &mut App) {
+    let Some((ref left, ref right)) = app.branch_compare else {
+        return;
+    };
+
+    let area = centered_rect(80, 60, f.area());
+    f.render_widget(Clear, area);
+
+    let mut items: Vec<ListItem> = app
+        .branch_compare_commits
+        .iter()
+        .map(|(on_right, commit)| {
+            let (marker, color) = if *on_right { ("> ", Color::Blue) } else { ("< ", Color::Magenta) };
+            let mut line = commit_line(app, commit, None);
+            line.spans.insert(0, Span::styled(marker, Style::default().fg(color)));
+            ListItem::new(line)
+        })
+        .collect();
+    if items.is_empty() {
+        items.push(ListItem::new("Both point to the same commit history"));
+    }
+
+    let only_left = app.branch_compare_commits.iter().filter(|(on_right, _)| !on_right).count();
+    let only_right = app.branch_compare_commits.len() - only_left;
+    let tracks = app
+        .branches
+        .iter()
+        .any(|b| b.name == *left && b.upstream.as_deref() == Some(right.as_str()));
+    let title = format!(
+        " < {} ({}) vs > {}{} ({}) ",
+        left,
+        only_left,
+        right,
+        if tracks { " [upstream]" } else { "" },
+        only_right
+    );
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(" Enter: Show in log | c/Tab: Next copy | Esc: Close ")
+        .border_style(Style::default().fg(Color::Magenta));
+    if let Some((ref hash, ref subject)) = app.branch_compare_merge_base {
+        block = block.title(
+            Line::from(vec![
+                Span::raw(" merge-base: "),
+                Span::styled(hash.clone(), Style::default().fg(Color::Yellow)),
+                Span::raw(format!(" {} ", subject)),
+            ])
+            .right_aligned(),
+        );
+    }
+
+    let total = items.len();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    f.render_stateful_widget(list, area, &mut app.branch_compare_list_state);
+    render_scrollbar(f, area, total, app.branch_compare_list_state.offset());
+}
+
+fn render_worktrees_popup(f: &mut Frame, app: &mut App) {
+    let area = centered_rect(70, 50, f.area());
+    f.render_widget(Clear, area);
+
+    let items: Vec<ListItem> = app
+        .worktrees
+        .iter()
+        .map(|worktree| {
+            let is_current = Some(&worktree.path) == app.current_worktree.as_ref();
+            let mut spans = vec![if is_current {
+                Span::styled("* ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+            } else {
+                Span::raw("  ")
+            }];
+
+            spans.push(Span::styled(
+                worktree.path.clone(),
+                if is_current {
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::White)
+                },
+            ));
+            spans.push(Span::raw(" "));
+
+            let branch = if worktree.is_bare {
+                "(bare)".to_string()
+            } else {
+                match worktree.branch {
+                    Some(ref branch) => format!("[{}]", branch),
+                    None => "(detached)".to_string(),
+                }
+            };
+            spans.push(Span::styled(branch, Style::default().fg(Color::Green)));
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(
+                worktree.head[..7.min(worktree.head.len())].to_string(),
+                Style::default().fg(Color::Yellow),
+            ));
+
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let title = format!(" Worktrees ({}) ", app.worktrees.len());
+    let help = " Enter: Switch | n: Add | Esc: Close ";
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(help)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    f.render_stateful_widget(list, area, &mut app.worktree_list_state);
+}
+
+fn render_browse_entries(f: &mut Frame, app: &mut App, area: Rect) {
+    let Some(ref hash) = app.browse_hash else {
+        return;
+    };
+
+    let items: Vec<ListItem> = app
+        .browse_entries
+        .iter()
+        .map(|(name, is_dir)| {
+            if *is_dir {
+                ListItem::new(Line::from(Span::styled(
+                    format!("▸ {}/", name),
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                )))
+            } else {
+                ListItem::new(Line::from(format!("  {}", name)))
+            }
+        })
+        .collect();
+
+    let title = format!(" Files at {}: /{} ({}) ", hash, app.browse_dir, app.browse_entries.len());
+    let help = " ↑/↓: Navigate | Enter: Open | Backspace: Up | y: Copy path | Esc: Close ";
+
+    let total = items.len();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(help),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    f.render_stateful_widget(list, area, &mut app.browse_list_state);
+    render_scrollbar(f, area, total, app.browse_list_state.offset());
+}
+
+/// A file as it was in the browsed commit, with line numbers
+fn render_browse_file(f: &mut Frame, app: &App, area: Rect) {
+    let (Some(ref hash), Some((ref path, ref lines))) = (&app.browse_hash, &app.browse_file) else {
+        return;
+    };
+
+    let title = format!(" {} @ {} ", path, hash);
+    let help = " ↑/↓: Scroll | y: Copy path | Esc: Back ";
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(help);
+
+    let Some(lines) = lines else {
+        f.render_widget(Paragraph::new(diff_note_line("Binary file")).block(block), area);
+        return;
+    };
+
+    let width = lines.len().to_string().len();
+    let visible: Vec<Line> = lines
+        .iter()
+        .enumerate()
+        .skip(app.browse_scroll as usize)
+        .take(area.height as usize)
+        .map(|(i, line)| {
+            let mut spans = vec![Span::styled(
+                format!("{:>width$} ", i + 1, width = width),
+                Style::default().fg(Color::DarkGray),
+            )];
+            spans.extend(line.spans.iter().cloned());
+            Line::from(spans)
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(visible).block(block), area);
+    render_scrollbar(f, area, lines.len(), app.browse_scroll as usize);
+}
+
 fn render_tree_file_list(f: &mut Frame, app: &mut App, area: Rect) {
     if let Some(ref diff) = app.current_diff {
-        let items: Vec<ListItem> = diff
-            .files
+        let visible = app.visible_diff_files();
+        let common = common_dir(visible.iter().map(|file| file.filename.as_str()));
+        let items: Vec<ListItem> = visible
             .iter()
             .map(|file| {
                 // Add a change indicator
@@ -773,15 +2178,26 @@ fn render_tree_file_list(f: &mut Frame, app: &mut App, area: Rect) {
                         format!("[{}] ", indicator),
                         Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
                     ),
-                    Span::raw(&file.filename),
+                    Span::raw(app.display_path(&file.filename, &common).to_string()),
                 ]);
                 ListItem::new(line)
             })
             .collect();
 
-        let title = format!(" Files Changed ({}) ", diff.files.len());
-        let help = " ↑/↓: Navigate | Enter: View File | Esc: Close | t: Toggle view ";
+        let count = file_count(app, visible.len(), diff.files.len());
+        let (mut title, help) = match app.merge_parent_label() {
+            Some(label) => (
+                format!(" Files Changed ({}) [{}] ", count, label),
+                " ↑/↓: Navigate | Enter: View File | /: Filter | m: Next parent | Esc: Close ",
+            ),
+            None => (
+                format!(" Files Changed ({}) ", count),
+                " ↑/↓: Navigate | Enter: View File | /: Filter | Esc: Close | t: Toggle view ",
+            ),
+        };
+        title.push_str(&path_display_tag(app, &common));
 
+        let total = items.len();
         let list = List::new(items)
             .block(
                 Block::default()
@@ -797,27 +2213,32 @@ fn render_tree_file_list(f: &mut Frame, app: &mut App, area: Rect) {
             .highlight_symbol(">> ");
 
         f.render_stateful_widget(list, area, &mut app.file_list_state);
+        render_scrollbar(f, area, total, app.file_list_state.offset());
     }
 }
 
 fn render_tree_file_diff(f: &mut Frame, app: &App, area: Rect) {
-    if let Some(ref commit_diff) = app.current_diff {
-        let selected_file_index = app.file_list_state.selected().unwrap_or(0);
-
-        let file_diff = commit_diff.files.get(selected_file_index);
+    if app.current_diff.is_some() {
+        let file_diff = app.selected_diff_file();
         let diff_content = file_diff.map(|f| f.diff_content.as_str()).unwrap_or("");
         let filename = file_diff.map(|f| f.filename.as_str()).unwrap_or("");
 
         // Apply syntax highlighting to the diff
-        let all_highlighted_lines = syntax::highlight_diff(diff_content, filename);
+        let mut all_highlighted_lines = diff_lines(app, diff_content, filename);
+        if let Some(note) = file_diff.and_then(|f| f.note.as_deref()) {
+            all_highlighted_lines.insert(0, diff_note_line(note));
+        } else if let Some((old, new)) = file_diff.and_then(|f| f.mode_change.as_ref()) {
+            all_highlighted_lines.insert(0, diff_note_line(&format!("mode: {} → {}", old, new)));
+        }
 
         // Apply scroll offset
+        let total = all_highlighted_lines.len();
         let diff_lines: Vec<Line> = all_highlighted_lines
             .into_iter()
             .skip(app.diff_scroll as usize)
             .collect();
 
-        let title = format!(" {} ", filename);
+        let title = format!(" {} {}", filename, whitespace_tag(app));
         let help = " ↑/↓: Scroll | Esc: Back to file list ";
 
         let paragraph = Paragraph::new(diff_lines)
@@ -830,11 +2251,22 @@ fn render_tree_file_diff(f: &mut Frame, app: &App, area: Rect) {
             .wrap(Wrap { trim: false });
 
         f.render_widget(paragraph, area);
+        render_scrollbar(f, area, total, app.diff_scroll as usize);
     }
 }
 
 fn render_status_message(f: &mut Frame, app: &App, area: Rect) {
-    if let Some(ref message) = app.status_message {
+    if let Some(ref input) = app.quick_commit_input {
+        let line = Line::from(vec![
+            Span::styled(
+                if app.quick_commit_all { " Stage all and commit: " } else { " Commit: " },
+                Style::default().fg(Color::Black).bg(Color::Green),
+            ),
+            Span::raw(format!(" {}▏", input)),
+            Span::styled("  Enter: Commit | Esc: Cancel", Style::default().fg(Color::DarkGray)),
+        ]);
+        f.render_widget(Paragraph::new(line), area);
+    } else if let Some(ref message) = app.status_message {
         let style = match app.status_message_type {
             MessageType::Success => Style::default().fg(Color::Black).bg(Color::Green),
             MessageType::Error => Style::default().fg(Color::White).bg(Color::Red),
@@ -894,56 +2326,225 @@ fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
-fn render_help_popup(f: &mut Frame) {
+fn render_interactive_staging(f: &mut Frame, app: &App) {
+    let Some(ref staging) = app.interactive_staging else {
+        return;
+    };
+
+    let area = centered_rect(80, 70, f.area());
+    f.render_widget(Clear, area);
+
+    let picker = &staging.picker;
+    let content = picker.current_lines().join("\n");
+    let lines = diff_lines(app, &content, &staging.path);
+
+    let verb = match (staging.stash, staging.unstage) {
+        (true, _) => "Stash",
+        (false, true) => "Unstage",
+        (false, false) => "Stage",
+    };
+    let title = format!(
+        " {} hunk {}/{}: {} ",
+        verb,
+        (picker.current + 1).min(picker.len()),
+        picker.len(),
+        staging.path
+    );
+    let help = if picker.can_split() {
+        format!(" y: {} | n: Skip | s: Split | q: Apply & quit | Esc: Cancel ", verb)
+    } else {
+        format!(" y: {} | n: Skip | q: Apply & quit | Esc: Cancel ", verb)
+    };
+
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(help)
+                .border_style(Style::default().fg(Color::Green)),
+        )
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_line_staging(f: &mut Frame, app: &App) {
+    let Some(ref staging) = app.line_staging else {
+        return;
+    };
+
+    let area = centered_rect(80, 70, f.area());
+    f.render_widget(Clear, area);
+
+    let picker = &staging.picker;
+    let (first, last) = picker.selected_rows();
+    let content = picker.display_lines().join("\n");
+    let lines: Vec<Line> = diff_lines(app, &content, &staging.path)
+        .into_iter()
+        .enumerate()
+        .map(|(row, line)| {
+            let selected = (first..=last).contains(&row);
+            let mut spans = vec![Span::styled(if selected { "▌" } else { " " }, Style::default().fg(Color::Green))];
+            spans.extend(line.spans);
+            let line = Line::from(spans);
+            if row == picker.cursor {
+                line.style(Style::default().bg(Color::DarkGray))
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    // Keep the cursor in the middle of the popup once it moves past it
+    let height = area.height.saturating_sub(2) as usize;
+    let scroll = picker.cursor.saturating_sub(height / 2) as u16;
+
+    let verb = if staging.unstage { "Unstage" } else { "Stage" };
+    let title = format!(" {} lines ({} selected): {} ", verb, picker.selected_changes(), staging.path);
+    let help = format!(" j/k: Move | J/K: Extend selection | Space/Enter: {} | Esc: Close ", verb);
+
+    let paragraph = Paragraph::new(lines).scroll((scroll, 0)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_bottom(help)
+            .border_style(Style::default().fg(Color::Green)),
+    );
+
+    f.render_widget(paragraph, area);
+}
+
+fn render_help_popup(f: &mut Frame, app: &App) {
     let area = centered_rect(60, 70, f.area());
     f.render_widget(Clear, area);
 
-    let help_text = vec![
+    // Remappable actions show the key the user has bound
+    let keys = &app.keymap;
+    let key = |action: &str, text: &str| Line::from(format!("  {:<10} {}", keys.hint(action), text));
+
+    let mut help_text = vec![
         Line::from(Span::styled("Keybindings", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))),
         Line::from(""),
         Line::from(Span::styled("Global", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
-        Line::from("  1-4        Switch panels (Status/Log/Stash/Branches)"),
+        Line::from("  1-5        Switch panels (Status/Log/Stash/Branches/Tags)"),
+        Line::from("  Tab/S-Tab  Next / previous panel"),
+        Line::from("  !          Open a shell in the repo root"),
+        Line::from("  T          Next syntax theme (saved to config)"),
+        Line::from("  V          Plain green/red diff text on/off"),
+        Line::from("  E          Expand/cut very long diff lines"),
+        Line::from("  N          File paths: full / below shared dir / names"),
+        Line::from("  K          Copy the current branch name"),
         Line::from("  ?          Toggle this help"),
         Line::from("  q          Quit / Close diff"),
         Line::from("  Esc        Cancel / Clear"),
         Line::from("  PgUp/PgDn  Scroll diff by 10 lines"),
+        Line::from("  W          Worktrees (Enter: switch, n: add)"),
         Line::from(""),
         Line::from(Span::styled("Status Panel", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
-        Line::from("  Space      Stage / Unstage file"),
-        Line::from("  a          Stage all files"),
-        Line::from("  u          Unstage all files"),
-        Line::from("  c          Commit"),
-        Line::from("  A          Amend last commit"),
-        Line::from("  x          Discard changes in file"),
-        Line::from("  s          Stash changes"),
-        Line::from("  Enter      Show / Hide diff"),
+        key("status.stage", "Stage / Unstage file"),
+        key("status.stage_all", "Stage all files"),
+        key("status.unstage_all", "Unstage all files"),
+        key("status.commit", "Commit (Tab: allow an empty commit)"),
+        key("status.commit_and_push", "Commit and push"),
+        key("status.quick_commit", "Quick commit (one-line message on the status line)"),
+        key("status.commit_all", "Stage everything and commit (one-line message)"),
+        key("status.amend", "Amend last commit (Tab: include unstaged)"),
+        key("status.discard", "Discard changes in file"),
+        key("status.discard_all", "Discard all changes (type \"yes\")"),
+        key("status.stash", "Stash changes"),
+        key("status.stage_hunks", "Interactive staging (hunk by hunk)"),
+        key("status.stage_lines", "Stage selected lines (J/K extend)"),
+        key("status.stash_hunks", "Stash hunk by hunk (like stash -p)"),
+        key("status.reveal", "Reveal file in file manager"),
+        key("status.copy_path", "Copy file path"),
+        key("status.rename", "Rename/move file (git mv)"),
+        key("status.update_submodules", "Update submodule (all if none selected)"),
+        Line::from("  Enter      Show / Hide diff (expand untracked dir)"),
+        Line::from("  h/l        Previous / next file (diff open)"),
+        key("status.jump_section", "Jump between staged / unstaged"),
+        key("status.fold_section", "Collapse/expand the section (also Enter on its header)"),
+        key("status.ignore_whitespace", "Hide whitespace changes in diffs on/off"),
+        key("status.copy_hunk", "Copy the hunk at the top of the diff (diff open)"),
+        key("status.next_conflict", "Next conflict in a conflicted file (diff open)"),
+        key("status.previous_conflict", "Previous conflict in a conflicted file (diff open)"),
         Line::from(""),
         Line::from(Span::styled("Log Panel", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
         Line::from("  Enter      Show / Hide diff"),
-        Line::from("  t          Tree view"),
-        Line::from("  /          Search commits"),
-        Line::from("  y          Copy commit hash"),
-        Line::from("  c          Checkout commit"),
-        Line::from("  b          Create branch from commit"),
-        Line::from("  p          Cherry-pick commit"),
-        Line::from("  r          Revert commit"),
-        Line::from("  f          Fetch from remote"),
-        Line::from("  P          Push to remote"),
-        Line::from("  U          Pull from remote"),
+        key("log.tree_view", "Tree view (y: copy file path)"),
+        key("log.browse", "Browse every file of the commit (Enter opens, Backspace goes up)"),
+        key("log.search", "Search commits (↑/↓ recalls recent searches)"),
+        key("log.search", "Filter changed files by glob or text (diff open)"),
+        key("log.goto", "Go to commit by hash or ref"),
+        key("log.copy_hash", "Copy commit hash"),
+        key("log.copy_summary", "Copy commit summary (hash + subject)"),
+        key("log.diff_worktree", "Diff working tree against commit"),
+        key("log.first_parent", "First-parent history of HEAD on/off"),
+        key("log.group_by_day", "Group commits by day on/off"),
+        key("log.merge_filter", "Cycle all / merges only / no merges"),
+        key("log.mark_range", "Mark a range (y copies its hashes)"),
+        key("log.checkout", "Checkout commit"),
+        key("log.branch", "Create branch from commit"),
+        key("log.cherry_pick", "Cherry-pick commit"),
+        key("log.revert", "Revert commit"),
+        key("log.reword", "Reword commit message"),
+        key("log.amend", "Amend HEAD (reword for older commits)"),
+        key("log.amend_author", "Amend HEAD author/date"),
+        key("log.next_parent", "Merge diff: next parent, then combined (diff open)"),
+        key("log.fullscreen", "Full-screen diff on/off (diff open)"),
+        key("log.pin_diff", "Pin the diff: j/k show each commit's diff"),
+        key("log.ignore_whitespace", "Hide whitespace changes in diffs on/off (also in tree view)"),
+        key("log.copy_hunk", "Copy the hunk at the top of the diff (also in tree view)"),
+        Line::from(format!(
+            "  {:<10} Go to parent / child commit",
+            format!("{} / {}", keys.hint("log.parent"), keys.hint("log.child"))
+        )),
+        key("log.fetch", "Fetch from remote"),
+        key("log.fetch_prune", "Fetch with prune (pick remote)"),
+        key("log.push", "Push (pick the remote when there are several)"),
+        key("log.outgoing", "Commits a push would send (vs upstream)"),
+        key("log.incoming", "Fetch, then show what a pull would bring"),
+        key("log.pull", "Pull from remote"),
+        key("log.remotes", "Remotes: fetch, edit URLs"),
+        key("log.bisect", "Start / Reset bisect"),
+        Line::from("  g/b/s      Mark good / bad / skip (bisecting)"),
         Line::from(""),
         Line::from(Span::styled("Stash Panel", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
-        Line::from("  a          Apply stash"),
-        Line::from("  p          Pop stash"),
-        Line::from("  d          Drop stash"),
+        Line::from("  Enter      Show / Hide diff (←/→: switch file)"),
+        key("stash.apply", "Apply stash"),
+        key("stash.pop", "Pop stash"),
+        key("stash.restore_index", "Apply/pop restage what was staged (--index) on/off"),
+        key("stash.drop", "Drop stash"),
+        key("stash.apply_on_branch", "Switch branch, then apply/pop stash"),
+        key("stash.rename", "Re-stash with a new message (moves it to stash@{0})"),
         Line::from(""),
         Line::from(Span::styled("Branches Panel", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
         Line::from("  Enter      Switch to branch"),
-        Line::from("  d          Delete branch"),
-        Line::from("  n          Create new branch"),
-        Line::from("  m          Merge branch into current"),
+        key("branches.delete", "Delete branch"),
+        key("branches.new", "Create new branch"),
+        key("branches.merge", "Merge branch into current (preview first)"),
+        key("branches.rebase", "Rebase current onto branch (preview first)"),
+        key("branches.reflog", "Branch reflog (Enter: reset branch to entry)"),
+        key("branches.compare", "Compare with the upstream or another remote's copy"),
+        key("branches.copy_name", "Copy branch name"),
+        key("branches.switch_previous", "Switch back to the previous checkout (git checkout -)"),
+        Line::from(""),
+        Line::from(Span::styled("Tags Panel", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
+        Line::from(format!("  {:<10} Checkout tag (detached HEAD)", format!("Enter/{}", keys.hint("tags.checkout")))),
+        key("tags.delete", "Delete tag (asks first)"),
+        key("tags.new", "Tag HEAD, Tab to sign (then offer to push)"),
+        key("tags.push", "Push tag to remote"),
+        key("tags.push_all", "Push all tags"),
         Line::from(""),
-        Line::from(Span::styled("  Press ? or Esc to close", Style::default().fg(Color::DarkGray))),
     ];
+    if !keys.macros().is_empty() {
+        help_text.push(Line::from(Span::styled("Macros", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))));
+        for mac in keys.macros() {
+            help_text.push(Line::from(format!("  {:<10} {}: {}", mac.key, mac.name, mac.actions.join(", "))));
+        }
+        help_text.push(Line::from(""));
+    }
+    help_text.push(Line::from(Span::styled("  Press ? or Esc to close", Style::default().fg(Color::DarkGray))));
 
     let paragraph = Paragraph::new(help_text)
         .block(